@@ -1,5 +1,7 @@
 use crate::engine::{EngineControl, TestEngine};
-use crate::model::{RunConfig, TestEvent};
+use crate::model::{
+    BufferbloatGrade, BufferbloatThresholds, HttpVersion, RunConfig, TestEvent, TracerouteMode,
+};
 use anyhow::{Context, Result};
 use clap::Parser;
 use rand::RngCore;
@@ -45,6 +47,36 @@ pub struct Cli {
     #[arg(long, default_value_t = 6)]
     pub concurrency: usize,
 
+    /// Ramp streams and request size up from a small start until throughput
+    /// plateaus (overrides --concurrency and the bytes-per-request options)
+    #[arg(long)]
+    pub adaptive: bool,
+
+    /// After the multi-stream phases, measure download and upload again over one connection
+    #[arg(long)]
+    pub single_stream: bool,
+
+    /// After the upload phase, saturate download and upload simultaneously
+    #[arg(long)]
+    pub bidirectional: bool,
+
+    /// TCP congestion-control algorithm for test connections, e.g. bbr or cubic (Linux only)
+    #[arg(long, value_name = "NAME")]
+    pub tcp_congestion: Option<String>,
+
+    /// SO_RCVBUF for test connections in bytes (Linux only; disables receive autotuning)
+    #[arg(long, value_name = "BYTES")]
+    pub tcp_rcvbuf: Option<usize>,
+
+    /// SO_SNDBUF for test connections in bytes (Linux only)
+    #[arg(long, value_name = "BYTES")]
+    pub tcp_sndbuf: Option<usize>,
+
+    /// HTTP version for download, upload and latency requests. auto never
+    /// upgrades to HTTP/3; 3 (QUIC over UDP/443) needs the http3 build feature
+    #[arg(long, value_enum, default_value_t = HttpVersion::Auto)]
+    pub http_version: HttpVersion,
+
     /// Bytes per download request
     #[arg(long, default_value_t = 10_000_000)]
     pub download_bytes_per_req: u64,
@@ -61,6 +93,11 @@ pub struct Cli {
     #[arg(long, default_value_t = 800)]
     pub probe_timeout_ms: u64,
 
+    /// Latency increase under load (ms) marking the upper bounds of bufferbloat
+    /// grades A+, A, B, C and D; anything above is an F
+    #[arg(long, value_name = "MS,MS,MS,MS,MS", value_parser = parse_bufferbloat_thresholds)]
+    pub bufferbloat_thresholds: Option<BufferbloatThresholds>,
+
     /// Reserved for future experimental features
     #[arg(long)]
     pub experimental: bool,
@@ -109,15 +146,84 @@ pub struct Cli {
     #[arg(long)]
     pub traceroute: bool,
 
+    /// Also time well-known public DNS resolvers (1.1.1.1, 8.8.8.8, 9.9.9.9) next to the system ones
+    #[arg(long)]
+    pub dns_public_resolvers: bool,
+
+    /// Time DNS-over-HTTPS lookups against this endpoint (bare flag: Cloudflare's)
+    #[arg(
+        long,
+        value_name = "URL",
+        num_args = 0..=1,
+        default_missing_value = "https://cloudflare-dns.com/dns-query"
+    )]
+    pub doh: Option<String>,
+
+    /// Time DNS-over-TLS lookups against HOST[:PORT] (bare flag: Cloudflare's)
+    #[arg(
+        long,
+        value_name = "HOST[:PORT]",
+        num_args = 0..=1,
+        default_missing_value = "one.one.one.one"
+    )]
+    pub dot: Option<String>,
+
+    /// Discover the path MTU toward Cloudflare edge (DF-set ICMP probes, else TCP MSS)
+    #[arg(long)]
+    pub measure_pmtu: bool,
+
+    /// Break fresh HTTPS requests down into DNS, TCP, TLS, TTFB and transfer time
+    #[arg(long)]
+    pub connection_timing: bool,
+
+    /// Number of fresh connections sampled for --connection-timing
+    #[arg(long, default_value_t = 5)]
+    pub connection_timing_samples: u32,
+
+    /// Also probe idle latency over a new connection per sample and over one warm connection
+    #[arg(long)]
+    pub connection_probes: bool,
+
+    /// Samples per series for --connection-probes
+    #[arg(long, default_value_t = 20)]
+    pub connection_probe_samples: u32,
+
+    /// While downloading and uploading, also open new connections to time
+    /// TCP, TLS and HTTP round trips for the full responsiveness (RPM) figure
+    #[arg(long)]
+    pub responsiveness: bool,
+
+    /// Fetch TURN credentials from the speed-test service and also measure
+    /// UDP loss and RTT relayed through a TURN allocation
+    #[arg(long)]
+    pub turn_relay: bool,
+
+    /// Detect the NAT type (mapping, filtering, hairpinning) with extra STUN
+    /// requests, some of them to the server's alternate address
+    #[arg(long)]
+    pub nat: bool,
+
     /// Maximum number of hops for traceroute
     #[arg(long, default_value_t = 30)]
     pub traceroute_max_hops: u8,
 
-    /// Force IPv4 only (no IPv6)
+    /// Traceroute probe type
+    #[arg(long, value_enum, default_value_t = TracerouteMode::Icmp)]
+    pub traceroute_mode: TracerouteMode,
+
+    /// Keep probing every hop (mtr-style) for the whole test and report per-hop loss and RTT
+    #[arg(long)]
+    pub path_monitor: bool,
+
+    /// Offline IP-to-ASN table (iptoasn.com TSV) used to label traceroute hops with their AS
     #[arg(long)]
+    pub asn_table: Option<std::path::PathBuf>,
+
+    /// Force IPv4 only (no IPv6) for every phase of the test
+    #[arg(long, conflicts_with = "ipv6_only")]
     pub ipv4_only: bool,
 
-    /// Force IPv6 only (no IPv4)
+    /// Force IPv6 only (no IPv4) for every phase of the test
     #[arg(long)]
     pub ipv6_only: bool,
 
@@ -128,6 +234,24 @@ pub struct Cli {
     /// Number of UDP packets to send for packet loss measurement
     #[arg(long, default_value_t = 50)]
     pub udp_packets: u64,
+
+    /// UDP packet-loss probe send rate in packets per second (50 matches 20 ms voice frames)
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..=10_000))]
+    pub udp_pps: u32,
+
+    /// UDP packet-loss probe request size in bytes, padded with a STUN SOFTWARE
+    /// attribute (at most 787, the largest value RFC 5389 allows it)
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u16).range(20..=787))]
+    pub udp_packet_size: u16,
+
+    /// Also run the UDP packet-loss probe during the download and upload phases
+    #[arg(long)]
+    pub udp_under_load: bool,
+
+    /// STUN server for the UDP packet-loss probe, as host[:port] or a stun:/turn: URL.
+    /// Repeat to probe several servers one after another and compare them
+    #[arg(long = "stun-server", value_name = "SERVER", value_parser = parse_stun_server)]
+    pub stun_servers: Vec<String>,
 }
 
 pub async fn run(args: Cli) -> Result<()> {
@@ -177,6 +301,47 @@ fn gen_meas_id() -> String {
     u64::from_le_bytes(b).to_string()
 }
 
+/// Parse five ascending, comma-separated millisecond bounds.
+fn parse_bufferbloat_thresholds(s: &str) -> Result<BufferbloatThresholds, String> {
+    let values = s
+        .split(',')
+        .map(|v| {
+            v.trim()
+                .parse::<f64>()
+                .map_err(|e| format!("'{}': {}", v, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let bounds: [f64; 5] = values
+        .try_into()
+        .map_err(|_| "expected five values, for grades A+, A, B, C and D".to_string())?;
+    if bounds[0] <= 0.0 || bounds.windows(2).any(|w| w[1] <= w[0]) {
+        return Err("values must be positive and strictly increasing".into());
+    }
+    Ok(BufferbloatThresholds(bounds))
+}
+
+/// Accept `host[:port]` or a full `stun:`/`turn:` URL, returning the URL.
+/// IPv6 addresses need brackets, as in `[2001:db8::1]:3478`.
+fn parse_stun_server(s: &str) -> Result<String, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty server".into());
+    }
+    let url = if s.starts_with("stun:") || s.starts_with("turn:") {
+        s.to_string()
+    } else {
+        format!("stun:{}", s)
+    };
+    let host_port = url[5..].split('?').next().unwrap_or("");
+    if !host_port.starts_with('[') && host_port.matches(':').count() > 1 {
+        return Err(format!(
+            "IPv6 addresses need brackets, e.g. [{}]:3478",
+            host_port
+        ));
+    }
+    Ok(url)
+}
+
 /// Build a `RunConfig` from CLI arguments.
 pub fn build_config(args: &Cli) -> RunConfig {
     // DNS and TLS run by default unless --skip-diagnostics is set
@@ -188,6 +353,14 @@ pub fn build_config(args: &Cli) -> RunConfig {
         download_bytes_per_req: args.download_bytes_per_req,
         upload_bytes_per_req: args.upload_bytes_per_req,
         concurrency: args.concurrency,
+        adaptive: args.adaptive,
+        single_stream: args.single_stream,
+        bidirectional: args.bidirectional,
+        tcp_congestion: args.tcp_congestion.clone(),
+        tcp_rcvbuf: args.tcp_rcvbuf,
+        tcp_sndbuf: args.tcp_sndbuf,
+        http_version: args.http_version,
+        bufferbloat_thresholds: args.bufferbloat_thresholds.unwrap_or_default(),
         idle_latency_duration: Duration::from(args.idle_latency_duration),
         download_duration: Duration::from(args.download_duration),
         upload_duration: Duration::from(args.upload_duration),
@@ -202,12 +375,30 @@ pub fn build_config(args: &Cli) -> RunConfig {
         // Diagnostic options: DNS and TLS run by default unless --skip-diagnostics
         measure_dns: !skip,
         measure_tls: !skip,
+        measure_nat: args.nat,
+        dns_public_resolvers: args.dns_public_resolvers,
+        doh_url: args.doh.clone(),
+        dot_server: args.dot.clone(),
+        measure_pmtu: args.measure_pmtu,
+        measure_connection_timing: args.connection_timing,
+        connection_timing_samples: args.connection_timing_samples,
+        connection_probes: args.connection_probes,
+        connection_probe_samples: args.connection_probe_samples,
+        measure_responsiveness: args.responsiveness,
+        measure_turn_relay: args.turn_relay,
         compare_ip_versions: args.compare_ip_versions,
         traceroute: args.traceroute,
         traceroute_max_hops: args.traceroute_max_hops,
+        traceroute_mode: args.traceroute_mode,
+        path_monitor: args.path_monitor,
+        asn_table: args.asn_table.clone(),
         ipv4_only: args.ipv4_only,
         ipv6_only: args.ipv6_only,
         udp_packets: args.udp_packets,
+        udp_pps: args.udp_pps,
+        udp_packet_size: args.udp_packet_size as usize,
+        udp_under_load: args.udp_under_load,
+        stun_servers: args.stun_servers.clone(),
     }
 }
 
@@ -317,6 +508,16 @@ async fn run_text(args: Cli) -> Result<()> {
                     }
                 }
             }
+            TestEvent::BidirectionalTick {
+                download_bps,
+                upload_bps,
+            } => {
+                eprintln!(
+                    "Bidirectional: download {:.2} Mbps, upload {:.2} Mbps",
+                    (download_bps * 8.0) / 1_000_000.0,
+                    (upload_bps * 8.0) / 1_000_000.0
+                );
+            }
             TestEvent::LatencySample {
                 phase,
                 ok,
@@ -370,6 +571,80 @@ async fn run_text(args: Cli) -> Result<()> {
             // Diagnostic events
             TestEvent::DiagnosticDns { summary } => {
                 eprintln!("DNS: {:.2}ms", summary.resolution_time_ms);
+                let timing = |t: &crate::model::DnsRecordTiming| match (t.first_ms, &t.error) {
+                    (Some(first), _) => format!(
+                        "{:.1}/{}ms",
+                        first,
+                        t.repeat_ms
+                            .map(|w| format!("{:.1}", w))
+                            .unwrap_or_else(|| "-".into())
+                    ),
+                    (None, Some(e)) => e.clone(),
+                    (None, None) => "-".into(),
+                };
+                for s in &summary.per_server {
+                    eprintln!(
+                        "  {:<39} A {:<16} AAAA {:<16}{}",
+                        s.server,
+                        timing(&s.a),
+                        timing(&s.aaaa),
+                        if s.public { " (public)" } else { "" }
+                    );
+                }
+                for e in &summary.encrypted {
+                    match (e.first_ms, &e.error) {
+                        (Some(first), _) => eprintln!(
+                            "{} {}: {:.2}ms first, {} reused",
+                            e.protocol.label(),
+                            e.target,
+                            first,
+                            e.reused_ms
+                                .map(|r| format!("{:.2}ms", r))
+                                .unwrap_or_else(|| "-".into())
+                        ),
+                        (None, err) => eprintln!(
+                            "{} {}: failed ({})",
+                            e.protocol.label(),
+                            e.target,
+                            err.as_deref().unwrap_or("no response")
+                        ),
+                    }
+                }
+            }
+            TestEvent::DiagnosticPmtu { summary } => {
+                eprintln!(
+                    "PMTU {}, MSS {} ({})",
+                    summary.pmtu,
+                    summary.mss,
+                    summary.method.label()
+                );
+                if summary.black_hole_suspected {
+                    eprintln!(
+                        "Warning: packets above {} bytes are dropped without ICMP feedback (possible MTU black hole)",
+                        summary.pmtu
+                    );
+                }
+            }
+            TestEvent::DiagnosticConnectionTiming { summary } => {
+                let stages = summary
+                    .median
+                    .stages()
+                    .iter()
+                    .map(|(label, ms)| format!("{} {:.1}", label, ms))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let failed = if summary.failed > 0 {
+                    format!(", {} failed", summary.failed)
+                } else {
+                    String::new()
+                };
+                eprintln!(
+                    "Connection (median of {}{}): {} = {:.1}ms",
+                    summary.samples.len(),
+                    failed,
+                    stages,
+                    summary.median.total_ms()
+                );
             }
             TestEvent::DiagnosticTls { summary } => {
                 eprintln!(
@@ -378,6 +653,43 @@ async fn run_text(args: Cli) -> Result<()> {
                     summary.protocol_version.as_deref().unwrap_or("-"),
                     summary.cipher_suite.as_deref().unwrap_or("-")
                 );
+                eprintln!(
+                    "TLS: ALPN {}, key exchange {}",
+                    summary.alpn.as_deref().unwrap_or("-"),
+                    summary.key_exchange_group.as_deref().unwrap_or("-")
+                );
+                if let Some(leaf) = summary.certificates.first() {
+                    eprintln!("TLS: certificate {} (issuer {})", leaf.subject, leaf.issuer);
+                    eprintln!("TLS: SHA-256 {}", leaf.sha256);
+                }
+                if summary.custom_root {
+                    eprintln!(
+                        "Warning: certificate chain is only trusted via --certificate (TLS inspection?)"
+                    );
+                }
+                if let Some(resumed_ms) = summary.resumed_handshake_time_ms {
+                    eprintln!(
+                        "TLS: resumed handshake {:.2}ms ({})",
+                        resumed_ms,
+                        if summary.resumed {
+                            "session resumed"
+                        } else {
+                            "full handshake"
+                        }
+                    );
+                }
+                match (summary.zero_rtt_first_byte_ms, summary.early_data_accepted) {
+                    (Some(zero_rtt_ms), Some(accepted)) => eprintln!(
+                        "TLS: 0-RTT first byte {:.2}ms vs {:.2}ms resumed (early data {})",
+                        zero_rtt_ms,
+                        summary.resumed_first_byte_ms.unwrap_or(f64::NAN),
+                        if accepted { "accepted" } else { "rejected" }
+                    ),
+                    _ if summary.resumed_handshake_time_ms.is_some() => {
+                        eprintln!("TLS: 0-RTT not offered by server")
+                    }
+                    _ => {}
+                }
             }
             TestEvent::DiagnosticIpComparison { comparison } => {
                 if let Some(ref v4) = comparison.ipv4_result {
@@ -413,7 +725,8 @@ async fn run_text(args: Cli) -> Result<()> {
             }
             TestEvent::TracerouteComplete { summary } => {
                 eprintln!(
-                    "Traceroute to {} {} ({} hops)",
+                    "Traceroute ({}) to {} {} ({} hops)",
+                    summary.mode.label(),
                     summary.destination,
                     if summary.completed {
                         "completed"
@@ -422,12 +735,36 @@ async fn run_text(args: Cli) -> Result<()> {
                     },
                     summary.hops.len()
                 );
+                // Names and ASNs are resolved after the last hop, so list them now
+                for hop in &summary.hops {
+                    if hop.hostname.is_none() && hop.asn.is_none() {
+                        continue;
+                    }
+                    let mut line = format!(
+                        "{:>2}  {}",
+                        hop.hop_number,
+                        hop.ip_address.as_deref().unwrap_or("*")
+                    );
+                    if let Some(ref name) = hop.hostname {
+                        line.push_str(&format!(" ({})", name));
+                    }
+                    if let Some(asn) = hop.asn {
+                        line.push_str(&format!(" AS{}", asn));
+                    }
+                    eprintln!("{}", line);
+                }
+                let as_path = summary.as_path();
+                if !as_path.is_empty() {
+                    eprintln!("AS path: {}", as_path.join(" > "));
+                }
             }
             TestEvent::ExternalIps { ipv4, ipv6 } => {
                 let v4 = ipv4.as_deref().unwrap_or("-");
                 let v6 = ipv6.as_deref().unwrap_or("-");
                 eprintln!("External IPs: v4={} v6={}", v4, v6);
             }
+            // Printed as a table once the run completes
+            TestEvent::PathMonitorUpdate { .. } => {}
         }
     }
 
@@ -449,6 +786,14 @@ async fn run_text(args: Cli) -> Result<()> {
     if let Some(server) = enriched.server.as_deref() {
         println!("Server: {server}");
     }
+    if let Some(protocol) = enriched.http_protocol.as_deref() {
+        match enriched.upload.http_protocol.as_deref() {
+            Some(upload) if upload != protocol => {
+                println!("Protocol: {protocol} (upload {upload})")
+            }
+            _ => println!("Protocol: {protocol}"),
+        }
+    }
     if let Some(comments) = enriched.comments.as_deref() {
         if !comments.trim().is_empty() {
             println!("Comments: {}", comments);
@@ -471,6 +816,87 @@ async fn run_text(args: Cli) -> Result<()> {
         "Upload:   avg {:.2} med {:.2} p25 {:.2} p75 {:.2}",
         ul_mean, ul_median, ul_p25, ul_p75
     );
+    for (label, single, multi) in [
+        (
+            "download",
+            &enriched.single_stream_download,
+            &enriched.download,
+        ),
+        ("upload", &enriched.single_stream_upload, &enriched.upload),
+    ] {
+        if let Some(single) = single {
+            println!(
+                "Single-stream {}: {:.2} Mbps ({:.0}% of multi-stream)",
+                label,
+                single.mbps,
+                single.percent_of(multi).unwrap_or(f64::NAN)
+            );
+        }
+    }
+    if let Some(ref bidir) = enriched.bidirectional {
+        println!(
+            "Bidirectional: download {:.2} Mbps ({:.0}% of download-only), upload {:.2} Mbps ({:.0}% of upload-only)",
+            bidir.download.mbps,
+            bidir.download.percent_of(&enriched.download).unwrap_or(f64::NAN),
+            bidir.upload.mbps,
+            bidir.upload.percent_of(&enriched.upload).unwrap_or(f64::NAN)
+        );
+    }
+    if let Some(ref acc) = enriched.upload.accounting {
+        let kernel = match (acc.kernel_acked_mbps(), acc.difference_pct()) {
+            (Some(mbps), Some(diff)) => format!(", kernel-acked {:.2} ({:+.1}%)", mbps, diff),
+            (Some(mbps), None) => format!(", kernel-acked {:.2}", mbps),
+            _ => String::new(),
+        };
+        println!(
+            "Upload accounting: sent {:.2}, confirmed {:.2}{} Mbps",
+            acc.sent_mbps(),
+            acc.confirmed_mbps(),
+            kernel
+        );
+    }
+    if let Some(ref socket) = enriched.tcp_socket {
+        println!("TCP socket options: {}", socket.describe());
+    }
+    if let Some(ref nat) = enriched.nat {
+        println!("NAT: {}", nat.describe());
+    }
+    if let Some(ref tcp) = enriched.tcp_telemetry {
+        for (label, phase) in [("download", &tcp.download), ("upload", &tcp.upload)] {
+            let Some(phase) = phase else { continue };
+            let mut line = format!(
+                "TCP ({}): {}, {} conns, srtt {:.1} ms, cwnd {:.0}, {} retrans",
+                label,
+                phase.congestion_control.as_deref().unwrap_or("-"),
+                phase.connections,
+                phase.median_srtt_ms().unwrap_or(f64::NAN),
+                phase.median_cwnd().unwrap_or(f64::NAN),
+                phase.retransmits
+            );
+            if let Some(pct) = phase.retransmit_pct {
+                line.push_str(&format!(" ({:.2}%)", pct));
+            }
+            if let (Some(rwnd), Some(sndbuf)) = (phase.rwnd_limited_pct, phase.sndbuf_limited_pct) {
+                line.push_str(&format!(
+                    ", rwnd-limited {:.0}%, sndbuf-limited {:.0}%",
+                    rwnd, sndbuf
+                ));
+            }
+            if let Some(limit) = phase.limit {
+                line.push_str(&format!(" -> limited by {}", limit.label()));
+            }
+            println!("{}", line);
+        }
+    }
+    if args.adaptive {
+        println!(
+            "Adaptive: download {} streams x {} bytes, upload {} streams x {} bytes",
+            enriched.download.streams,
+            enriched.download.bytes_per_req,
+            enriched.upload.streams,
+            enriched.upload.bytes_per_req
+        );
+    }
 
     // Compute and display latency metrics (mean, median, p25, p75)
     let (idle_mean, idle_median, idle_p25, idle_p75) =
@@ -485,6 +911,22 @@ async fn run_text(args: Cli) -> Result<()> {
         enriched.idle_latency.loss * 100.0,
         enriched.idle_latency.jitter_ms.unwrap_or(f64::NAN)
     );
+    for (label, lat) in [
+        ("fresh connection", &enriched.fresh_connection_latency),
+        ("warm connection", &enriched.warm_connection_latency),
+    ] {
+        if let Some(lat) = lat {
+            println!(
+                "Idle latency ({}): avg {:.1} med {:.1} p25 {:.1} p75 {:.1} ms (loss {:.1}%)",
+                label,
+                lat.mean_ms.unwrap_or(f64::NAN),
+                lat.median_ms.unwrap_or(f64::NAN),
+                lat.p25_ms.unwrap_or(f64::NAN),
+                lat.p75_ms.unwrap_or(f64::NAN),
+                lat.loss * 100.0
+            );
+        }
+    }
 
     let (dl_lat_mean, dl_lat_median, dl_lat_p25, dl_lat_p75) =
         crate::metrics::compute_metrics(&loaded_dl_latency_samples)
@@ -511,6 +953,46 @@ async fn run_text(args: Cli) -> Result<()> {
         enriched.loaded_latency_upload.loss * 100.0,
         enriched.loaded_latency_upload.jitter_ms.unwrap_or(f64::NAN)
     );
+    if let Some(ref bidir) = enriched.bidirectional {
+        let lat = &bidir.loaded_latency;
+        println!(
+            "Loaded latency (bidirectional): avg {:.1} med {:.1} p25 {:.1} p75 {:.1} ms (loss {:.1}%, jitter {:.1} ms)",
+            lat.mean_ms.unwrap_or(f64::NAN),
+            lat.median_ms.unwrap_or(f64::NAN),
+            lat.p25_ms.unwrap_or(f64::NAN),
+            lat.p75_ms.unwrap_or(f64::NAN),
+            lat.loss * 100.0,
+            lat.jitter_ms.unwrap_or(f64::NAN)
+        );
+    }
+    if let Some(ref bb) = enriched.bufferbloat {
+        let direction = |ms: Option<f64>, grade: Option<BufferbloatGrade>| match (ms, grade) {
+            (Some(ms), Some(grade)) => format!("+{:.1} ms ({})", ms, grade.label()),
+            _ => "-".to_string(),
+        };
+        println!(
+            "Bufferbloat: grade {} (download {}, upload {})",
+            bb.grade.label(),
+            direction(bb.download_increase_ms, bb.download_grade),
+            direction(bb.upload_increase_ms, bb.upload_grade)
+        );
+    }
+    if let Some(ref resp) = enriched.responsiveness {
+        let fmt = |v: Option<f64>| v.map(|v| format!("{:.0}", v)).unwrap_or_else(|| "-".into());
+        for (label, rpm) in [("download", &resp.download), ("upload", &resp.upload)] {
+            if let Some(rpm) = rpm {
+                println!(
+                    "Responsiveness ({}): {} RPM (fresh {}, in-flight {:.0}; {} fresh / {} in-flight probes)",
+                    label,
+                    fmt(rpm.rpm),
+                    fmt(rpm.fresh_rpm),
+                    rpm.in_flight_rpm,
+                    rpm.fresh_probes,
+                    rpm.in_flight_probes
+                );
+            }
+        }
+    }
     if let Some(ref exp) = enriched.experimental_udp {
         let mos_str = exp.mos.map(|m| format!("MOS {:.1}", m)).unwrap_or_else(|| "N/A".to_string());
         let jitter_str = exp.latency.jitter_ms.map(|j| format!("{:.1}ms", j)).unwrap_or_else(|| "-".to_string());
@@ -523,6 +1005,87 @@ async fn run_text(args: Cli) -> Result<()> {
             exp.out_of_order_pct,
             exp.latency.median_ms.unwrap_or(f64::NAN)
         );
+        if let (Some(pps), Some(size)) = (exp.pps, exp.packet_size) {
+            println!(
+                "UDP probe: {} packets at {} pps, {} bytes each",
+                exp.latency.sent, pps, size
+            );
+        }
+    }
+    if enriched.stun_results.len() > 1 {
+        println!("UDP by STUN server:");
+        for result in &enriched.stun_results {
+            let Some(ref exp) = result.summary else {
+                println!(
+                    "  {}: failed ({})",
+                    result.target,
+                    result.error.as_deref().unwrap_or("no result")
+                );
+                continue;
+            };
+            let jitter_str = exp
+                .latency
+                .jitter_ms
+                .map(|j| format!("{:.1}ms", j))
+                .unwrap_or_else(|| "-".to_string());
+            println!(
+                "  {}: {} | loss {:.1}% jitter {} rtt {}ms",
+                result.target,
+                exp.quality_label,
+                exp.latency.loss * 100.0,
+                jitter_str,
+                exp.latency.median_ms.unwrap_or(f64::NAN)
+            );
+        }
+    }
+    if let Some(ref relay) = enriched.turn_relay {
+        println!(
+            "UDP via TURN relay: {} | loss {:.1}% rtt {}ms ({})",
+            relay.quality_label,
+            relay.latency.loss * 100.0,
+            relay.latency.median_ms.unwrap_or(f64::NAN),
+            relay.target.as_deref().unwrap_or("-")
+        );
+    }
+    if let Some(ref loaded) = enriched.loaded_udp {
+        for (label, exp) in [("download", &loaded.download), ("upload", &loaded.upload)] {
+            if let Some(exp) = exp {
+                let jitter_str = exp
+                    .latency
+                    .jitter_ms
+                    .map(|j| format!("{:.1}ms", j))
+                    .unwrap_or_else(|| "-".to_string());
+                println!(
+                    "UDP under {} load: {} | loss {:.1}% jitter {} rtt {}ms",
+                    label,
+                    exp.quality_label,
+                    exp.latency.loss * 100.0,
+                    jitter_str,
+                    exp.latency.median_ms.unwrap_or(f64::NAN)
+                );
+            }
+        }
+    }
+    if let Some(ref pm) = enriched.path_monitor {
+        let ms = |v: Option<f64>| v.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "-".into());
+        println!("Path to {} ({} rounds):", pm.destination, pm.rounds);
+        println!(
+            "{:>3}  {:<39} {:>6} {:>5} {:>7} {:>7} {:>7} {:>7}",
+            "Hop", "Address", "Loss%", "Snt", "Best", "Avg", "Worst", "StDev"
+        );
+        for hop in &pm.hops {
+            println!(
+                "{:>3}  {:<39} {:>6.1} {:>5} {:>7} {:>7} {:>7} {:>7}",
+                hop.hop_number,
+                hop.ip_address.as_deref().unwrap_or("???"),
+                hop.loss * 100.0,
+                hop.sent,
+                ms(hop.best_ms),
+                ms(hop.avg_ms),
+                ms(hop.worst_ms),
+                ms(hop.stddev_ms)
+            );
+        }
     }
     if args.auto_save {
         if let Ok(p) = crate::storage::save_run(&enriched) {
@@ -0,0 +1,111 @@
+//! Offline IP-to-ASN lookup
+//!
+//! Loads a local range table in the iptoasn.com TSV layout
+//! (`range_start  range_end  AS_number  country_code  AS_description`), which
+//! covers IPv4 and IPv6 in a single file, and annotates traceroute hops with
+//! the owning AS so a path reads as networks rather than bare addresses.
+
+use crate::model::TracerouteHop;
+use anyhow::{Context, Result};
+use std::net::IpAddr;
+use std::path::Path;
+
+/// One contiguous address range announced by an AS.
+#[derive(Debug, Clone)]
+struct AsnRange {
+    start: IpAddr,
+    end: IpAddr,
+    asn: u32,
+    org: String,
+}
+
+/// Sorted, non-overlapping address ranges for binary search.
+#[derive(Debug, Default)]
+pub struct AsnTable {
+    ranges: Vec<AsnRange>,
+}
+
+impl AsnTable {
+    /// Read a table from disk.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ASN table {}", path.display()))?;
+        let table = Self::parse(&content);
+        anyhow::ensure!(
+            !table.ranges.is_empty(),
+            "ASN table {} has no usable entries",
+            path.display()
+        );
+        Ok(table)
+    }
+
+    /// Parse TSV content, skipping malformed lines and unrouted (AS0) ranges.
+    fn parse(content: &str) -> Self {
+        let mut ranges: Vec<AsnRange> = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let start: IpAddr = fields.next()?.trim().parse().ok()?;
+                let end: IpAddr = fields.next()?.trim().parse().ok()?;
+                let asn: u32 = fields.next()?.trim().parse().ok()?;
+                let _country = fields.next();
+                let org = fields.next().unwrap_or("").trim().to_string();
+                (asn != 0 && start.is_ipv4() == end.is_ipv4() && start <= end).then_some(AsnRange {
+                    start,
+                    end,
+                    asn,
+                    org,
+                })
+            })
+            .collect();
+        ranges.sort_by_key(|r| r.start);
+        Self { ranges }
+    }
+
+    /// Find the AS number and organization owning `ip`.
+    pub fn lookup(&self, ip: &IpAddr) -> Option<(u32, &str)> {
+        // Last range starting at or before `ip`
+        let idx = self.ranges.partition_point(|r| r.start <= *ip);
+        let range = self.ranges.get(idx.checked_sub(1)?)?;
+        (*ip <= range.end).then_some((range.asn, range.org.as_str()))
+    }
+
+    /// Fill in `asn`/`as_org` for every hop with a known address.
+    pub fn annotate(&self, hops: &mut [TracerouteHop]) {
+        for hop in hops {
+            let Some(ip) = hop.ip_address.as_deref().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            if let Some((asn, org)) = self.lookup(&ip) {
+                hop.asn = Some(asn);
+                hop.as_org = (!org.is_empty()).then(|| org.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asn_table_lookup() {
+        let table = AsnTable::parse(
+            "1.0.0.0\t1.0.0.255\t13335\tUS\tCLOUDFLARENET\n\
+             1.0.1.0\t1.0.3.255\t0\tNone\tNot routed\n\
+             not an ip\t1.0.4.0\t1\tUS\tBROKEN\n\
+             4.0.0.0\t4.255.255.255\t3356\tUS\tLEVEL3\n\
+             2606:4700::\t2606:4700:ffff:ffff:ffff:ffff:ffff:ffff\t13335\tUS\tCLOUDFLARENET\n",
+        );
+
+        let lookup = |s: &str| table.lookup(&s.parse().unwrap());
+        assert_eq!(lookup("1.0.0.1"), Some((13335, "CLOUDFLARENET")));
+        assert_eq!(lookup("4.2.2.2"), Some((3356, "LEVEL3")));
+        assert_eq!(lookup("2606:4700::1111"), Some((13335, "CLOUDFLARENET")));
+        // Unrouted, between ranges, and before the first range
+        assert_eq!(lookup("1.0.2.1"), None);
+        assert_eq!(lookup("3.3.3.3"), None);
+        assert_eq!(lookup("0.1.2.3"), None);
+        assert_eq!(lookup("2001:db8::1"), None);
+    }
+}
@@ -1,18 +1,41 @@
 use anyhow::{Context, Result};
-use reqwest::Url;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use reqwest::{StatusCode, Url, Version};
+use std::net::IpAddr;
 use std::time::Duration;
 
-use crate::model::RunConfig;
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::engine::socket_tuning::{SocketTuning, TunedClient};
+use crate::model::{HttpVersion, RunConfig, TurnInfo};
 
 #[derive(Clone)]
 pub struct CloudflareClient {
     pub base_url: Url,
     pub meas_id: String,
     pub http: reqwest::Client,
+    /// Source address from --interface / --source, for probes that open
+    /// their own connections
+    pub local_ip: Option<IpAddr>,
+    /// Carries the throughput requests when congestion control or buffer
+    /// sizes are set, since those need a socket tuned before connect
+    pub tuned: Option<TunedClient>,
 }
 
 impl CloudflareClient {
     pub fn new(cfg: &RunConfig) -> Result<Self> {
+        Self::build(cfg, None)
+    }
+
+    /// Like `new`, but keeping at most `max_idle` idle connections per host.
+    /// With 1 and requests issued one at a time, they all share a single
+    /// warm connection.
+    pub fn with_idle_pool(cfg: &RunConfig, max_idle: usize) -> Result<Self> {
+        Self::build(cfg, Some(max_idle))
+    }
+
+    fn build(cfg: &RunConfig, max_idle: Option<usize>) -> Result<Self> {
         let base_url = Url::parse(&cfg.base_url).context("invalid base_url")?;
 
         let mut default_headers = reqwest::header::HeaderMap::new();
@@ -26,13 +49,25 @@ impl CloudflareClient {
             .default_headers(default_headers)
             .timeout(Duration::from_secs(30))
             .tcp_keepalive(Duration::from_secs(15));
+        if let Some(max_idle) = max_idle {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+
+        // Pin every connection to one address family if requested
+        let family = AddressFamily::from_config(cfg);
+        let mut local_ip = None;
+        if let Some(f) = family {
+            builder = builder
+                .dns_resolver(network_bind::FamilyResolver::new(f))
+                .local_address(f.unspecified());
+        }
 
         // Configure binding to interface or source IP if specified
         if let Some(ref iface) = cfg.interface {
-            use crate::engine::network_bind;
-            match network_bind::get_interface_ip(iface) {
+            match network_bind::get_interface_ip(iface, family) {
                 Ok(ip) => {
                     builder = builder.local_address(ip);
+                    local_ip = Some(ip);
                     eprintln!(
                         "Binding HTTP connections to interface {} (IP: {})",
                         iface, ip
@@ -49,8 +84,16 @@ impl CloudflareClient {
         } else if let Some(ref source_ip) = cfg.source_ip {
             // Bind to specific source IP address
             match source_ip.parse::<std::net::IpAddr>() {
+                Ok(ip) if family.is_some_and(|f| !f.matches(&ip)) => {
+                    return Err(anyhow::anyhow!(
+                        "Source IP {} conflicts with --{}-only",
+                        ip,
+                        if ip.is_ipv4() { "ipv6" } else { "ipv4" }
+                    ));
+                }
                 Ok(ip) => {
                     builder = builder.local_address(ip);
+                    local_ip = Some(ip);
                     eprintln!("Binding HTTP connections to source IP: {}", ip);
                 }
                 Err(e) => {
@@ -110,6 +153,35 @@ impl CloudflareClient {
             builder = builder.add_root_certificate(cert);
         }
 
+        let tuned = match SocketTuning::from_config(cfg)? {
+            Some(tuning) => Some(TunedClient::new(
+                tuning,
+                cfg,
+                local_ip,
+                cfg.certificate_path.as_deref(),
+            )?),
+            None => None,
+        };
+
+        // Pin the HTTP version if requested; auto leaves the choice to TLS ALPN
+        builder = match cfg.http_version {
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Http2 => builder.http2_prior_knowledge(),
+            #[cfg(feature = "http3")]
+            HttpVersion::Http3 => {
+                anyhow::ensure!(
+                    cfg.proxy.is_none(),
+                    "--http-version 3 cannot be used with --proxy"
+                );
+                anyhow::ensure!(
+                    tuned.is_none(),
+                    "TCP socket options do not apply to --http-version 3 (QUIC runs over UDP)"
+                );
+                builder.http3_prior_knowledge()
+            }
+            HttpVersion::Auto => builder,
+        };
+
         // Configure proxy if specified
         if let Some(ref proxy_url) = cfg.proxy {
             let proxy = reqwest::Proxy::all(proxy_url).with_context(|| {
@@ -127,6 +199,8 @@ impl CloudflareClient {
             base_url,
             meas_id: cfg.meas_id.clone(),
             http,
+            local_ip,
+            tuned,
         })
     }
 
@@ -138,6 +212,42 @@ impl CloudflareClient {
         self.base_url.join("/__up").expect("join __up")
     }
 
+    /// GET for the throughput workers, returning the status, the protocol
+    /// version and the body chunks. Goes through the tuned client when socket
+    /// options are set.
+    pub async fn get_stream(
+        &self,
+        url: Url,
+    ) -> Result<(StatusCode, Version, BoxStream<'static, Result<Bytes>>)> {
+        if let Some(ref tuned) = self.tuned {
+            return tuned.get(url).await;
+        }
+        let resp = self.http.get(url).send().await?;
+        let (status, version) = (resp.status(), resp.version());
+        Ok((
+            status,
+            version,
+            resp.bytes_stream().map_err(Into::into).boxed(),
+        ))
+    }
+
+    /// POST a streamed body for the throughput workers, like `get_stream`
+    pub async fn post_stream(
+        &self,
+        url: Url,
+        body: BoxStream<'static, std::io::Result<Bytes>>,
+    ) -> Result<(StatusCode, Version)> {
+        if let Some(ref tuned) = self.tuned {
+            return tuned.post(url, body).await;
+        }
+        let resp = self
+            .http
+            .post(url)
+            .body(reqwest::Body::wrap_stream(body))
+            .send()
+            .await?;
+        Ok((resp.status(), resp.version()))
+    }
 
     pub async fn probe_latency_ms(
         &self,
@@ -284,6 +394,18 @@ pub async fn fetch_meta_from_response(client: &CloudflareClient) -> Result<serde
     Ok(client.extract_meta_from_response(&resp))
 }
 
+/// Protocol version the server answers a test request with
+pub async fn fetch_http_version(client: &CloudflareClient) -> Result<String> {
+    let mut url = client.down_url();
+    url.query_pairs_mut()
+        .append_pair("bytes", "0")
+        .append_pair("measId", &client.meas_id);
+
+    let resp = client.http.get(url).send().await?;
+
+    Ok(format!("{:?}", resp.version()))
+}
+
 pub async fn fetch_meta(client: &CloudflareClient) -> Result<serde_json::Value> {
     let mut url = client.base_url.join("/meta").context("join /meta")?;
     // Try with measId parameter
@@ -345,6 +467,54 @@ pub async fn fetch_locations(client: &CloudflareClient) -> Result<serde_json::Va
     Ok(v)
 }
 
+/// TURN server URLs and short-lived credentials from /__turn
+pub async fn fetch_turn(client: &CloudflareClient) -> Result<TurnInfo> {
+    let url = client.base_url.join("/__turn").context("join /__turn")?;
+    let v: serde_json::Value = client
+        .http
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    parse_turn_info(&v).context("no TURN servers in /__turn response")
+}
+
+/// Accepts a bare `{urls, username, credential}` object or a list of them
+/// (RTCIceServer style), either optionally wrapped in `iceServers`. `urls`
+/// may be a single string. A list is merged into one set of URLs, with the
+/// first credentials found.
+fn parse_turn_info(v: &serde_json::Value) -> Option<TurnInfo> {
+    if let Some(servers) = v.get("iceServers") {
+        return parse_turn_info(servers);
+    }
+    if let Some(servers) = v.as_array() {
+        let parsed: Vec<TurnInfo> = servers.iter().filter_map(parse_turn_info).collect();
+        let creds = parsed.iter().find(|t| t.username.is_some());
+        return (!parsed.is_empty()).then(|| TurnInfo {
+            urls: parsed.iter().flat_map(|t| t.urls.clone()).collect(),
+            username: creds.and_then(|t| t.username.clone()),
+            credential: creds.and_then(|t| t.credential.clone()),
+        });
+    }
+
+    let urls: Vec<String> = match v.get("urls")? {
+        serde_json::Value::String(url) => vec![url.clone()],
+        serde_json::Value::Array(urls) => urls
+            .iter()
+            .filter_map(|u| u.as_str().map(String::from))
+            .collect(),
+        _ => return None,
+    };
+    let field = |name: &str| v.get(name).and_then(|s| s.as_str()).map(String::from);
+    (!urls.is_empty()).then(|| TurnInfo {
+        urls,
+        username: field("username"),
+        credential: field("credential"),
+    })
+}
+
 pub fn map_colo_to_server(locations: &serde_json::Value, colo: &str) -> Option<String> {
     // Try to get location info from dynamic locations data
     fn visit(v: &serde_json::Value, colo: &str) -> Option<serde_json::Value> {
@@ -408,3 +578,32 @@ pub fn map_colo_to_server(locations: &serde_json::Value, colo: &str) -> Option<S
     // Just return the colo code if no location data available
     Some(colo.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_turn_info() {
+        let bare = serde_json::json!({
+            "urls": ["turn:turn.example.com:3478?transport=udp"],
+            "username": "1700000000:user",
+            "credential": "secret",
+        });
+        let info = parse_turn_info(&bare).unwrap();
+        assert_eq!(info.urls.len(), 1);
+        assert_eq!(info.username.as_deref(), Some("1700000000:user"));
+
+        let wrapped = serde_json::json!({
+            "iceServers": [
+                { "urls": "stun:stun.example.com:3478" },
+                { "urls": ["turn:turn.example.com:3478"], "username": "u", "credential": "c" },
+            ]
+        });
+        let info = parse_turn_info(&wrapped).unwrap();
+        assert_eq!(info.urls.len(), 2);
+        assert_eq!(info.credential.as_deref(), Some("c"));
+
+        assert!(parse_turn_info(&serde_json::json!({ "urls": [] })).is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! Connection-establishment breakdown, in the spirit of `curl -w`
+//!
+//! Each sample opens a brand-new connection and times DNS resolution, the TCP
+//! handshake, the TLS handshake, time to first response byte and the body
+//! transfer as separate stages. Requests are plain HTTP/1.1 with
+//! `Connection: close` so nothing is pooled between samples.
+
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::model::{ConnectionStages, ConnectionTimingSummary};
+use anyhow::{Context, Result};
+use rustls::pki_types::ServerName;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpSocket, TcpStream};
+
+/// Response body size requested per sample
+const SAMPLE_BYTES: u64 = 100_000;
+
+/// Upper bound for one complete sample
+const SAMPLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Measure `samples` fresh requests against the speed-test download endpoint.
+/// Failed samples are counted and skipped; only all of them failing is an error.
+pub async fn measure_connection_timing(
+    base_url: &str,
+    user_agent: &str,
+    family: Option<AddressFamily>,
+    local_ip: Option<IpAddr>,
+    samples: u32,
+) -> Result<ConnectionTimingSummary> {
+    let target = FreshTarget::new(base_url, user_agent, family, local_ip, SAMPLE_BYTES)?;
+
+    let mut results = Vec::new();
+    let mut failed = 0;
+    let mut last_error = None;
+    for _ in 0..samples.max(1) {
+        match tokio::time::timeout(SAMPLE_TIMEOUT, target.sample()).await {
+            Ok(Ok(stages)) => results.push(stages),
+            Ok(Err(e)) => {
+                failed += 1;
+                last_error = Some(e);
+            }
+            Err(_) => {
+                failed += 1;
+                last_error = Some(anyhow::anyhow!("Connection timing sample timed out"));
+            }
+        }
+    }
+    if results.is_empty() {
+        return Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No samples taken")));
+    }
+
+    let median = |f: fn(&ConnectionStages) -> f64| {
+        let values: Vec<f64> = results.iter().map(f).collect();
+        crate::metrics::median(&values).unwrap_or(0.0)
+    };
+    let median = ConnectionStages {
+        dns_ms: median(|s| s.dns_ms),
+        tcp_ms: median(|s| s.tcp_ms),
+        tls_ms: median(|s| s.tls_ms),
+        ttfb_ms: median(|s| s.ttfb_ms),
+        transfer_ms: median(|s| s.transfer_ms),
+    };
+
+    Ok(ConnectionTimingSummary {
+        url: target.url.to_string(),
+        bytes: SAMPLE_BYTES,
+        samples: results,
+        failed,
+        median,
+    })
+}
+
+/// A download-endpoint request that can be repeated over fresh connections.
+pub struct FreshTarget {
+    url: reqwest::Url,
+    host: String,
+    port: u16,
+    tls: bool,
+    family: Option<AddressFamily>,
+    /// Source address, as the HTTP client binds with --interface / --source
+    local_ip: Option<IpAddr>,
+    request: String,
+}
+
+impl FreshTarget {
+    pub fn new(
+        base_url: &str,
+        user_agent: &str,
+        family: Option<AddressFamily>,
+        local_ip: Option<IpAddr>,
+        bytes: u64,
+    ) -> Result<Self> {
+        let url = reqwest::Url::parse(&format!("{}/__down?bytes={}", base_url, bytes))
+            .context("Invalid base URL")?;
+        let host = url.host_str().context("Base URL has no host")?.to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let tls = url.scheme() == "https";
+        let request = format!(
+            "GET {}?{} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+            url.path(),
+            url.query().unwrap_or(""),
+            host,
+            user_agent
+        );
+        Ok(Self {
+            url,
+            host,
+            port,
+            tls,
+            family,
+            local_ip,
+            request,
+        })
+    }
+
+    /// Whether samples include a TLS handshake (https base URL)
+    pub fn uses_tls(&self) -> bool {
+        self.tls
+    }
+
+    /// Time one request over a brand-new connection.
+    pub async fn sample(&self) -> Result<ConnectionStages> {
+        sample(
+            &self.host,
+            self.port,
+            self.tls,
+            self.family,
+            self.local_ip,
+            self.request.as_bytes(),
+        )
+        .await
+    }
+}
+
+/// One fresh connection: resolve, connect, handshake, request, read to EOF.
+async fn sample(
+    host: &str,
+    port: u16,
+    tls: bool,
+    family: Option<AddressFamily>,
+    local_ip: Option<IpAddr>,
+    request: &[u8],
+) -> Result<ConnectionStages> {
+    let mut stages = ConnectionStages::default();
+
+    let start = Instant::now();
+    let addrs = network_bind::lookup_host(host, port, family).await?;
+    stages.dns_ms = ms_since(start);
+
+    let start = Instant::now();
+    let tcp = connect(&addrs, local_ip)
+        .await
+        .with_context(|| format!("TCP connection failed to {}:{}", host, port))?;
+    tcp.set_nodelay(true).ok();
+    stages.tcp_ms = ms_since(start);
+
+    if !tls {
+        return exchange(tcp, request, stages).await;
+    }
+
+    let server_name: ServerName<'static> = host
+        .to_string()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid DNS name: {}", host))?;
+    let start = Instant::now();
+    let stream = crate::engine::tls::webpki_connector()
+        .connect(server_name, tcp)
+        .await
+        .with_context(|| format!("TLS handshake failed with {}", host))?;
+    stages.tls_ms = ms_since(start);
+
+    exchange(stream, request, stages).await
+}
+
+/// Connect to the first reachable address, from `local_ip` when set. Addresses
+/// of the other family are skipped rather than reached from another source.
+async fn connect(addrs: &[SocketAddr], local_ip: Option<IpAddr>) -> std::io::Result<TcpStream> {
+    let mut last_err = None;
+    for &addr in addrs {
+        if local_ip.is_some_and(|ip| ip.is_ipv4() != addr.is_ipv4()) {
+            continue;
+        }
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        if let Some(ip) = local_ip {
+            socket.bind(SocketAddr::new(ip, 0))?;
+        }
+        match socket.connect(addr).await {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::AddrNotAvailable,
+            "no address matches the source address family",
+        )
+    }))
+}
+
+/// Send the request and time first byte and full body separately.
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    request: &[u8],
+    mut stages: ConnectionStages,
+) -> Result<ConnectionStages> {
+    let start = Instant::now();
+    stream
+        .write_all(request)
+        .await
+        .context("Failed to send request")?;
+
+    let mut buf = vec![0u8; 16 * 1024];
+    let n = stream
+        .read(&mut buf)
+        .await
+        .context("Failed to read response")?;
+    anyhow::ensure!(n > 0, "Connection closed before response");
+    stages.ttfb_ms = ms_since(start);
+    anyhow::ensure!(
+        is_success_status(&buf[..n]),
+        "Unexpected response: {}",
+        String::from_utf8_lossy(&buf[..n])
+            .lines()
+            .next()
+            .unwrap_or("")
+    );
+
+    let start = Instant::now();
+    loop {
+        match stream.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(_) => {}
+            // Servers often close without a TLS close_notify
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e).context("Failed to read response body"),
+        }
+    }
+    stages.transfer_ms = ms_since(start);
+
+    Ok(stages)
+}
+
+fn is_success_status(head: &[u8]) -> bool {
+    head.starts_with(b"HTTP/1.1 2") || head.starts_with(b"HTTP/1.0 2")
+}
+
+fn ms_since(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
@@ -1,15 +1,55 @@
 //! DNS resolution time measurement module
-
-use crate::model::DnsSummary;
+//!
+//! Times the system resolver as a whole, then queries each configured (and
+//! optionally each well-known public) resolver directly over UDP/53 for A and
+//! AAAA records, twice each. The test host has normally been resolved by then,
+//! so neither query is guaranteed to be a cold lookup; the repeat shows how
+//! fast the resolver answers from its own cache.
+//! Encrypted DNS (DoH and DoT) is timed separately so its overhead can be
+//! compared against the plain resolvers.
+
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::model::{
+    DnsRecordTiming, DnsServerTiming, DnsSummary, EncryptedDnsProtocol, EncryptedDnsTiming,
+};
 use anyhow::{Context, Result};
-use std::net::IpAddr;
-use std::time::Instant;
-use tokio::net::lookup_host;
+use rustls::pki_types::ServerName;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{lookup_host, TcpStream};
+
+/// Timeout for a single direct query to one resolver
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// DNS record types queried per resolver
+const QTYPE_A: u16 = 1;
+const QTYPE_AAAA: u16 = 28;
+
+/// Timeout for one encrypted query, including connection setup
+const ENCRYPTED_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default DNS-over-TLS port (RFC 7858)
+const DOT_PORT: u16 = 853;
+
+/// Well-known public resolvers (Cloudflare, Google, Quad9)
+const PUBLIC_RESOLVERS_V4: &[&str] = &["1.1.1.1", "8.8.8.8", "9.9.9.9"];
+const PUBLIC_RESOLVERS_V6: &[&str] = &[
+    "2606:4700:4700::1111",
+    "2001:4860:4860::8888",
+    "2620:fe::fe",
+];
 
 /// Measure DNS resolution time for a given hostname.
 ///
-/// Returns a `DnsSummary` containing the resolution time and resolved IP addresses.
-pub async fn measure_dns_resolution(hostname: &str) -> Result<DnsSummary> {
+/// Returns a `DnsSummary` containing the resolution time and resolved IP addresses,
+/// plus direct per-resolver timings. Resolvers outside `family` are skipped;
+/// `include_public` adds well-known public resolvers to the comparison.
+pub async fn measure_dns_resolution(
+    hostname: &str,
+    family: Option<AddressFamily>,
+    include_public: bool,
+) -> Result<DnsSummary> {
     // Get system DNS servers
     let dns_servers = get_system_dns_servers();
 
@@ -44,6 +84,33 @@ pub async fn measure_dns_resolution(hostname: &str) -> Result<DnsSummary> {
     resolved_ips.sort();
     resolved_ips.dedup();
 
+    // Direct queries: system resolvers first, then public ones for comparison
+    let allowed = |ip: &IpAddr| family.map(|f| f.matches(ip)).unwrap_or(true);
+    let mut targets: Vec<(IpAddr, bool)> = dns_servers
+        .iter()
+        .filter_map(|s| s.parse::<IpAddr>().ok())
+        .filter(allowed)
+        .map(|ip| (ip, false))
+        .collect();
+    if include_public {
+        let public = if family == Some(AddressFamily::V6) {
+            PUBLIC_RESOLVERS_V6
+        } else {
+            PUBLIC_RESOLVERS_V4
+        };
+        for ip in public.iter().filter_map(|s| s.parse::<IpAddr>().ok()) {
+            if !targets.iter().any(|(t, _)| *t == ip) {
+                targets.push((ip, true));
+            }
+        }
+    }
+    let per_server = futures::future::join_all(
+        targets
+            .into_iter()
+            .map(|(server, public)| time_resolver(server, public, hostname)),
+    )
+    .await;
+
     Ok(DnsSummary {
         hostname: hostname.to_string(),
         resolution_time_ms: elapsed.as_secs_f64() * 1000.0,
@@ -51,7 +118,318 @@ pub async fn measure_dns_resolution(hostname: &str) -> Result<DnsSummary> {
         ipv4_count,
         ipv6_count,
         dns_servers,
+        per_server,
+        encrypted: Vec::new(),
+    })
+}
+
+/// Time lookups of `hostname` over DoH and/or DoT.
+///
+/// DoH goes through `http` (the test's own client, so proxy and bind settings
+/// apply); DoT opens its own TLS connection. Each endpoint is queried twice:
+/// the first query pays for TCP and TLS setup, the second reuses the connection.
+pub async fn measure_encrypted_dns(
+    http: &reqwest::Client,
+    hostname: &str,
+    family: Option<AddressFamily>,
+    doh_url: Option<&str>,
+    dot_server: Option<&str>,
+) -> Vec<EncryptedDnsTiming> {
+    let qtype = if family == Some(AddressFamily::V6) {
+        QTYPE_AAAA
+    } else {
+        QTYPE_A
+    };
+
+    let doh = async {
+        let url = doh_url?;
+        let mut timing = encrypted_timing(EncryptedDnsProtocol::Doh, url);
+        for attempt in 0..2 {
+            match query_doh(http, url, hostname, qtype).await {
+                Ok((ms, answers)) => record_encrypted(&mut timing, attempt, ms, answers),
+                Err(e) => {
+                    timing.error = Some(format!("{:#}", e));
+                    break;
+                }
+            }
+        }
+        Some(timing)
+    };
+
+    let dot = async {
+        let target = dot_server?;
+        let mut timing = encrypted_timing(EncryptedDnsProtocol::Dot, target);
+        if let Err(e) = query_dot(target, hostname, qtype, family, &mut timing).await {
+            timing.error = Some(format!("{:#}", e));
+        }
+        Some(timing)
+    };
+
+    let (doh, dot) = tokio::join!(doh, dot);
+    doh.into_iter().chain(dot).collect()
+}
+
+fn encrypted_timing(protocol: EncryptedDnsProtocol, target: &str) -> EncryptedDnsTiming {
+    EncryptedDnsTiming {
+        protocol,
+        target: target.to_string(),
+        first_ms: None,
+        reused_ms: None,
+        answers: 0,
+        error: None,
+    }
+}
+
+fn record_encrypted(timing: &mut EncryptedDnsTiming, attempt: usize, ms: f64, answers: usize) {
+    if attempt == 0 {
+        timing.first_ms = Some(ms);
+        timing.answers = answers;
+    } else {
+        timing.reused_ms = Some(ms);
+    }
+}
+
+/// One RFC 8484 POST query; the pooled client keeps the connection for the next one.
+async fn query_doh(
+    http: &reqwest::Client,
+    url: &str,
+    hostname: &str,
+    qtype: u16,
+) -> Result<(f64, usize)> {
+    // RFC 8484 recommends id 0 so responses stay cacheable
+    let query = build_dns_query(0, hostname, qtype)?;
+
+    let start = Instant::now();
+    let resp = http
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/dns-message")
+        .header(reqwest::header::ACCEPT, "application/dns-message")
+        .timeout(ENCRYPTED_QUERY_TIMEOUT)
+        .body(query)
+        .send()
+        .await
+        .with_context(|| format!("DoH request to {} failed", url))?
+        .error_for_status()?;
+    let body = resp.bytes().await.context("Failed to read DoH response")?;
+    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let answers = parse_dns_response(&body, 0, qtype)?;
+    Ok((elapsed_ms, answers))
+}
+
+/// Connect to a DoT server and send two queries over the same TLS session.
+async fn query_dot(
+    target: &str,
+    hostname: &str,
+    qtype: u16,
+    family: Option<AddressFamily>,
+    timing: &mut EncryptedDnsTiming,
+) -> Result<()> {
+    let (host, port) = parse_dot_target(target);
+    let server_name: ServerName<'static> = host
+        .clone()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid DoT server name: {}", host))?;
+    // Resolve before timing so only the encrypted lookup itself is measured
+    let addrs = network_bind::lookup_host(&host, port, family).await?;
+    let connector = crate::engine::tls::webpki_connector();
+
+    let start = Instant::now();
+    let mut stream = tokio::time::timeout(ENCRYPTED_QUERY_TIMEOUT, async {
+        let tcp = TcpStream::connect(addrs.as_slice())
+            .await
+            .with_context(|| format!("TCP connection failed to {}:{}", host, port))?;
+        connector
+            .connect(server_name, tcp)
+            .await
+            .with_context(|| format!("TLS handshake failed with {}", host))
     })
+    .await
+    .map_err(|_| anyhow::anyhow!("timed out"))??;
+
+    for attempt in 0..2 {
+        let id: u16 = rand::random();
+        let query = build_dns_query(id, hostname, qtype)?;
+        let query_start = if attempt == 0 { start } else { Instant::now() };
+        let response = tokio::time::timeout(ENCRYPTED_QUERY_TIMEOUT, async {
+            // RFC 7858 framing: two-byte length prefix
+            let mut framed = (query.len() as u16).to_be_bytes().to_vec();
+            framed.extend_from_slice(&query);
+            stream.write_all(&framed).await?;
+            let len = stream.read_u16().await? as usize;
+            let mut buf = vec![0u8; len];
+            stream.read_exact(&mut buf).await?;
+            Ok::<_, std::io::Error>(buf)
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out"))?
+        .context("DoT query failed")?;
+        let ms = query_start.elapsed().as_secs_f64() * 1000.0;
+        let answers = parse_dns_response(&response, id, qtype)?;
+        record_encrypted(timing, attempt, ms, answers);
+    }
+    Ok(())
+}
+
+/// Split a DoT target (`host`, `host:port`, `ip`, `[v6]:port`) into host and port.
+fn parse_dot_target(target: &str) -> (String, u16) {
+    if let Ok(addr) = target.parse::<SocketAddr>() {
+        return (addr.ip().to_string(), addr.port());
+    }
+    if let Ok(ip) = target
+        .trim_matches(|c| c == '[' || c == ']')
+        .parse::<IpAddr>()
+    {
+        return (ip.to_string(), DOT_PORT);
+    }
+    match target
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+    {
+        Some((host, port)) => (host.to_string(), port),
+        None => (target.to_string(), DOT_PORT),
+    }
+}
+
+/// Query one resolver for A and AAAA, each twice (first, then repeat).
+async fn time_resolver(server: IpAddr, public: bool, hostname: &str) -> DnsServerTiming {
+    DnsServerTiming {
+        server: server.to_string(),
+        public,
+        a: time_record(server, hostname, QTYPE_A).await,
+        aaaa: time_record(server, hostname, QTYPE_AAAA).await,
+    }
+}
+
+async fn time_record(server: IpAddr, hostname: &str, qtype: u16) -> DnsRecordTiming {
+    let mut timing = DnsRecordTiming::default();
+    match query_udp(server, hostname, qtype).await {
+        Ok((ms, answers)) => {
+            timing.first_ms = Some(ms);
+            timing.answers = answers;
+        }
+        Err(e) => {
+            timing.error = Some(format!("{:#}", e));
+            return timing;
+        }
+    }
+    // The repeat should be answered from the resolver's cache
+    match query_udp(server, hostname, qtype).await {
+        Ok((ms, _)) => timing.repeat_ms = Some(ms),
+        Err(e) => timing.error = Some(format!("{:#}", e)),
+    }
+    timing
+}
+
+/// Send one query over UDP/53 and return (elapsed ms, matching answer count).
+async fn query_udp(server: IpAddr, hostname: &str, qtype: u16) -> Result<(f64, usize)> {
+    let local = if server.is_ipv4() {
+        AddressFamily::V4
+    } else {
+        AddressFamily::V6
+    }
+    .unspecified();
+    let socket = tokio::net::UdpSocket::bind(SocketAddr::new(local, 0))
+        .await
+        .context("Failed to bind UDP socket")?;
+    socket
+        .connect(SocketAddr::new(server, 53))
+        .await
+        .with_context(|| format!("No route to {}", server))?;
+
+    let id: u16 = rand::random();
+    let query = build_dns_query(id, hostname, qtype)?;
+
+    let start = Instant::now();
+    socket.send(&query).await.context("Failed to send query")?;
+    let mut buf = [0u8; 4096];
+    let len = tokio::time::timeout(QUERY_TIMEOUT, socket.recv(&mut buf))
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out"))?
+        .context("Failed to receive response")?;
+    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    let answers = parse_dns_response(&buf[..len], id, qtype)?;
+    Ok((elapsed_ms, answers))
+}
+
+/// Build a recursive query for `hostname` (RFC 1035 wire format).
+fn build_dns_query(id: u16, hostname: &str, qtype: u16) -> Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(18 + hostname.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&0x0100u16.to_be_bytes()); // RD
+    packet.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    packet.extend_from_slice(&[0, 0, 0, 0, 0, 0]); // AN/NS/AR
+    for label in hostname.trim_end_matches('.').split('.') {
+        anyhow::ensure!(
+            !label.is_empty() && label.len() <= 63,
+            "Invalid DNS name {}",
+            hostname
+        );
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes()); // IN
+    Ok(packet)
+}
+
+/// Validate a response to query `id` and count answer records of `qtype`.
+///
+/// CNAMEs in the chain are skipped; only records of the asked type count.
+fn parse_dns_response(data: &[u8], id: u16, qtype: u16) -> Result<usize> {
+    let be16 = |pos: usize| -> Result<u16> {
+        data.get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .context("Truncated DNS response")
+    };
+
+    anyhow::ensure!(be16(0)? == id, "Mismatched DNS transaction id");
+    let flags = be16(2)?;
+    anyhow::ensure!(flags & 0x8000 != 0, "Not a DNS response");
+    match flags & 0x000f {
+        0 => {}
+        2 => anyhow::bail!("SERVFAIL"),
+        3 => anyhow::bail!("NXDOMAIN"),
+        5 => anyhow::bail!("REFUSED"),
+        rcode => anyhow::bail!("rcode {}", rcode),
+    }
+
+    let qdcount = be16(4)?;
+    let ancount = be16(6)?;
+    let mut pos = 12;
+    for _ in 0..qdcount {
+        pos = skip_dns_name(data, pos).context("Truncated DNS question")? + 4;
+    }
+
+    let mut count = 0;
+    for _ in 0..ancount {
+        pos = skip_dns_name(data, pos).context("Truncated DNS answer")?;
+        let rtype = be16(pos)?;
+        let rdlength = be16(pos + 8)? as usize;
+        pos += 10 + rdlength;
+        anyhow::ensure!(pos <= data.len(), "Truncated DNS answer");
+        if rtype == qtype {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Return the offset just past a (possibly compressed) name starting at `pos`.
+fn skip_dns_name(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *data.get(pos)?;
+        if len & 0xc0 == 0xc0 {
+            data.get(pos + 1)?;
+            return Some(pos + 2);
+        }
+        if len == 0 {
+            return Some(pos + 1);
+        }
+        pos += 1 + len as usize;
+    }
 }
 
 /// Get the system's configured DNS servers.
@@ -208,7 +586,11 @@ pub fn extract_hostname(url: &str) -> Option<String> {
 
 /// Fetch external IPv4 and IPv6 addresses by making requests to Cloudflare.
 /// Returns (ipv4, ipv6) - either may be None if not available.
-pub async fn fetch_external_ips(base_url: &str) -> (Option<String>, Option<String>) {
+/// When the run is pinned to one address family, the other one is not queried.
+pub async fn fetch_external_ips(
+    base_url: &str,
+    family: Option<AddressFamily>,
+) -> (Option<String>, Option<String>) {
     let hostname = match extract_hostname(base_url) {
         Some(h) => h,
         None => return (None, None),
@@ -217,24 +599,31 @@ pub async fn fetch_external_ips(base_url: &str) -> (Option<String>, Option<Strin
     // Resolve to get IPv4 and IPv6 addresses
     let url = format!("{}/__down?bytes=0", base_url);
 
+    let want = |f: AddressFamily| family.is_none() || family == Some(f);
     let (ipv4, ipv6) = tokio::join!(
-        fetch_external_ip_version(&url, &hostname, IpVersion::V4),
-        fetch_external_ip_version(&url, &hostname, IpVersion::V6)
+        async {
+            if want(AddressFamily::V4) {
+                fetch_external_ip_version(&url, &hostname, AddressFamily::V4).await
+            } else {
+                None
+            }
+        },
+        async {
+            if want(AddressFamily::V6) {
+                fetch_external_ip_version(&url, &hostname, AddressFamily::V6).await
+            } else {
+                None
+            }
+        }
     );
 
     (ipv4, ipv6)
 }
 
-#[derive(Clone, Copy)]
-enum IpVersion {
-    V4,
-    V6,
-}
-
 async fn fetch_external_ip_version(
     url: &str,
     hostname: &str,
-    version: IpVersion,
+    version: AddressFamily,
 ) -> Option<String> {
     use std::net::SocketAddr;
     use std::time::Duration;
@@ -247,10 +636,7 @@ async fn fetch_external_ip_version(
         .collect();
 
     // Find an address of the requested version
-    let target_addr = addrs.into_iter().find(|addr| match version {
-        IpVersion::V4 => addr.is_ipv4(),
-        IpVersion::V6 => addr.is_ipv6(),
-    })?;
+    let target_addr = addrs.into_iter().find(|addr| version.matches(&addr.ip()))?;
 
     // Build client that resolves to the specific IP
     let client = reqwest::Client::builder()
@@ -285,4 +671,47 @@ mod tests {
         );
         assert_eq!(extract_hostname("not a url"), None);
     }
+
+    #[test]
+    fn test_parse_dot_target() {
+        let t = |s: &str| parse_dot_target(s);
+        assert_eq!(t("one.one.one.one"), ("one.one.one.one".into(), 853));
+        assert_eq!(t("dns.google:8853"), ("dns.google".into(), 8853));
+        assert_eq!(t("1.1.1.1"), ("1.1.1.1".into(), 853));
+        assert_eq!(
+            t("[2606:4700:4700::1111]:853"),
+            ("2606:4700:4700::1111".into(), 853)
+        );
+        assert_eq!(
+            t("2606:4700:4700::1111"),
+            ("2606:4700:4700::1111".into(), 853)
+        );
+    }
+
+    #[test]
+    fn test_dns_query_roundtrip() {
+        let query = build_dns_query(0xbeef, "speed.cloudflare.com.", QTYPE_AAAA).unwrap();
+        assert_eq!(&query[12..18], b"\x05speed");
+        assert_eq!(query.len(), 12 + 22 + 4);
+        assert!(build_dns_query(1, "bad..name", QTYPE_A).is_err());
+
+        // Response: header with QR set, the question echoed back, then a CNAME
+        // and two AAAA answers using name compression
+        let mut resp = query.clone();
+        resp[2] = 0x81;
+        resp[3] = 0x80;
+        resp[7] = 3; // ANCOUNT
+        resp.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 0xc0, 12]);
+        for _ in 0..2 {
+            resp.extend_from_slice(&[0xc0, 12, 0, 28, 0, 1, 0, 0, 0, 60, 0, 16]);
+            resp.extend_from_slice(&[0u8; 16]);
+        }
+        assert_eq!(parse_dns_response(&resp, 0xbeef, QTYPE_AAAA).unwrap(), 2);
+        assert!(parse_dns_response(&resp, 0xdead, QTYPE_AAAA).is_err());
+        assert!(parse_dns_response(&resp[..resp.len() - 4], 0xbeef, QTYPE_AAAA).is_err());
+
+        resp[3] = 0x83;
+        let err = parse_dns_response(&resp, 0xbeef, QTYPE_AAAA).unwrap_err();
+        assert_eq!(err.to_string(), "NXDOMAIN");
+    }
 }
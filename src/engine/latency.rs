@@ -3,6 +3,7 @@ use crate::engine::wait_if_paused_or_cancelled;
 use crate::model::{LatencySummary, Phase, TestEvent};
 use crate::stats::{latency_summary_from_samples, OnlineStats};
 use anyhow::Result;
+use std::future::Future;
 use std::sync::{atomic::AtomicBool, Arc};
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -81,3 +82,34 @@ pub async fn run_latency_probes(
         online.stddev(),
     ))
 }
+
+/// `samples` probes one after another, without live events, for series kept
+/// apart from the idle and loaded latency views. `probe` returns the round
+/// trip in milliseconds, or `None` when it failed.
+pub async fn run_probe_series<F, Fut>(
+    mut probe: F,
+    samples: u32,
+    interval_ms: u64,
+    paused: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+) -> LatencySummary
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<f64>>,
+{
+    let mut sent = 0u64;
+    let mut rtts = Vec::<f64>::new();
+
+    for _ in 0..samples {
+        if wait_if_paused_or_cancelled(&paused, &cancel).await {
+            break;
+        }
+        sent += 1;
+        if let Some(ms) = probe().await {
+            rtts.push(ms);
+        }
+        tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+    }
+
+    latency_summary_from_samples(sent, rtts.len() as u64, &rtts, None)
+}
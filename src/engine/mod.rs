@@ -1,18 +1,30 @@
+pub mod asn;
 mod cloudflare;
+pub mod connection_timing;
 pub mod dns;
 pub mod ip_comparison;
 mod latency;
+mod nat;
 mod network_bind;
+pub mod path_monitor;
+pub mod pmtu;
+mod responsiveness;
+mod socket_tuning;
+mod stun;
+mod tcp_info;
 mod throughput;
 pub mod tls;
 pub mod traceroute;
+mod turn_relay;
 mod turn_udp;
 
 use crate::model::{
-    DnsSummary, IpVersionComparison, Phase, RunConfig, RunResult, TestEvent, TlsSummary,
-    TracerouteSummary,
+    ConnectionStages, ConnectionTimingSummary, DnsSummary, ExperimentalUdpSummary,
+    IpVersionComparison, LoadedUdpSummary, PathMonitorSummary, Phase, PmtuSummary, Responsiveness,
+    RunConfig, RunResult, StunServerResult, TcpTelemetry, TestEvent, TlsSummary, TracerouteSummary,
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
+use network_bind::AddressFamily;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
@@ -29,6 +41,62 @@ pub(crate) async fn wait_if_paused_or_cancelled(paused: &AtomicBool, cancel: &At
     cancel.load(Ordering::Relaxed)
 }
 
+/// Samples of a fresh-connection probe task, if one was started.
+async fn join_fresh_probes(
+    handle: Option<tokio::task::JoinHandle<Vec<ConnectionStages>>>,
+) -> Vec<ConnectionStages> {
+    match handle {
+        Some(handle) => handle.await.unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+/// Wait for a UDP probe run under load; a failed probe is reported and dropped.
+async fn join_loaded_udp(
+    handle: Option<tokio::task::JoinHandle<Result<ExperimentalUdpSummary>>>,
+    phase: Phase,
+    event_tx: &mpsc::Sender<TestEvent>,
+) -> Option<ExperimentalUdpSummary> {
+    let result = handle?
+        .await
+        .context("UDP probe task failed")
+        .and_then(|r| r);
+    match result {
+        Ok(summary) => Some(summary),
+        Err(e) => {
+            event_tx
+                .send(TestEvent::Info {
+                    message: format!("UDP probe during {phase:?} failed: {e:#}"),
+                })
+                .await
+                .ok();
+            None
+        }
+    }
+}
+
+/// Fail early when a run is pinned to an address family that the speed-test
+/// host or this machine cannot use, rather than letting every phase time out.
+async fn check_address_family(cfg: &RunConfig, family: AddressFamily) -> Result<()> {
+    // With a proxy the target is resolved and reached by the proxy, not by us
+    if cfg.proxy.is_some() {
+        return Ok(());
+    }
+
+    let (host, port) = tls::extract_host_port(&cfg.base_url).context("invalid base_url")?;
+    let addrs = network_bind::lookup_host(&host, port, Some(family))
+        .await
+        .with_context(|| format!("cannot run {}-only test", family.label()))?;
+    let bind = network_bind::resolve_bind_address(
+        cfg.interface.as_ref(),
+        cfg.source_ip.as_ref(),
+        Some(family),
+    )?;
+    network_bind::ensure_route(addrs[0], bind)
+        .with_context(|| format!("cannot run {}-only test", family.label()))?;
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub enum EngineControl {
     /// Pause (true) or resume (false) the running test
@@ -52,10 +120,23 @@ impl TestEngine {
         mut control_rx: mpsc::Receiver<EngineControl>,
     ) -> Result<RunResult> {
         let client = cloudflare::CloudflareClient::new(&self.cfg)?;
+        let family = AddressFamily::from_config(&self.cfg);
+        if let Some(f) = family {
+            check_address_family(&self.cfg, f).await?;
+        }
 
         let paused = Arc::new(AtomicBool::new(false));
         let cancel = Arc::new(AtomicBool::new(false));
 
+        // A pinned HTTP/3 fails outright where UDP/443 is blocked; say so up front
+        let http_protocol = match cloudflare::fetch_http_version(&client).await {
+            Ok(version) => Some(version),
+            Err(e) if self.cfg.http_version.is_http3() => {
+                return Err(e.context("HTTP/3 request failed, UDP/443 may be blocked"));
+            }
+            Err(_) => None,
+        };
+
         // Try to get meta from multiple sources in order of preference:
         // 1. /meta endpoint (may have full details)
         // 2. /cdn-cgi/trace endpoint (reliable source for colo, ip, country)
@@ -151,8 +232,20 @@ impl TestEngine {
                     .await
                     .ok();
 
-                match dns::measure_dns_resolution(&hostname).await {
-                    Ok(summary) => {
+                match dns::measure_dns_resolution(&hostname, family, self.cfg.dns_public_resolvers)
+                    .await
+                {
+                    Ok(mut summary) => {
+                        if self.cfg.doh_url.is_some() || self.cfg.dot_server.is_some() {
+                            summary.encrypted = dns::measure_encrypted_dns(
+                                &client.http,
+                                &hostname,
+                                family,
+                                self.cfg.doh_url.as_deref(),
+                                self.cfg.dot_server.as_deref(),
+                            )
+                            .await;
+                        }
                         event_tx
                             .send(TestEvent::DiagnosticDns {
                                 summary: summary.clone(),
@@ -183,7 +276,14 @@ impl TestEngine {
                     .await
                     .ok();
 
-                match tls::measure_tls_handshake(&hostname, port).await {
+                match tls::measure_tls_handshake(
+                    &hostname,
+                    port,
+                    family,
+                    self.cfg.certificate_path.as_deref(),
+                )
+                .await
+                {
                     Ok(summary) => {
                         event_tx
                             .send(TestEvent::DiagnosticTls {
@@ -205,9 +305,92 @@ impl TestEngine {
             }
         }
 
+        // Path MTU discovery (the probes bypass any proxy, so skip it there)
+        let mut pmtu_summary: Option<PmtuSummary> = None;
+        if self.cfg.measure_pmtu && self.cfg.proxy.is_none() {
+            if let Some((hostname, port)) = tls::extract_host_port(&self.cfg.base_url) {
+                event_tx
+                    .send(TestEvent::Info {
+                        message: format!("Discovering path MTU to {}...", hostname),
+                    })
+                    .await
+                    .ok();
+
+                let result = match network_bind::resolve_bind_address(
+                    self.cfg.interface.as_ref(),
+                    self.cfg.source_ip.as_ref(),
+                    family,
+                ) {
+                    Ok(bind) => pmtu::measure_pmtu(&hostname, port, family, bind).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(summary) => {
+                        event_tx
+                            .send(TestEvent::DiagnosticPmtu {
+                                summary: summary.clone(),
+                            })
+                            .await
+                            .ok();
+                        pmtu_summary = Some(summary);
+                    }
+                    Err(e) => {
+                        event_tx
+                            .send(TestEvent::Info {
+                                message: format!("PMTU discovery failed: {:#}", e),
+                            })
+                            .await
+                            .ok();
+                    }
+                }
+            }
+        }
+
+        // Connection-establishment breakdown (opens its own connections, so not via a proxy)
+        let mut connection_timing_summary: Option<ConnectionTimingSummary> = None;
+        if self.cfg.measure_connection_timing && self.cfg.proxy.is_none() {
+            event_tx
+                .send(TestEvent::Info {
+                    message: format!(
+                        "Timing {} fresh connections...",
+                        self.cfg.connection_timing_samples
+                    ),
+                })
+                .await
+                .ok();
+
+            match connection_timing::measure_connection_timing(
+                &self.cfg.base_url,
+                &self.cfg.user_agent,
+                family,
+                client.local_ip,
+                self.cfg.connection_timing_samples,
+            )
+            .await
+            {
+                Ok(summary) => {
+                    event_tx
+                        .send(TestEvent::DiagnosticConnectionTiming {
+                            summary: summary.clone(),
+                        })
+                        .await
+                        .ok();
+                    connection_timing_summary = Some(summary);
+                }
+                Err(e) => {
+                    event_tx
+                        .send(TestEvent::Info {
+                            message: format!("Connection timing failed: {:#}", e),
+                        })
+                        .await
+                        .ok();
+                }
+            }
+        }
+
         // Fetch external IPs (runs in parallel, part of default diagnostics)
         if self.cfg.measure_dns {
-            let (v4, v6) = dns::fetch_external_ips(&self.cfg.base_url).await;
+            let (v4, v6) = dns::fetch_external_ips(&self.cfg.base_url, family).await;
             external_ipv4 = v4.clone();
             external_ipv6 = v6.clone();
             event_tx
@@ -249,21 +432,48 @@ impl TestEngine {
 
         // Traceroute
         if self.cfg.traceroute {
-            if let Some(hostname) = dns::extract_hostname(&self.cfg.base_url) {
+            if let Some((hostname, port)) = tls::extract_host_port(&self.cfg.base_url) {
                 event_tx
                     .send(TestEvent::Info {
                         message: format!(
-                            "Running traceroute to {} (max {} hops)...",
-                            hostname, self.cfg.traceroute_max_hops
+                            "Running {} traceroute to {} (max {} hops)...",
+                            self.cfg.traceroute_mode.label(),
+                            hostname,
+                            self.cfg.traceroute_max_hops
                         ),
                     })
                     .await
                     .ok();
 
-                match traceroute::run_traceroute(&hostname, self.cfg.traceroute_max_hops, &event_tx)
-                    .await
+                match traceroute::run_traceroute(
+                    &hostname,
+                    port,
+                    self.cfg.traceroute_max_hops,
+                    self.cfg.traceroute_mode,
+                    family,
+                    &event_tx,
+                )
+                .await
                 {
-                    Ok(summary) => {
+                    Ok(mut summary) => {
+                        if let Some(ref path) = self.cfg.asn_table {
+                            let path = path.clone();
+                            match tokio::task::spawn_blocking(move || asn::AsnTable::load(&path))
+                                .await
+                                .context("ASN table task failed")
+                                .and_then(|r| r)
+                            {
+                                Ok(table) => table.annotate(&mut summary.hops),
+                                Err(e) => {
+                                    event_tx
+                                        .send(TestEvent::Info {
+                                            message: format!("ASN annotation skipped: {:#}", e),
+                                        })
+                                        .await
+                                        .ok();
+                                }
+                            }
+                        }
                         event_tx
                             .send(TestEvent::TracerouteComplete {
                                 summary: summary.clone(),
@@ -284,6 +494,32 @@ impl TestEngine {
             }
         }
 
+        // Path monitor keeps probing in the background through the load phases
+        let mut path_monitor = None;
+        if self.cfg.path_monitor {
+            let started = dns::extract_hostname(&self.cfg.base_url)
+                .context("Base URL has no hostname")
+                .and_then(|hostname| traceroute::resolve_destination(&hostname, family))
+                .and_then(|ip| {
+                    path_monitor::PathMonitor::start(
+                        ip,
+                        self.cfg.traceroute_max_hops,
+                        event_tx.clone(),
+                    )
+                });
+            match started {
+                Ok(monitor) => path_monitor = Some(monitor),
+                Err(e) => {
+                    event_tx
+                        .send(TestEvent::Info {
+                            message: format!("Path monitor unavailable: {:#}", e),
+                        })
+                        .await
+                        .ok();
+                }
+            }
+        }
+
         event_tx
             .send(TestEvent::PhaseStarted {
                 phase: Phase::IdleLatency,
@@ -304,6 +540,153 @@ impl TestEngine {
         )
         .await?;
 
+        // Connection setup vs warm round trip, while the link is still idle
+        let mut fresh_connection_latency = None;
+        let mut warm_connection_latency = None;
+        if self.cfg.connection_probes {
+            if self.cfg.http_version.is_http3() || self.cfg.proxy.is_some() {
+                event_tx
+                    .send(TestEvent::Info {
+                        message: "Connection probes are not available over HTTP/3 or a proxy"
+                            .into(),
+                    })
+                    .await
+                    .ok();
+            } else {
+                event_tx
+                    .send(TestEvent::Info {
+                        message: format!(
+                            "Probing latency over {} fresh and {} warm connection samples...",
+                            self.cfg.connection_probe_samples, self.cfg.connection_probe_samples
+                        ),
+                    })
+                    .await
+                    .ok();
+
+                // Each sample resolves, connects and handshakes from scratch,
+                // with no TLS session cache to resume from
+                let fresh = connection_timing::FreshTarget::new(
+                    &self.cfg.base_url,
+                    &self.cfg.user_agent,
+                    family,
+                    client.local_ip,
+                    0,
+                )?;
+                let timeout = Duration::from_millis(self.cfg.probe_timeout_ms);
+                fresh_connection_latency = Some(
+                    latency::run_probe_series(
+                        || async {
+                            let stages = tokio::time::timeout(timeout, fresh.sample()).await;
+                            stages.ok()?.ok().map(|s| s.total_ms())
+                        },
+                        self.cfg.connection_probe_samples,
+                        self.cfg.probe_interval_ms,
+                        paused.clone(),
+                        cancel.clone(),
+                    )
+                    .await,
+                );
+
+                let warm = cloudflare::CloudflareClient::with_idle_pool(&self.cfg, 1)?;
+                // Opens the connection every later sample reuses
+                let _ = warm.probe_latency_ms(None, self.cfg.probe_timeout_ms).await;
+                warm_connection_latency = Some(
+                    latency::run_probe_series(
+                        || async {
+                            let probe = warm.probe_latency_ms(None, self.cfg.probe_timeout_ms);
+                            probe.await.ok().map(|(ms, _)| ms)
+                        },
+                        self.cfg.connection_probe_samples,
+                        self.cfg.probe_interval_ms,
+                        paused.clone(),
+                        cancel.clone(),
+                    )
+                    .await,
+                );
+            }
+        }
+
+        // With --responsiveness, fresh-connection probes for the full RPM
+        // figure run alongside each throughput phase (own connections, so
+        // not via a proxy)
+        let fresh_target = (self.cfg.measure_responsiveness && self.cfg.proxy.is_none())
+            .then(|| {
+                connection_timing::FreshTarget::new(
+                    &self.cfg.base_url,
+                    &self.cfg.user_agent,
+                    family,
+                    client.local_ip,
+                    0,
+                )
+            })
+            .and_then(Result::ok)
+            .map(Arc::new);
+        let spawn_fresh_probes = |duration| {
+            fresh_target.clone().map(|target| {
+                tokio::spawn(responsiveness::run_fresh_probes(
+                    target,
+                    duration,
+                    self.cfg.probe_interval_ms,
+                    paused.clone(),
+                    cancel.clone(),
+                ))
+            })
+        };
+
+        // With --turn-relay, TURN credentials from the speed-test service.
+        // The STUN probes then use the same servers; otherwise, or without
+        // credentials, they use turn.cloudflare.com.
+        let turn = if !self.cfg.measure_turn_relay {
+            None
+        } else {
+            match cloudflare::fetch_turn(&client).await {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    event_tx
+                        .send(TestEvent::Info {
+                            message: format!("TURN credentials unavailable: {:#}", e),
+                        })
+                        .await
+                        .ok();
+                    None
+                }
+            }
+        };
+        // Explicit --stun-server targets take their place, each probed in
+        // turn; the first one also serves the probes under load
+        let stun_targets: Vec<crate::model::TurnInfo> = if self.cfg.stun_servers.is_empty() {
+            vec![turn.clone().unwrap_or_else(|| crate::model::TurnInfo {
+                urls: vec!["stun:turn.cloudflare.com:3478".to_string()],
+                username: None,
+                credential: None,
+            })]
+        } else {
+            self.cfg
+                .stun_servers
+                .iter()
+                .map(|url| crate::model::TurnInfo {
+                    urls: vec![url.clone()],
+                    username: None,
+                    credential: None,
+                })
+                .collect()
+        };
+        let stun_info = stun_targets[0].clone();
+        // With --udp-under-load the STUN probe sends for the whole phase. Its
+        // progress events go nowhere so they don't mix with the phase display.
+        let spawn_loaded_udp = |duration: Duration| {
+            self.cfg.udp_under_load.then(|| {
+                let info = stun_info.clone();
+                let cfg = self.cfg.clone();
+                let attempts = (duration.as_secs_f64() * cfg.udp_pps.max(1) as f64).ceil() as u64;
+                tokio::spawn(async move {
+                    let (discard_tx, _) = mpsc::channel(1);
+                    turn_udp::run_udp_like_loss_probe(&info, &cfg, attempts, &discard_tx, None)
+                        .await
+                })
+            })
+        };
+
         event_tx
             .send(TestEvent::PhaseStarted {
                 phase: Phase::Download,
@@ -311,14 +694,18 @@ impl TestEngine {
             .await
             .ok();
 
-        let (download, loaded_latency_download) = throughput::run_download_with_loaded_latency(
-            &client,
-            &self.cfg,
-            &event_tx,
-            paused.clone(),
-            cancel.clone(),
-        )
-        .await?;
+        let download_fresh = spawn_fresh_probes(self.cfg.download_duration);
+        let download_udp = spawn_loaded_udp(self.cfg.download_duration);
+        let (download, loaded_latency_download, download_tcp) =
+            throughput::run_download_with_loaded_latency(
+                &client,
+                &self.cfg,
+                &event_tx,
+                paused.clone(),
+                cancel.clone(),
+            )
+            .await?;
+        let loaded_udp_download = join_loaded_udp(download_udp, Phase::Download, &event_tx).await;
 
         event_tx
             .send(TestEvent::PhaseStarted {
@@ -328,21 +715,109 @@ impl TestEngine {
             .ok();
 
         // Prefetch DNS for STUN server during upload to eliminate delay before packet loss phase
+        let stun_target = turn_udp::stun_host_port(&stun_info).ok();
         let stun_dns_handle = tokio::spawn(async move {
-            tokio::net::lookup_host(("turn.cloudflare.com", 3478_u16))
+            let (host, port) = stun_target?;
+            network_bind::lookup_host(&host, port, family)
                 .await
                 .ok()
-                .and_then(|mut addrs| addrs.next())
+                .and_then(|addrs| addrs.into_iter().next())
         });
 
-        let (upload, loaded_latency_upload) = throughput::run_upload_with_loaded_latency(
-            &client,
-            &self.cfg,
-            &event_tx,
-            paused,
-            cancel.clone(),
-        )
-        .await?;
+        let upload_fresh = spawn_fresh_probes(self.cfg.upload_duration);
+        let upload_udp = spawn_loaded_udp(self.cfg.upload_duration);
+        let (upload, loaded_latency_upload, upload_tcp) =
+            throughput::run_upload_with_loaded_latency(
+                &client,
+                &self.cfg,
+                &event_tx,
+                paused.clone(),
+                cancel.clone(),
+            )
+            .await?;
+        let loaded_udp_upload = join_loaded_udp(upload_udp, Phase::Upload, &event_tx).await;
+        let loaded_udp = (loaded_udp_download.is_some() || loaded_udp_upload.is_some()).then_some(
+            LoadedUdpSummary {
+                download: loaded_udp_download,
+                upload: loaded_udp_upload,
+            },
+        );
+        let fresh_tls = fresh_target.as_ref().is_some_and(|t| t.uses_tls());
+        let responsiveness = Responsiveness {
+            download: responsiveness::summarize(
+                &join_fresh_probes(download_fresh).await,
+                fresh_tls,
+                &loaded_latency_download,
+            ),
+            upload: responsiveness::summarize(
+                &join_fresh_probes(upload_fresh).await,
+                fresh_tls,
+                &loaded_latency_upload,
+            ),
+        };
+        let responsiveness = (responsiveness.download.is_some() || responsiveness.upload.is_some())
+            .then_some(responsiveness);
+        let bufferbloat = crate::stats::bufferbloat_summary(
+            &idle_latency,
+            &loaded_latency_download,
+            &loaded_latency_upload,
+            self.cfg.bufferbloat_thresholds,
+        );
+        let tcp_telemetry =
+            (download_tcp.is_some() || upload_tcp.is_some()).then_some(TcpTelemetry {
+                download: download_tcp,
+                upload: upload_tcp,
+            });
+
+        // Optional one-connection comparison, on a client with its own pool
+        let mut single_stream_download = None;
+        let mut single_stream_upload = None;
+        if self.cfg.single_stream {
+            event_tx
+                .send(TestEvent::PhaseStarted {
+                    phase: Phase::SingleStream,
+                })
+                .await
+                .ok();
+
+            let single = cloudflare::CloudflareClient::new(&self.cfg)?;
+            for phase in [Phase::Download, Phase::Upload] {
+                let summary = throughput::run_single_stream(
+                    &single,
+                    &self.cfg,
+                    phase,
+                    &event_tx,
+                    paused.clone(),
+                    cancel.clone(),
+                )
+                .await?;
+                match phase {
+                    Phase::Download => single_stream_download = Some(summary),
+                    _ => single_stream_upload = Some(summary),
+                }
+            }
+        }
+
+        let mut bidirectional = None;
+        if self.cfg.bidirectional {
+            event_tx
+                .send(TestEvent::PhaseStarted {
+                    phase: Phase::Bidirectional,
+                })
+                .await
+                .ok();
+
+            bidirectional = Some(
+                throughput::run_bidirectional_with_loaded_latency(
+                    &client,
+                    &self.cfg,
+                    &event_tx,
+                    paused.clone(),
+                    cancel.clone(),
+                )
+                .await?,
+            );
+        }
 
         event_tx
             .send(TestEvent::PhaseStarted {
@@ -352,28 +827,111 @@ impl TestEngine {
             .ok();
 
         let mut experimental_udp = None;
+        let mut stun_results = Vec::new();
         let mut udp_error = None;
 
-        let info = crate::model::TurnInfo {
-            urls: vec!["stun:turn.cloudflare.com:3478".to_string()],
-            username: None,
-            credential: None,
-        };
+        // Use prefetched DNS (first target only) if available
+        let mut pre_resolved = stun_dns_handle.await.ok().flatten();
+
+        for info in &stun_targets {
+            match turn_udp::run_udp_like_loss_probe(
+                info,
+                &self.cfg,
+                self.cfg.udp_packets,
+                &event_tx,
+                pre_resolved.take(),
+            )
+            .await
+            {
+                Ok(udp) => {
+                    if !self.cfg.stun_servers.is_empty() {
+                        stun_results.push(StunServerResult {
+                            target: info.urls.join(", "),
+                            summary: Some(udp.clone()),
+                            error: None,
+                        });
+                    }
+                    experimental_udp.get_or_insert(udp);
+                }
+                Err(e) => {
+                    let msg = if self.cfg.stun_servers.is_empty() {
+                        format!("UDP probe failed: {e:#}")
+                    } else {
+                        stun_results.push(StunServerResult {
+                            target: info.urls.join(", "),
+                            summary: None,
+                            error: Some(format!("{e:#}")),
+                        });
+                        format!("UDP probe to {} failed: {e:#}", info.urls.join(", "))
+                    };
+                    udp_error = Some(msg.clone());
+                    event_tx.send(TestEvent::Info { message: msg }).await.ok();
+                }
+            }
+        }
+        // Only an error when no server answered at all
+        if experimental_udp.is_some() {
+            udp_error = None;
+        }
 
-        // Use prefetched DNS if available
-        let pre_resolved = stun_dns_handle.await.ok().flatten();
+        let mut nat = None;
+        if self.cfg.measure_nat {
+            event_tx
+                .send(TestEvent::Info {
+                    message: "Detecting NAT behavior...".into(),
+                })
+                .await
+                .ok();
+            match nat::detect_nat(&stun_targets, &self.cfg).await {
+                Ok(summary) => nat = Some(summary),
+                Err(e) => {
+                    event_tx
+                        .send(TestEvent::Info {
+                            message: format!("NAT detection failed: {e:#}"),
+                        })
+                        .await
+                        .ok();
+                }
+            }
+        }
 
-        match turn_udp::run_udp_like_loss_probe(&info, &self.cfg, &event_tx, pre_resolved).await {
-            Ok(udp) => {
-                experimental_udp = Some(udp);
+        // Same probe relayed through a TURN allocation, when we have credentials
+        let mut turn_relay = None;
+        if let Some(info) = turn.as_ref().filter(|t| t.username.is_some()) {
+            event_tx
+                .send(TestEvent::Info {
+                    message: "Measuring UDP through the TURN relay...".into(),
+                })
+                .await
+                .ok();
+            let (discard_tx, _) = mpsc::channel(1);
+            match turn_relay::run_relay_probe(info, &self.cfg, self.cfg.udp_packets, &discard_tx)
+                .await
+            {
+                Ok(summary) => turn_relay = Some(summary),
+                Err(e) => {
+                    event_tx
+                        .send(TestEvent::Info {
+                            message: format!("TURN relay probe failed: {e:#}"),
+                        })
+                        .await
+                        .ok();
+                }
             }
-            Err(e) => {
-                let msg = format!("UDP probe failed: {e:#}");
-                udp_error = Some(msg.clone());
-                event_tx
-                    .send(TestEvent::Info { message: msg })
-                    .await
-                    .ok();
+        }
+
+        let mut path_monitor_summary: Option<PathMonitorSummary> = None;
+        if let Some(monitor) = path_monitor {
+            match monitor.stop().await {
+                Ok(summary) => path_monitor_summary = Some(summary),
+                Err(e) => {
+                    event_tx
+                        .send(TestEvent::Info {
+                            message: format!("Path monitor failed: {:#}", e),
+                        })
+                        .await
+                        .ok();
+                }
             }
         }
 
@@ -391,6 +949,9 @@ impl TestEngine {
         control_handle.abort();
         // Don't await the aborted task - just let it be cleaned up
 
+        // What the download workers saw beats the bytes=0 probe
+        let http_protocol = download.http_protocol.clone().or(http_protocol);
+
         Ok(RunResult {
             version: Some(env!("CARGO_PKG_VERSION").to_string()),
             timestamp_utc: time::OffsetDateTime::now_utc()
@@ -404,10 +965,22 @@ impl TestEngine {
             idle_latency,
             download,
             upload,
+            single_stream_download,
+            single_stream_upload,
+            bidirectional,
             loaded_latency_download,
             loaded_latency_upload,
-            turn: None,
+            fresh_connection_latency,
+            warm_connection_latency,
+            // The credential is short-lived and not worth keeping
+            turn: turn.map(|t| crate::model::TurnInfo {
+                credential: None,
+                ..t
+            }),
             experimental_udp,
+            stun_results,
+            loaded_udp,
+            turn_relay,
             udp_error,
             // Network information - will be populated by TUI when available
             ip: None,
@@ -425,8 +998,17 @@ impl TestEngine {
             // Diagnostic results
             dns: dns_summary,
             tls: tls_summary,
+            pmtu: pmtu_summary,
+            connection_timing: connection_timing_summary,
+            tcp_telemetry,
+            tcp_socket: client.tuned.as_ref().map(|t| t.tuning.settings()),
+            nat,
+            http_protocol,
+            responsiveness,
+            bufferbloat,
             ip_comparison: ip_comparison_result,
             traceroute: traceroute_summary,
+            path_monitor: path_monitor_summary,
         })
     }
 }
@@ -0,0 +1,310 @@
+//! NAT behavior discovery (RFC 5780) with STUN Binding requests
+//!
+//! Mapping: does the NAT keep our public address and port when we talk to a
+//! second server IP (and port)? Filtering: does it let in responses that the
+//! server sends from its alternate IP and/or port (CHANGE-REQUEST)? Both
+//! rely on the server advertising OTHER-ADDRESS. Without it, mapping is
+//! checked against another address of the same or the next STUN target, and
+//! filtering stays unknown. Hairpinning: does a request sent to our own
+//! mapped address come back to us?
+
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::engine::stun::{
+    decode_address, Message, MessageBuilder, ATTR_CHANGE_REQUEST, ATTR_OTHER_ADDRESS,
+    BINDING_REQUEST, SUCCESS_CLASS,
+};
+use crate::engine::turn_udp;
+use crate::model::{NatBehavior, NatSummary, RunConfig, TurnInfo};
+use anyhow::{Context, Result};
+use rand::RngCore;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// Transmissions of each test request, and how long to wait after each one.
+/// Filtering tests expect silence, so this bounds how long they take.
+const REQUEST_ATTEMPTS: u32 = 2;
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// CHANGE-REQUEST flags
+const CHANGE_IP: u8 = 0x04;
+const CHANGE_PORT: u8 = 0x02;
+
+/// Alternate server addresses tried for the mapping test without OTHER-ADDRESS
+const MAX_ALTERNATES: usize = 3;
+
+/// The parts of a Binding response the tests look at
+struct BindingResponse {
+    mapped: SocketAddr,
+    other: Option<SocketAddr>,
+    /// Answered from `dest` although CHANGE-REQUEST asked for another
+    /// address: the server cannot run the filtering tests
+    unchanged: bool,
+}
+
+fn random_txid() -> [u8; 12] {
+    let mut txid = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut txid);
+    txid
+}
+
+/// Send a Binding request to `dest` and wait for its response from `from`,
+/// which differs from `dest` when `change` asks for another IP or port. A
+/// server that ignores CHANGE-REQUEST answers from `dest` instead, which the
+/// response marks as `unchanged`. None on silence.
+async fn binding(
+    sock: &UdpSocket,
+    dest: SocketAddr,
+    change: u8,
+    from: SocketAddr,
+) -> Result<Option<BindingResponse>> {
+    let txid = random_txid();
+    let mut msg = MessageBuilder::new(BINDING_REQUEST, txid);
+    if change != 0 {
+        msg = msg.attr(ATTR_CHANGE_REQUEST, &[0, 0, 0, change]);
+    }
+    let msg = msg.finish();
+
+    let mut buf = [0u8; 1500];
+    for _ in 0..REQUEST_ATTEMPTS {
+        sock.send_to(&msg, dest).await?;
+        let deadline = tokio::time::Instant::now() + REQUEST_TIMEOUT;
+        while let Ok(r) = tokio::time::timeout_at(deadline, sock.recv_from(&mut buf)).await {
+            let (n, source) = r?;
+            if source != from && source != dest {
+                continue;
+            }
+            let Some(resp) = Message::parse(&buf[..n]) else {
+                continue;
+            };
+            if resp.txid != txid || resp.msg_type != BINDING_REQUEST | SUCCESS_CLASS {
+                continue;
+            }
+            let Some(mapped) = resp.mapped_address() else {
+                continue;
+            };
+            return Ok(Some(BindingResponse {
+                mapped,
+                other: resp.attr(ATTR_OTHER_ADDRESS).and_then(decode_address),
+                unchanged: source != from,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Send a Binding request to our own mapped address. The NAT hairpins if
+/// it arrives back on the same socket.
+async fn hairpins(sock: &UdpSocket, mapped: SocketAddr) -> Result<bool> {
+    let txid = random_txid();
+    let msg = MessageBuilder::new(BINDING_REQUEST, txid).finish();
+
+    let mut buf = [0u8; 1500];
+    for _ in 0..REQUEST_ATTEMPTS {
+        sock.send_to(&msg, mapped).await?;
+        let deadline = tokio::time::Instant::now() + REQUEST_TIMEOUT;
+        while let Ok(r) = tokio::time::timeout_at(deadline, sock.recv_from(&mut buf)).await {
+            let (n, _) = r?;
+            if Message::parse(&buf[..n])
+                .is_some_and(|m| m.txid == txid && m.msg_type == BINDING_REQUEST)
+            {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Address of `sock` as seen on this host, with the source IP the OS picks
+/// toward `dest` when the socket is bound to the wildcard address
+fn local_address(sock: &UdpSocket, dest: SocketAddr) -> Result<SocketAddr> {
+    let local = sock.local_addr()?;
+    if !local.ip().is_unspecified() {
+        return Ok(local);
+    }
+    let wildcard = if dest.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let route = std::net::UdpSocket::bind(wildcard)?;
+    route.connect(dest)?;
+    Ok(SocketAddr::new(route.local_addr()?.ip(), local.port()))
+}
+
+/// Classic NAT name for the measured behavior
+fn classify(
+    behind_nat: bool,
+    mapping: Option<NatBehavior>,
+    filtering: Option<NatBehavior>,
+) -> &'static str {
+    if !behind_nat {
+        return "No NAT";
+    }
+    match (mapping, filtering) {
+        (Some(NatBehavior::EndpointIndependent), Some(NatBehavior::EndpointIndependent)) => {
+            "Full cone"
+        }
+        (Some(NatBehavior::EndpointIndependent), Some(NatBehavior::AddressDependent)) => {
+            "Restricted cone"
+        }
+        (Some(NatBehavior::EndpointIndependent), Some(_)) => "Port-restricted cone",
+        (Some(NatBehavior::EndpointIndependent), None) => "Cone",
+        (Some(_), _) => "Symmetric",
+        (None, _) => "Unknown",
+    }
+}
+
+/// Run the RFC 5780 tests against the first of `targets`. Other addresses
+/// of that server, then later targets, stand in for a missing OTHER-ADDRESS
+/// in the mapping test.
+pub async fn detect_nat(targets: &[TurnInfo], cfg: &RunConfig) -> Result<NatSummary> {
+    let family = AddressFamily::from_config(cfg);
+    let (host, port) = turn_udp::stun_host_port(targets.first().context("no STUN server")?)?;
+    let resolved = network_bind::lookup_host(&host, port, family).await?;
+    let server = resolved[0];
+
+    let sock = turn_udp::bind_udp_socket(cfg, server).await?;
+    let local = local_address(&sock, server)?;
+
+    // Test I: the mapping toward the primary address
+    let first = binding(&sock, server, 0, server)
+        .await?
+        .context("no response from STUN server")?;
+    let mapped = first.mapped;
+    let behind_nat = mapped != local;
+    let is_alternate = |a: &SocketAddr| a.is_ipv4() == server.is_ipv4() && a.ip() != server.ip();
+    let other = first.other.filter(is_alternate);
+
+    let mapping = if let Some(other) = other {
+        // Test II: alternate IP, primary port. Test III: alternate IP and port.
+        let alternate_ip = SocketAddr::new(other.ip(), server.port());
+        match binding(&sock, alternate_ip, 0, alternate_ip).await? {
+            None => None,
+            Some(r) if r.mapped == mapped => Some(NatBehavior::EndpointIndependent),
+            Some(second) => match binding(&sock, other, 0, other).await? {
+                Some(r) if r.mapped == second.mapped => Some(NatBehavior::AddressDependent),
+                Some(_) => Some(NatBehavior::AddressAndPortDependent),
+                None => Some(NatBehavior::EndpointDependent),
+            },
+        }
+    } else {
+        let mut alternates = resolved[1..].to_vec();
+        for target in &targets[1..] {
+            if let Ok((host, port)) = turn_udp::stun_host_port(target) {
+                if let Ok(addrs) = network_bind::lookup_host(&host, port, family).await {
+                    alternates.extend(addrs);
+                }
+            }
+        }
+        alternates.retain(is_alternate);
+
+        let mut mapping = None;
+        for alt in alternates.into_iter().take(MAX_ALTERNATES) {
+            if let Some(r) = binding(&sock, alt, 0, alt).await? {
+                mapping = Some(if r.mapped == mapped {
+                    NatBehavior::EndpointIndependent
+                } else {
+                    NatBehavior::EndpointDependent
+                });
+                break;
+            }
+        }
+        mapping
+    };
+
+    // Test II/III of filtering: responses from the alternate IP and port,
+    // then from the alternate port only. A server that ignores CHANGE-REQUEST
+    // leaves filtering unknown.
+    let filtering = match other {
+        Some(other) => match binding(&sock, server, CHANGE_IP | CHANGE_PORT, other).await? {
+            Some(r) if r.unchanged => None,
+            Some(_) => Some(NatBehavior::EndpointIndependent),
+            None => {
+                let alternate_port = SocketAddr::new(server.ip(), other.port());
+                match binding(&sock, server, CHANGE_PORT, alternate_port).await? {
+                    Some(r) if r.unchanged => None,
+                    Some(_) => Some(NatBehavior::AddressDependent),
+                    None => Some(NatBehavior::AddressAndPortDependent),
+                }
+            }
+        },
+        None => None,
+    };
+
+    let hairpinning = if behind_nat {
+        Some(hairpins(&sock, mapped).await?)
+    } else {
+        None
+    };
+
+    Ok(NatSummary {
+        server: server.to_string(),
+        local_address: local.to_string(),
+        mapped_address: mapped.to_string(),
+        behind_nat,
+        mapping,
+        filtering,
+        hairpinning,
+        port_preserved: mapped.port() == local.port(),
+        nat_type: classify(behind_nat, mapping, filtering).to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::stun::tests::{config, stun_responder, stun_target};
+
+    #[test]
+    fn test_classify() {
+        use NatBehavior::*;
+        assert_eq!(classify(false, None, None), "No NAT");
+        assert_eq!(
+            classify(true, Some(EndpointIndependent), Some(EndpointIndependent)),
+            "Full cone"
+        );
+        assert_eq!(
+            classify(true, Some(EndpointIndependent), Some(AddressDependent)),
+            "Restricted cone"
+        );
+        assert_eq!(
+            classify(
+                true,
+                Some(EndpointIndependent),
+                Some(AddressAndPortDependent)
+            ),
+            "Port-restricted cone"
+        );
+        assert_eq!(classify(true, Some(EndpointDependent), None), "Symmetric");
+        assert_eq!(classify(true, None, None), "Unknown");
+    }
+
+    #[tokio::test]
+    async fn test_detect_nat_without_nat() {
+        let server = stun_responder(None).await;
+        let targets = [stun_target(format!("stun:{}", server))];
+
+        let nat = detect_nat(&targets, &config(&[])).await.unwrap();
+        assert!(!nat.behind_nat);
+        assert!(nat.port_preserved);
+        assert_eq!(nat.mapped_address, nat.local_address);
+        assert_eq!(nat.nat_type, "No NAT");
+        // A lone server without OTHER-ADDRESS leaves both behaviors open
+        assert_eq!(nat.mapping, None);
+        assert_eq!(nat.filtering, None);
+    }
+
+    #[tokio::test]
+    async fn test_filtering_unknown_when_change_ignored() {
+        // Nothing listens on the advertised alternate address, and every
+        // response comes back from the primary one: the server cannot run
+        // the filtering tests
+        let server = stun_responder(Some("127.0.0.2:3478".parse().unwrap())).await;
+        let targets = [stun_target(format!("stun:{}", server))];
+
+        let nat = detect_nat(&targets, &config(&[])).await.unwrap();
+        assert_eq!(nat.mapping, None);
+        assert_eq!(nat.filtering, None);
+    }
+}
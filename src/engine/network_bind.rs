@@ -1,51 +1,183 @@
+use crate::model::RunConfig;
 use anyhow::{Context, Result};
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
 
-/// Get the IP address of a network interface using the `if-addrs` crate
-pub fn get_interface_ip(interface: &str) -> Result<IpAddr> {
+/// IP address family a run can be pinned to with `--ipv4-only` / `--ipv6-only`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressFamily {
+    V4,
+    V6,
+}
+
+impl AddressFamily {
+    /// Read the requested family from the run configuration (None = dual-stack).
+    pub fn from_config(cfg: &RunConfig) -> Option<Self> {
+        if cfg.ipv4_only {
+            Some(Self::V4)
+        } else if cfg.ipv6_only {
+            Some(Self::V6)
+        } else {
+            None
+        }
+    }
+
+    pub fn matches(self, ip: &IpAddr) -> bool {
+        match self {
+            Self::V4 => ip.is_ipv4(),
+            Self::V6 => ip.is_ipv6(),
+        }
+    }
+
+    /// Wildcard address of this family, used to pin outgoing sockets.
+    pub fn unspecified(self) -> IpAddr {
+        match self {
+            Self::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            Self::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::V4 => "IPv4",
+            Self::V6 => "IPv6",
+        }
+    }
+}
+
+/// Get the IP address of a network interface using the `if-addrs` crate.
+///
+/// Without a family constraint IPv4 is preferred, falling back to IPv6.
+pub fn get_interface_ip(interface: &str, family: Option<AddressFamily>) -> Result<IpAddr> {
     use if_addrs::get_if_addrs;
 
     let addrs = get_if_addrs().context("Failed to enumerate network interfaces")?;
 
     // Prefer IPv4 addresses
-    for addr in &addrs {
-        if addr.name == interface {
-            if let if_addrs::IfAddr::V4(v4) = &addr.addr {
-                return Ok(IpAddr::V4(v4.ip));
+    if family != Some(AddressFamily::V6) {
+        for addr in &addrs {
+            if addr.name == interface {
+                if let if_addrs::IfAddr::V4(v4) = &addr.addr {
+                    return Ok(IpAddr::V4(v4.ip));
+                }
             }
         }
     }
 
     // Fallback to IPv6 if no IPv4 found
-    for addr in &addrs {
-        if addr.name == interface {
-            if let if_addrs::IfAddr::V6(v6) = &addr.addr {
-                return Ok(IpAddr::V6(v6.ip));
+    if family != Some(AddressFamily::V4) {
+        for addr in &addrs {
+            if addr.name == interface {
+                if let if_addrs::IfAddr::V6(v6) = &addr.addr {
+                    return Ok(IpAddr::V6(v6.ip));
+                }
             }
         }
     }
 
-    Err(anyhow::anyhow!(
-        "Interface {} not found or has no IP address assigned",
-        interface
-    ))
+    match family {
+        Some(f) => Err(anyhow::anyhow!(
+            "Interface {} has no {} address assigned",
+            interface,
+            f.label()
+        )),
+        None => Err(anyhow::anyhow!(
+            "Interface {} not found or has no IP address assigned",
+            interface
+        )),
+    }
 }
 
 /// Resolve binding address from interface name or source IP
 pub fn resolve_bind_address(
     interface: Option<&String>,
     source_ip: Option<&String>,
+    family: Option<AddressFamily>,
 ) -> Result<Option<SocketAddr>> {
     if let Some(ip_str) = source_ip {
         let ip: IpAddr = ip_str.parse().context("Invalid source IP address format")?;
+        if let Some(f) = family {
+            anyhow::ensure!(
+                f.matches(&ip),
+                "Source IP {} is not an {} address",
+                ip,
+                f.label()
+            );
+        }
         return Ok(Some(SocketAddr::new(ip, 0)));
     }
 
     if let Some(iface) = interface {
-        let ip = get_interface_ip(iface)
+        let ip = get_interface_ip(iface, family)
             .with_context(|| format!("Failed to get IP for interface {}", iface))?;
         return Ok(Some(SocketAddr::new(ip, 0)));
     }
 
     Ok(None)
 }
+
+/// Resolve `host:port`, keeping only addresses of the requested family.
+///
+/// Fails with a descriptive error when the name has no address of that family.
+pub async fn lookup_host(
+    host: &str,
+    port: u16,
+    family: Option<AddressFamily>,
+) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .with_context(|| format!("DNS lookup failed for {}", host))?
+        .filter(|a| family.map(|f| f.matches(&a.ip())).unwrap_or(true))
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(match family {
+            Some(f) => anyhow::anyhow!("{} has no {} address", host, f.label()),
+            None => anyhow::anyhow!("{} did not resolve to any address", host),
+        });
+    }
+    Ok(addrs)
+}
+
+/// Check that the local host has a route toward `addr`.
+///
+/// Connecting a UDP socket sends nothing but makes the kernel pick a route and
+/// source address, so a missing default route fails here with ENETUNREACH.
+pub fn ensure_route(addr: SocketAddr, bind: Option<SocketAddr>) -> Result<()> {
+    let family = if addr.is_ipv4() {
+        AddressFamily::V4
+    } else {
+        AddressFamily::V6
+    };
+    let local = bind.unwrap_or_else(|| SocketAddr::new(family.unspecified(), 0));
+    let sock = std::net::UdpSocket::bind(local)
+        .with_context(|| format!("{} is not available on this host", family.label()))?;
+    sock.connect(addr)
+        .with_context(|| format!("No {} route to {}", family.label(), addr.ip()))?;
+    Ok(())
+}
+
+/// DNS resolver for reqwest that drops addresses outside the pinned family,
+/// so every HTTP connection (meta, latency probes, throughput) uses it.
+pub struct FamilyResolver {
+    family: AddressFamily,
+}
+
+impl FamilyResolver {
+    pub fn new(family: AddressFamily) -> Arc<Self> {
+        Arc::new(Self { family })
+    }
+}
+
+impl reqwest::dns::Resolve for FamilyResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let family = self.family;
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = lookup_host(&host, 0, Some(family))
+                .await
+                .map_err(|e| -> Box<dyn std::error::Error + Send + Sync> { e.into() })?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
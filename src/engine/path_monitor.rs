@@ -0,0 +1,347 @@
+//! Continuous path monitor (mtr-style)
+//!
+//! Probes every hop toward the destination once per round for as long as the
+//! test runs, accumulating per-hop loss and RTT statistics. Probes are ICMP Echo
+//! Requests with a fixed identifier and a constant checksum (Paris traceroute),
+//! so routers that hash the ICMP header for ECMP keep every probe on one path.
+
+use crate::engine::traceroute::{
+    build_icmp_packet, build_icmpv6_packet, calculate_icmp_checksum, recv_icmp,
+};
+use crate::model::{PathMonitorHop, PathMonitorSummary, TestEvent};
+use crate::stats::OnlineStats;
+use anyhow::{Context, Result};
+use pnet_packet::icmp::IcmpTypes;
+use pnet_packet::icmpv6::Icmpv6Types;
+use pnet_packet::ip::IpNextHeaderProtocols;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Time between the start of two probing rounds; also the reply deadline
+const ROUND_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Granularity at which the idle part of a round checks for stop requests
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Handle to a running path monitor.
+pub struct PathMonitor {
+    stop: Arc<AtomicBool>,
+    handle: tokio::task::JoinHandle<PathMonitorSummary>,
+}
+
+impl PathMonitor {
+    /// Open the raw socket and start probing in a background thread.
+    ///
+    /// Fails immediately when raw ICMP sockets are not available.
+    pub fn start(
+        destination: IpAddr,
+        max_hops: u8,
+        event_tx: mpsc::Sender<TestEvent>,
+    ) -> Result<Self> {
+        let socket = match destination {
+            IpAddr::V4(_) => Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4))
+                .context("Failed to create raw ICMP socket (need CAP_NET_RAW or root)")?,
+            IpAddr::V6(_) => Socket::new(Domain::IPV6, Type::RAW, Some(Protocol::ICMPV6))
+                .context("Failed to create raw ICMPv6 socket (need CAP_NET_RAW or root)")?,
+        };
+        socket.set_nonblocking(false)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop2 = stop.clone();
+        let handle = tokio::task::spawn_blocking(move || {
+            monitor_loop(&socket, destination, max_hops, &stop2, &event_tx)
+        });
+
+        Ok(Self { stop, handle })
+    }
+
+    /// Stop probing and return the final statistics.
+    pub async fn stop(mut self) -> Result<PathMonitorSummary> {
+        self.stop.store(true, Ordering::Relaxed);
+        (&mut self.handle).await.context("Path monitor task failed")
+    }
+}
+
+impl Drop for PathMonitor {
+    // Runs that end early (cancel, errors) must not leave the thread probing
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Running statistics for one hop.
+#[derive(Default)]
+struct HopStats {
+    ip: Option<IpAddr>,
+    sent: u64,
+    received: u64,
+    best_ms: Option<f64>,
+    worst_ms: Option<f64>,
+    sum_ms: f64,
+    last_ms: Option<f64>,
+    spread: OnlineStats,
+}
+
+impl HopStats {
+    fn record(&mut self, from: IpAddr, rtt_ms: f64) {
+        self.ip.get_or_insert(from);
+        self.received += 1;
+        self.best_ms = Some(self.best_ms.map_or(rtt_ms, |b| b.min(rtt_ms)));
+        self.worst_ms = Some(self.worst_ms.map_or(rtt_ms, |w| w.max(rtt_ms)));
+        self.sum_ms += rtt_ms;
+        self.last_ms = Some(rtt_ms);
+        self.spread.push(rtt_ms);
+    }
+
+    fn summary(&self, hop_number: u8) -> PathMonitorHop {
+        let loss = if self.sent == 0 {
+            0.0
+        } else {
+            (self.sent - self.received) as f64 / self.sent as f64
+        };
+        PathMonitorHop {
+            hop_number,
+            ip_address: self.ip.map(|ip| ip.to_string()),
+            sent: self.sent,
+            received: self.received,
+            loss,
+            best_ms: self.best_ms,
+            avg_ms: (self.received > 0).then(|| self.sum_ms / self.received as f64),
+            worst_ms: self.worst_ms,
+            stddev_ms: self.spread.stddev(),
+            last_ms: self.last_ms,
+        }
+    }
+}
+
+fn monitor_loop(
+    socket: &Socket,
+    destination: IpAddr,
+    max_hops: u8,
+    stop: &AtomicBool,
+    event_tx: &mpsc::Sender<TestEvent>,
+) -> PathMonitorSummary {
+    let icmp_id = std::process::id() as u16;
+    let dest_addr = SocketAddr::new(destination, 0);
+
+    let mut hops: Vec<HopStats> = (0..max_hops).map(|_| HopStats::default()).collect();
+    // Shrinks to the hop where the destination answered
+    let mut path_len = max_hops;
+    let mut seq: u16 = 0;
+    let mut rounds: u64 = 0;
+
+    let summarize = |hops: &[HopStats], path_len: u8, rounds: u64| PathMonitorSummary {
+        destination: destination.to_string(),
+        rounds,
+        hops: hops[..path_len as usize]
+            .iter()
+            .enumerate()
+            .map(|(i, h)| h.summary(i as u8 + 1))
+            .collect(),
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let round_start = Instant::now();
+        let round_end = round_start + ROUND_INTERVAL;
+
+        // One probe per hop, back to back
+        let mut pending: HashMap<u16, (u8, Instant)> = HashMap::new();
+        for ttl in 1..=path_len {
+            seq = seq.wrapping_add(1);
+            let packet = build_paris_probe(destination, icmp_id, seq);
+            let hop_limit_set = match destination {
+                IpAddr::V4(_) => socket.set_ttl(ttl as u32),
+                IpAddr::V6(_) => socket.set_unicast_hops_v6(ttl as u32),
+            };
+            if hop_limit_set.is_err() {
+                continue;
+            }
+            let sent_at = Instant::now();
+            if socket.send_to(&packet, &dest_addr.into()).is_ok() {
+                hops[ttl as usize - 1].sent += 1;
+                pending.insert(seq, (ttl, sent_at));
+            }
+        }
+
+        // Collect replies until the round ends; unanswered probes count as lost
+        while !pending.is_empty() {
+            let remaining = round_end.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+                break;
+            }
+
+            let (data, from) = match recv_icmp(socket, 1280) {
+                Ok(r) => r,
+                Err(_) => break,
+            };
+            let received_at = Instant::now();
+
+            let Some((reached, reply_seq)) =
+                parse_probe_reply(&data, destination.is_ipv4(), icmp_id)
+            else {
+                continue;
+            };
+            let Some((ttl, sent_at)) = pending.remove(&reply_seq) else {
+                continue;
+            };
+
+            let from_ip = from.as_socket().map(|a| a.ip()).unwrap_or(destination);
+            let rtt_ms = received_at.duration_since(sent_at).as_secs_f64() * 1000.0;
+            hops[ttl as usize - 1].record(from_ip, rtt_ms);
+            if reached || from_ip == destination {
+                path_len = path_len.min(ttl);
+            }
+        }
+
+        rounds += 1;
+        let _ = event_tx.blocking_send(TestEvent::PathMonitorUpdate {
+            summary: summarize(&hops, path_len, rounds),
+        });
+
+        // Idle until the next round, staying responsive to stop requests
+        while !stop.load(Ordering::Relaxed) {
+            let remaining = round_end.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            std::thread::sleep(remaining.min(STOP_POLL_INTERVAL));
+        }
+    }
+
+    summarize(&hops, path_len, rounds)
+}
+
+/// Build an echo request whose checksum does not depend on `seq`.
+///
+/// The first payload word is the one's complement of the sequence number, so
+/// the pair always sums to 0xffff and the checksum stays constant.
+fn build_paris_probe(destination: IpAddr, id: u16, seq: u16) -> Vec<u8> {
+    let compensation = (!seq).to_be_bytes();
+    match destination {
+        IpAddr::V4(_) => {
+            let mut packet = build_icmp_packet(id, seq);
+            packet[8..10].copy_from_slice(&compensation);
+            packet[2..4].copy_from_slice(&[0, 0]);
+            let checksum = calculate_icmp_checksum(&packet);
+            packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+            packet
+        }
+        IpAddr::V6(_) => {
+            // The kernel computes the ICMPv6 checksum; the same trick keeps it fixed
+            let mut packet = build_icmpv6_packet(id, seq);
+            packet[8..10].copy_from_slice(&compensation);
+            packet
+        }
+    }
+}
+
+/// Match an ICMP message to one of our probes.
+///
+/// Returns whether the destination itself answered (Echo Reply or Destination
+/// Unreachable) and the probe's sequence number. IPv4 messages include the
+/// outer IP header; IPv6 messages start at the ICMPv6 header.
+fn parse_probe_reply(data: &[u8], ipv4: bool, id: u16) -> Option<(bool, u16)> {
+    let icmp = if ipv4 {
+        data.get((*data.first()? as usize & 0x0f) * 4..)?
+    } else {
+        data
+    };
+
+    let (echo_reply, time_exceeded, unreachable, echo_request, icmp_protocol) = if ipv4 {
+        (
+            IcmpTypes::EchoReply.0,
+            IcmpTypes::TimeExceeded.0,
+            IcmpTypes::DestinationUnreachable.0,
+            IcmpTypes::EchoRequest.0,
+            IpNextHeaderProtocols::Icmp.0,
+        )
+    } else {
+        (
+            Icmpv6Types::EchoReply.0,
+            Icmpv6Types::TimeExceeded.0,
+            Icmpv6Types::DestinationUnreachable.0,
+            Icmpv6Types::EchoRequest.0,
+            IpNextHeaderProtocols::Icmpv6.0,
+        )
+    };
+
+    let icmp_type = *icmp.first()?;
+    let (reached, original) = if icmp_type == echo_reply {
+        (true, icmp)
+    } else if icmp_type == time_exceeded || icmp_type == unreachable {
+        // Quoted IP header of our probe, followed by the start of the echo request
+        let inner = icmp.get(8..)?;
+        let (inner_len, protocol) = if ipv4 {
+            ((*inner.first()? as usize & 0x0f) * 4, *inner.get(9)?)
+        } else {
+            (40, *inner.get(6)?)
+        };
+        if protocol != icmp_protocol {
+            return None;
+        }
+        let original = inner.get(inner_len..)?;
+        if *original.first()? != echo_request {
+            return None;
+        }
+        (icmp_type == unreachable, original)
+    } else {
+        return None;
+    };
+
+    let header = original.get(4..8)?;
+    if u16::from_be_bytes([header[0], header[1]]) != id {
+        return None;
+    }
+    Some((reached, u16::from_be_bytes([header[2], header[3]])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_paris_probe_checksum_is_constant() {
+        let dest = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let first = build_paris_probe(dest, 0x1234, 1);
+        for seq in [2, 255, 256, 0x8000, 0xffff] {
+            let probe = build_paris_probe(dest, 0x1234, seq);
+            assert_eq!(probe[2..4], first[2..4]);
+            assert_eq!(calculate_icmp_checksum(&probe), 0);
+        }
+    }
+
+    #[test]
+    fn test_parse_probe_reply() {
+        let dest = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let probe = build_paris_probe(dest, 0x1234, 7);
+
+        // IPv4 Time Exceeded: outer IP header, ICMP header, quoted IP header, probe
+        let mut ip_header = vec![0u8; 20];
+        ip_header[0] = 0x45;
+        ip_header[9] = IpNextHeaderProtocols::Icmp.0;
+        let mut exceeded = ip_header.clone();
+        exceeded.extend_from_slice(&[IcmpTypes::TimeExceeded.0, 0, 0, 0, 0, 0, 0, 0]);
+        exceeded.extend_from_slice(&ip_header);
+        exceeded.extend_from_slice(&probe[..8]);
+        assert_eq!(parse_probe_reply(&exceeded, true, 0x1234), Some((false, 7)));
+        assert_eq!(parse_probe_reply(&exceeded, true, 0x4321), None);
+
+        // IPv4 Echo Reply from the destination
+        let mut reply = ip_header.clone();
+        reply.extend_from_slice(&probe);
+        reply[20] = IcmpTypes::EchoReply.0;
+        assert_eq!(parse_probe_reply(&reply, true, 0x1234), Some((true, 7)));
+
+        // IPv6 Echo Reply (no outer header)
+        let dest6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let mut reply6 = build_paris_probe(dest6, 0x1234, 9);
+        reply6[0] = Icmpv6Types::EchoReply.0;
+        assert_eq!(parse_probe_reply(&reply6, false, 0x1234), Some((true, 9)));
+    }
+}
@@ -0,0 +1,515 @@
+//! Path MTU discovery module
+//!
+//! Binary-searches the largest packet that reaches the edge unfragmented by
+//! sending ICMP Echo Requests with Don't Fragment set over a raw socket, and
+//! reads the MSS negotiated on a real TCP connection to the speed-test port.
+//! When ICMP probing is not permitted the path MTU is derived from the MSS,
+//! which PPPoE routers and tunnel endpoints usually clamp to fit the link.
+
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::engine::traceroute::{calculate_icmp_checksum, recv_icmp};
+use crate::model::{PmtuMethod, PmtuSummary};
+use anyhow::{Context, Result};
+use pnet_packet::icmp::IcmpTypes;
+use pnet_packet::icmpv6::Icmpv6Types;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// How long to wait for the answer to one probe
+const PROBE_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Probes per size before the size is considered lost
+const PROBE_ATTEMPTS: usize = 2;
+
+/// Timeout for the TCP connection used to observe the MSS
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound when the route MTU cannot be read
+const DEFAULT_LINK_MTU: u32 = 1500;
+
+/// ICMP "Fragmentation Needed" code of Destination Unreachable
+const ICMP_FRAG_NEEDED: u8 = 4;
+
+/// Header sizes of one address family.
+#[derive(Debug, Clone, Copy)]
+struct Headers {
+    ip: u32,
+    /// Smallest MTU every link of this family must support
+    min_mtu: u32,
+}
+
+impl Headers {
+    fn for_ip(ip: &IpAddr) -> Self {
+        if ip.is_ipv4() {
+            Self {
+                ip: 20,
+                min_mtu: 576,
+            }
+        } else {
+            Self {
+                ip: 40,
+                min_mtu: 1280,
+            }
+        }
+    }
+
+    /// IP + TCP header bytes in front of a full-size segment (no options)
+    fn tcp_overhead(self) -> u32 {
+        self.ip + 20
+    }
+}
+
+/// Measure the path MTU toward `hostname:port`.
+pub async fn measure_pmtu(
+    hostname: &str,
+    port: u16,
+    family: Option<AddressFamily>,
+    bind: Option<SocketAddr>,
+) -> Result<PmtuSummary> {
+    let addrs = network_bind::lookup_host(hostname, port, family).await?;
+    let addr = addrs[0];
+    let headers = Headers::for_ip(&addr.ip());
+
+    let tcp_mss = tokio::task::spawn_blocking(move || observe_mss(addr, bind))
+        .await
+        .context("MSS observation task failed")?;
+    let local_mtu = route_mtu(addr.ip(), bind);
+
+    let upper = local_mtu.unwrap_or(DEFAULT_LINK_MTU);
+    let icmp = tokio::task::spawn_blocking(move || icmp_search(addr.ip(), bind, upper))
+        .await
+        .context("PMTU probe task failed")?;
+
+    match (icmp, tcp_mss) {
+        (Ok(search), tcp_mss) => {
+            let mss = search.pmtu - headers.tcp_overhead();
+            // Silent drops mean the network ate oversized packets without
+            // telling anyone; that only hurts if TCP would send them anyway.
+            let black_hole_suspected =
+                search.silent_drop && tcp_mss.as_ref().map_or(true, |m| *m > mss);
+            Ok(PmtuSummary {
+                method: PmtuMethod::Icmp,
+                pmtu: search.pmtu,
+                mss,
+                local_mtu,
+                tcp_mss: tcp_mss.ok(),
+                black_hole_suspected,
+            })
+        }
+        (Err(_), Ok(observed)) => Ok(PmtuSummary {
+            method: PmtuMethod::TcpMss,
+            pmtu: observed + headers.tcp_overhead(),
+            mss: observed,
+            local_mtu,
+            tcp_mss: Some(observed),
+            black_hole_suspected: false,
+        }),
+        (Err(icmp_err), Err(tcp_err)) => Err(anyhow::anyhow!(
+            "ICMP probing failed ({:#}) and TCP MSS unavailable ({:#})",
+            icmp_err,
+            tcp_err
+        )),
+    }
+}
+
+/// Outcome of the ICMP binary search.
+struct IcmpSearch {
+    pmtu: u32,
+    /// Some larger probe vanished without a Fragmentation Needed / Packet Too Big
+    silent_drop: bool,
+}
+
+/// What happened to one DF probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeOutcome {
+    /// The destination answered, so the packet fit the whole path
+    Reply,
+    /// A router reported the next-hop MTU (0 if it did not say)
+    TooBig(u32),
+    /// The local stack refused to send a packet this large
+    LocalTooBig,
+    /// Nothing came back
+    Lost,
+}
+
+fn icmp_search(dest: IpAddr, bind: Option<SocketAddr>, upper: u32) -> Result<IcmpSearch> {
+    let headers = Headers::for_ip(&dest);
+    let socket = match dest {
+        IpAddr::V4(_) => Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4))
+            .context("Failed to create raw ICMP socket (need CAP_NET_RAW or root)")?,
+        IpAddr::V6(_) => Socket::new(Domain::IPV6, Type::RAW, Some(Protocol::ICMPV6))
+            .context("Failed to create raw ICMPv6 socket (need CAP_NET_RAW or root)")?,
+    };
+    if let Some(local) = bind {
+        socket.bind(&SocketAddr::new(local.ip(), 0).into())?;
+    }
+    set_dont_fragment(&socket, dest.is_ipv4())?;
+
+    let mut prober = Prober {
+        socket,
+        dest: SocketAddr::new(dest, 0),
+        id: std::process::id() as u16,
+        seq: 0,
+    };
+
+    let mut lo = headers.min_mtu;
+    anyhow::ensure!(
+        prober.probe(lo)? == ProbeOutcome::Reply,
+        "{} does not answer ICMP echo requests",
+        dest
+    );
+
+    // Most paths carry the full link MTU; check that before searching
+    let mut silent_drop = false;
+    let mut hi = match prober.probe(upper)? {
+        ProbeOutcome::Reply => {
+            return Ok(IcmpSearch {
+                pmtu: upper,
+                silent_drop: false,
+            })
+        }
+        ProbeOutcome::TooBig(mtu) if mtu >= lo && mtu < upper => mtu,
+        ProbeOutcome::Lost => {
+            silent_drop = true;
+            upper - 1
+        }
+        _ => upper - 1,
+    };
+
+    // Invariant: `lo` got a reply, everything above `hi` did not
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        match prober.probe(mid)? {
+            ProbeOutcome::Reply => lo = mid,
+            ProbeOutcome::TooBig(mtu) if mtu >= lo && mtu < mid => hi = mtu,
+            ProbeOutcome::Lost => {
+                silent_drop = true;
+                hi = mid - 1;
+            }
+            _ => hi = mid - 1,
+        }
+    }
+
+    Ok(IcmpSearch {
+        pmtu: lo,
+        silent_drop,
+    })
+}
+
+struct Prober {
+    socket: Socket,
+    dest: SocketAddr,
+    id: u16,
+    seq: u16,
+}
+
+impl Prober {
+    /// Send an echo request whose IP packet is `size` bytes and classify the answer.
+    fn probe(&mut self, size: u32) -> Result<ProbeOutcome> {
+        let headers = Headers::for_ip(&self.dest.ip());
+        let ipv4 = self.dest.is_ipv4();
+
+        let mut last = ProbeOutcome::Lost;
+        for _ in 0..PROBE_ATTEMPTS {
+            self.seq = self.seq.wrapping_add(1);
+            let packet = build_echo_request(ipv4, self.id, self.seq, (size - headers.ip) as usize);
+
+            if let Err(e) = self.socket.send_to(&packet, &self.dest.into()) {
+                if e.raw_os_error() == Some(libc::EMSGSIZE) {
+                    return Ok(ProbeOutcome::LocalTooBig);
+                }
+                return Err(e).context("Failed to send PMTU probe");
+            }
+
+            last = self.wait_reply(ipv4)?;
+            if last != ProbeOutcome::Lost {
+                break;
+            }
+        }
+        Ok(last)
+    }
+
+    fn wait_reply(&self, ipv4: bool) -> Result<ProbeOutcome> {
+        let deadline = Instant::now() + PROBE_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(ProbeOutcome::Lost);
+            }
+            self.socket.set_read_timeout(Some(remaining))?;
+
+            let data = match recv_icmp(&self.socket, 9216) {
+                Ok((data, _)) => data,
+                Err(_) => return Ok(ProbeOutcome::Lost),
+            };
+
+            if let Some(outcome) = parse_pmtu_reply(&data, ipv4, self.id, self.seq) {
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+/// Build an echo request of `len` bytes (ICMP header included).
+///
+/// The ICMPv4 checksum is filled in here; the kernel computes the ICMPv6 one.
+fn build_echo_request(ipv4: bool, id: u16, seq: u16, len: usize) -> Vec<u8> {
+    let mut packet = vec![0u8; len.max(8)];
+    packet[0] = if ipv4 {
+        IcmpTypes::EchoRequest.0
+    } else {
+        Icmpv6Types::EchoRequest.0
+    };
+    packet[4..6].copy_from_slice(&id.to_be_bytes());
+    packet[6..8].copy_from_slice(&seq.to_be_bytes());
+    for (i, b) in packet.iter_mut().enumerate().skip(8) {
+        *b = i as u8;
+    }
+    if ipv4 {
+        let checksum = calculate_icmp_checksum(&packet);
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+    packet
+}
+
+/// Classify an ICMP message received while waiting for probe `id`/`seq`.
+///
+/// IPv4 messages include the outer IP header; IPv6 messages start at the
+/// ICMPv6 header. Errors are matched through the echo request they quote.
+fn parse_pmtu_reply(data: &[u8], ipv4: bool, id: u16, seq: u16) -> Option<ProbeOutcome> {
+    let icmp = if ipv4 {
+        data.get((*data.first()? as usize & 0x0f) * 4..)?
+    } else {
+        data
+    };
+    let icmp_type = *icmp.first()?;
+    let code = *icmp.get(1)?;
+
+    let matches = |echo: &[u8], request_type: u8| {
+        echo.len() >= 8
+            && echo[0] == request_type
+            && u16::from_be_bytes([echo[4], echo[5]]) == id
+            && u16::from_be_bytes([echo[6], echo[7]]) == seq
+    };
+
+    if ipv4 {
+        if icmp_type == IcmpTypes::EchoReply.0 {
+            return matches(icmp, IcmpTypes::EchoReply.0).then_some(ProbeOutcome::Reply);
+        }
+        if icmp_type != IcmpTypes::DestinationUnreachable.0 || code != ICMP_FRAG_NEEDED {
+            return None;
+        }
+        // Next-hop MTU lives in the low half of the "unused" word (RFC 1191)
+        let mtu = u16::from_be_bytes([*icmp.get(6)?, *icmp.get(7)?]) as u32;
+        let inner = icmp.get(8..)?;
+        let quoted = inner.get((*inner.first()? as usize & 0x0f) * 4..)?;
+        matches(quoted, IcmpTypes::EchoRequest.0).then_some(ProbeOutcome::TooBig(mtu))
+    } else {
+        if icmp_type == Icmpv6Types::EchoReply.0 {
+            return matches(icmp, Icmpv6Types::EchoReply.0).then_some(ProbeOutcome::Reply);
+        }
+        if icmp_type != Icmpv6Types::PacketTooBig.0 {
+            return None;
+        }
+        let mtu = u32::from_be_bytes([*icmp.get(4)?, *icmp.get(5)?, *icmp.get(6)?, *icmp.get(7)?]);
+        let quoted = icmp.get(8 + 40..)?;
+        matches(quoted, Icmpv6Types::EchoRequest.0).then_some(ProbeOutcome::TooBig(mtu))
+    }
+}
+
+/// Set Don't Fragment and make the kernel ignore its cached path MTU, so
+/// every probe size is actually sent.
+#[cfg(target_os = "linux")]
+fn set_dont_fragment(socket: &Socket, ipv4: bool) -> Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let set = |level: libc::c_int, name: libc::c_int, value: libc::c_int| {
+        let rc = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    };
+
+    if ipv4 {
+        set(
+            libc::IPPROTO_IP,
+            libc::IP_MTU_DISCOVER,
+            libc::IP_PMTUDISC_PROBE,
+        )
+        .context("Failed to set Don't Fragment")?;
+    } else {
+        set(
+            libc::IPPROTO_IPV6,
+            libc::IPV6_MTU_DISCOVER,
+            libc::IPV6_PMTUDISC_PROBE,
+        )
+        .context("Failed to disable IPv6 fragmentation")?;
+        set(libc::IPPROTO_IPV6, libc::IPV6_DONTFRAG, 1)
+            .context("Failed to disable IPv6 fragmentation")?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_dont_fragment(_socket: &Socket, _ipv4: bool) -> Result<()> {
+    Err(anyhow::anyhow!("DF probes are only supported on Linux"))
+}
+
+/// MTU of the route toward `dest` as the kernel sees it (link MTU or cached PMTU).
+#[cfg(target_os = "linux")]
+fn route_mtu(dest: IpAddr, bind: Option<SocketAddr>) -> Option<u32> {
+    use std::os::fd::AsRawFd;
+
+    let local = bind.map(|b| SocketAddr::new(b.ip(), 0)).unwrap_or_else(|| {
+        SocketAddr::new(
+            if dest.is_ipv4() {
+                AddressFamily::V4.unspecified()
+            } else {
+                AddressFamily::V6.unspecified()
+            },
+            0,
+        )
+    });
+    let sock = std::net::UdpSocket::bind(local).ok()?;
+    sock.connect(SocketAddr::new(dest, 9)).ok()?;
+
+    let (level, name) = if dest.is_ipv4() {
+        (libc::IPPROTO_IP, libc::IP_MTU)
+    } else {
+        (libc::IPPROTO_IPV6, libc::IPV6_MTU)
+    };
+    let mut mtu: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            sock.as_raw_fd(),
+            level,
+            name,
+            &mut mtu as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (rc == 0 && mtu > 0).then_some(mtu as u32)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn route_mtu(_dest: IpAddr, _bind: Option<SocketAddr>) -> Option<u32> {
+    None
+}
+
+/// Open a TCP connection and read the MSS the two ends settled on.
+fn observe_mss(addr: SocketAddr, bind: Option<SocketAddr>) -> Result<u32> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if let Some(local) = bind {
+        socket.bind(&SocketAddr::new(local.ip(), 0).into())?;
+    }
+    socket
+        .connect_timeout(&addr.into(), CONNECT_TIMEOUT)
+        .with_context(|| format!("TCP connection to {} failed", addr))?;
+    negotiated_mss(&socket)
+}
+
+/// MSS of an established connection, excluding the per-segment TCP options.
+#[cfg(target_os = "linux")]
+fn negotiated_mss(socket: &Socket) -> Result<u32> {
+    use std::os::fd::AsRawFd;
+
+    /// `TCPI_OPT_TIMESTAMPS` from linux/tcp.h
+    const TCPI_OPT_TIMESTAMPS: u8 = 1;
+    /// Timestamp option bytes carried on every segment once negotiated
+    const TIMESTAMP_OPTION_LEN: u32 = 12;
+
+    let mut info: libc::tcp_info = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            &mut info as *mut libc::tcp_info as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to read TCP_INFO");
+    }
+
+    // snd_mss already has the option space taken out; add it back to get the
+    // MSS as advertised/clamped on the SYN
+    let options = if info.tcpi_options & TCPI_OPT_TIMESTAMPS != 0 {
+        TIMESTAMP_OPTION_LEN
+    } else {
+        0
+    };
+    Ok(info.tcpi_snd_mss + options)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn negotiated_mss(socket: &Socket) -> Result<u32> {
+    socket.mss().context("Failed to read TCP MSS")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pmtu_reply() {
+        let probe = build_echo_request(true, 0x1234, 5, 1472);
+        assert_eq!(probe.len(), 1472);
+        assert_eq!(calculate_icmp_checksum(&probe), 0);
+
+        let mut ip_header = vec![0u8; 20];
+        ip_header[0] = 0x45;
+
+        // Echo Reply
+        let mut reply = ip_header.clone();
+        reply.extend_from_slice(&probe);
+        reply[20] = IcmpTypes::EchoReply.0;
+        assert_eq!(
+            parse_pmtu_reply(&reply, true, 0x1234, 5),
+            Some(ProbeOutcome::Reply)
+        );
+        assert_eq!(parse_pmtu_reply(&reply, true, 0x1234, 6), None);
+
+        // Fragmentation Needed with next-hop MTU 1492, quoting the probe
+        let mut frag = ip_header.clone();
+        frag.extend_from_slice(&[
+            IcmpTypes::DestinationUnreachable.0,
+            ICMP_FRAG_NEEDED,
+            0,
+            0,
+            0,
+            0,
+            0x05,
+            0xd4,
+        ]);
+        frag.extend_from_slice(&ip_header);
+        frag.extend_from_slice(&probe[..8]);
+        assert_eq!(
+            parse_pmtu_reply(&frag, true, 0x1234, 5),
+            Some(ProbeOutcome::TooBig(1492))
+        );
+
+        // ICMPv6 Packet Too Big with MTU 1280
+        let probe6 = build_echo_request(false, 0x1234, 5, 1400);
+        let mut too_big = vec![Icmpv6Types::PacketTooBig.0, 0, 0, 0, 0, 0, 0x05, 0x00];
+        too_big.extend_from_slice(&[0u8; 40]);
+        too_big.extend_from_slice(&probe6[..8]);
+        assert_eq!(
+            parse_pmtu_reply(&too_big, false, 0x1234, 5),
+            Some(ProbeOutcome::TooBig(1280))
+        );
+    }
+}
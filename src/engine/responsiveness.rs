@@ -0,0 +1,128 @@
+//! Responsiveness in round-trips per minute (RPM), following the IETF
+//! "Responsiveness under Working Conditions" draft
+//!
+//! While a throughput phase saturates the link, two kinds of probe run:
+//! fresh-connection probes that time the TCP, TLS and HTTP round trips of a
+//! brand-new connection, and the existing loaded-latency probes, which ride
+//! the already loaded connections (in-flight probes). Each kind is reduced to
+//! a 95% trimmed mean, and
+//!
+//!   RPM = 60000 / (1/6 * (TM(tcp) + TM(tls) + TM(http)) + 1/2 * TM(in-flight))
+
+use crate::engine::connection_timing::FreshTarget;
+use crate::engine::wait_if_paused_or_cancelled;
+use crate::metrics::trimmed_mean_95;
+use crate::model::{ConnectionStages, LatencySummary, RpmSummary};
+use std::sync::{atomic::AtomicBool, Arc};
+use std::time::{Duration, Instant};
+
+/// A fresh connection taking longer than this counts as failed. Longer than
+/// the loaded-latency timeout, since it spans three round trips under load.
+const FRESH_PROBE_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Run fresh-connection probes back to back, at most one per `interval_ms`,
+/// for `total_duration`. Failed or timed-out probes are dropped.
+pub async fn run_fresh_probes(
+    target: Arc<FreshTarget>,
+    total_duration: Duration,
+    interval_ms: u64,
+    paused: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+) -> Vec<ConnectionStages> {
+    let start = Instant::now();
+    let mut samples = Vec::new();
+
+    while start.elapsed() < total_duration {
+        if wait_if_paused_or_cancelled(&paused, &cancel).await {
+            break;
+        }
+        let probe_start = Instant::now();
+        if let Ok(Ok(stages)) = tokio::time::timeout(FRESH_PROBE_TIMEOUT, target.sample()).await {
+            samples.push(stages);
+        }
+        let interval = Duration::from_millis(interval_ms);
+        tokio::time::sleep(interval.saturating_sub(probe_start.elapsed())).await;
+    }
+
+    samples
+}
+
+/// Combine fresh-connection samples with the loaded-latency probes of the
+/// same phase. None when the in-flight probes produced nothing. Without
+/// `tls` (plain-HTTP base URL) the fresh figure averages TCP and HTTP only.
+pub fn summarize(
+    fresh: &[ConnectionStages],
+    tls: bool,
+    in_flight: &LatencySummary,
+) -> Option<RpmSummary> {
+    let in_flight_ms = in_flight.trimmed_mean_ms.filter(|ms| *ms > 0.0)?;
+
+    let stage =
+        |f: fn(&ConnectionStages) -> f64| trimmed_mean_95(&fresh.iter().map(f).collect::<Vec<_>>());
+    let tcp_ms = stage(|s| s.tcp_ms);
+    let tls_ms = if tls { stage(|s| s.tls_ms) } else { None };
+    let http_ms = stage(|s| s.ttfb_ms);
+    // Average round trip on a fresh connection
+    let fresh_ms = match (tcp_ms, tls_ms, http_ms) {
+        (Some(tcp), Some(tls), Some(http)) => Some((tcp + tls + http) / 3.0),
+        (Some(tcp), None, Some(http)) if !tls => Some((tcp + http) / 2.0),
+        _ => None,
+    };
+
+    Some(RpmSummary {
+        rpm: fresh_ms.map(|f| 60_000.0 / (f / 2.0 + in_flight_ms / 2.0)),
+        fresh_rpm: fresh_ms.filter(|ms| *ms > 0.0).map(|ms| 60_000.0 / ms),
+        in_flight_rpm: 60_000.0 / in_flight_ms,
+        fresh_probes: fresh.len(),
+        in_flight_probes: in_flight.received,
+        tcp_ms,
+        tls_ms,
+        http_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_rpm() {
+        let fresh = vec![
+            ConnectionStages {
+                tcp_ms: 20.0,
+                tls_ms: 40.0,
+                ttfb_ms: 60.0,
+                ..Default::default()
+            };
+            4
+        ];
+        let in_flight = LatencySummary {
+            received: 10,
+            trimmed_mean_ms: Some(100.0),
+            ..Default::default()
+        };
+        let rpm = summarize(&fresh, true, &in_flight).unwrap();
+        // 1/6 * (20 + 40 + 60) + 1/2 * 100 = 70 ms
+        assert!((rpm.rpm.unwrap() - 60_000.0 / 70.0).abs() < 0.001);
+        assert!((rpm.fresh_rpm.unwrap() - 1_500.0).abs() < 0.001);
+        assert!((rpm.in_flight_rpm - 600.0).abs() < 0.001);
+
+        // Without fresh-connection samples only the in-flight figure remains
+        let rpm = summarize(&[], true, &in_flight).unwrap();
+        assert!(rpm.rpm.is_none());
+        assert!(summarize(&fresh, true, &LatencySummary::default()).is_none());
+
+        // Over plain HTTP the TLS stage is left out instead of counting as 0 ms
+        let plain: Vec<_> = fresh
+            .iter()
+            .map(|s| ConnectionStages {
+                tls_ms: 0.0,
+                ..s.clone()
+            })
+            .collect();
+        let rpm = summarize(&plain, false, &in_flight).unwrap();
+        assert!(rpm.tls_ms.is_none());
+        // (20 + 60) / 2 = 40 ms per round trip
+        assert!((rpm.fresh_rpm.unwrap() - 1_500.0).abs() < 0.001);
+    }
+}
@@ -0,0 +1,392 @@
+//! Per-connection TCP congestion control and socket buffer sizes
+//!
+//! The options have to be on the socket before it connects: the receive
+//! buffer sets the window scale and initial window advertised in the SYN.
+//! reqwest offers no hook for that, so when any option is requested the
+//! throughput requests go through a small hyper client whose connector
+//! creates, tunes and connects each socket itself. That lets runs compare
+//! algorithms without touching system-wide sysctls. Linux only.
+
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::model::{HttpVersion, RunConfig, TcpSocketSettings};
+use anyhow::{Context as _, Result};
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use http_body_util::{combinators::UnsyncBoxBody, BodyDataStream, BodyExt, Empty, StreamBody};
+use hyper::body::Frame;
+use hyper_util::client::legacy::connect::{Connected, Connection};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use reqwest::{StatusCode, Url, Version};
+use rustls::pki_types::ServerName;
+use socket2::{Domain, Socket, TcpKeepalive, Type};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpSocket;
+use tokio_rustls::TlsConnector;
+
+/// Same limits as the reqwest client (the timeout here covers the response head)
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Socket options requested for every throughput connection.
+pub struct SocketTuning {
+    congestion: Option<String>,
+    rcvbuf: Option<usize>,
+    sndbuf: Option<usize>,
+    /// Buffer sizes the kernel reported back for the probe socket
+    effective_rcvbuf: Option<usize>,
+    effective_sndbuf: Option<usize>,
+    tuned: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl SocketTuning {
+    /// `None` when no option is set. Fails up front if the kernel rejects an
+    /// option, e.g. an unknown or non-permitted congestion-control algorithm.
+    pub fn from_config(cfg: &RunConfig) -> Result<Option<Arc<Self>>> {
+        if cfg.tcp_congestion.is_none() && cfg.tcp_rcvbuf.is_none() && cfg.tcp_sndbuf.is_none() {
+            return Ok(None);
+        }
+        anyhow::ensure!(
+            cfg!(target_os = "linux"),
+            "--tcp-congestion, --tcp-rcvbuf and --tcp-sndbuf are only supported on Linux"
+        );
+        anyhow::ensure!(
+            cfg.proxy.is_none(),
+            "TCP socket options cannot be combined with --proxy"
+        );
+
+        let mut tuning = Self {
+            congestion: cfg.tcp_congestion.clone(),
+            rcvbuf: cfg.tcp_rcvbuf,
+            sndbuf: cfg.tcp_sndbuf,
+            effective_rcvbuf: None,
+            effective_sndbuf: None,
+            tuned: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        };
+
+        // Try the options on a throwaway socket so mistakes surface before the test
+        let probe = Socket::new(Domain::IPV4, Type::STREAM, None)
+            .context("Failed to create probe socket")?;
+        tuning.apply(&probe)?;
+        if tuning.rcvbuf.is_some() {
+            tuning.effective_rcvbuf = probe.recv_buffer_size().ok();
+        }
+        if tuning.sndbuf.is_some() {
+            tuning.effective_sndbuf = probe.send_buffer_size().ok();
+        }
+
+        Ok(Some(Arc::new(tuning)))
+    }
+
+    fn apply(&self, sock: &Socket) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(ref name) = self.congestion {
+            sock.set_tcp_congestion(name.as_bytes()).with_context(|| {
+                format!(
+                    "TCP congestion control '{}' is not available (see /proc/sys/net/ipv4/tcp_allowed_congestion_control)",
+                    name
+                )
+            })?;
+        }
+        if let Some(size) = self.rcvbuf {
+            sock.set_recv_buffer_size(size)
+                .context("Failed to set SO_RCVBUF")?;
+        }
+        if let Some(size) = self.sndbuf {
+            sock.set_send_buffer_size(size)
+                .context("Failed to set SO_SNDBUF")?;
+        }
+        Ok(())
+    }
+
+    /// What was requested, what the kernel granted and how often it was applied
+    pub fn settings(&self) -> TcpSocketSettings {
+        TcpSocketSettings {
+            congestion_control: self.congestion.clone(),
+            rcvbuf_requested: self.rcvbuf,
+            rcvbuf: self.effective_rcvbuf,
+            sndbuf_requested: self.sndbuf,
+            sndbuf: self.effective_sndbuf,
+            connections: self.tuned.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// hyper client for throughput requests over sockets tuned before connect.
+#[derive(Clone)]
+pub struct TunedClient {
+    pub tuning: Arc<SocketTuning>,
+    client: Client<TunedConnector, UnsyncBoxBody<Bytes, std::io::Error>>,
+    headers: http::HeaderMap,
+}
+
+impl TunedClient {
+    /// Connections bind to `local_ip` when set and only use addresses of
+    /// `family`; TLS trusts the public roots plus `certificate_path`.
+    pub fn new(
+        tuning: Arc<SocketTuning>,
+        cfg: &RunConfig,
+        local_ip: Option<IpAddr>,
+        certificate_path: Option<&Path>,
+    ) -> Result<Self> {
+        let alpn: &[&[u8]] = match cfg.http_version {
+            HttpVersion::Http1 => &[b"http/1.1"],
+            HttpVersion::Http2 => &[b"h2"],
+            _ => &[b"h2", b"http/1.1"],
+        };
+        let mut tls = crate::engine::tls::client_config(certificate_path)?;
+        tls.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+
+        let connector = TunedConnector {
+            tuning: tuning.clone(),
+            family: AddressFamily::from_config(cfg),
+            local_ip,
+            tls: TlsConnector::from(Arc::new(tls)),
+        };
+        let mut builder = Client::builder(TokioExecutor::new());
+        if cfg.http_version == HttpVersion::Http2 {
+            builder.http2_only(true);
+        }
+
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            http::header::USER_AGENT,
+            cfg.user_agent.parse().context("invalid user agent")?,
+        );
+        headers.insert(
+            http::header::REFERER,
+            http::HeaderValue::from_static("https://speed.cloudflare.com/"),
+        );
+
+        Ok(Self {
+            tuning,
+            client: builder.build(connector),
+            headers,
+        })
+    }
+
+    /// GET, returning the status, the protocol version and the body as a
+    /// stream of chunks
+    pub async fn get(
+        &self,
+        url: Url,
+    ) -> Result<(StatusCode, Version, BoxStream<'static, Result<Bytes>>)> {
+        let body = Empty::new().map_err(|never| match never {}).boxed_unsync();
+        let resp = self.send(http::Method::GET, url, body).await?;
+        let (status, version) = (resp.status(), resp.version());
+        let stream = BodyDataStream::new(resp.into_body())
+            .map_err(anyhow::Error::from)
+            .boxed();
+        Ok((status, version, stream))
+    }
+
+    /// POST a streamed body and return the response status and version
+    pub async fn post(
+        &self,
+        url: Url,
+        body: BoxStream<'static, std::io::Result<Bytes>>,
+    ) -> Result<(StatusCode, Version)> {
+        let body = BodyExt::boxed_unsync(StreamBody::new(body.map_ok(Frame::data)));
+        let resp = self.send(http::Method::POST, url, body).await?;
+        Ok((resp.status(), resp.version()))
+    }
+
+    async fn send(
+        &self,
+        method: http::Method,
+        url: Url,
+        body: UnsyncBoxBody<Bytes, std::io::Error>,
+    ) -> Result<http::Response<hyper::body::Incoming>> {
+        let mut req = http::Request::builder()
+            .method(method)
+            .uri(url.as_str())
+            .body(body)?;
+        req.headers_mut().extend(self.headers.clone());
+        tokio::time::timeout(REQUEST_TIMEOUT, self.client.request(req))
+            .await
+            .map_err(|_| anyhow::anyhow!("request timed out"))?
+            .context("request failed")
+    }
+}
+
+/// Connector that sets the socket options between socket() and connect().
+#[derive(Clone)]
+struct TunedConnector {
+    tuning: Arc<SocketTuning>,
+    family: Option<AddressFamily>,
+    local_ip: Option<IpAddr>,
+    tls: TlsConnector,
+}
+
+impl TunedConnector {
+    async fn connect(self, uri: http::Uri) -> Result<TunedConn> {
+        let host = uri.host().context("URL has no host")?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let https = uri.scheme_str() == Some("https");
+        let port = uri.port_u16().unwrap_or(if https { 443 } else { 80 });
+
+        let mut last_err = None;
+        for addr in network_bind::lookup_host(host, port, self.family).await? {
+            match self.connect_tcp(addr).await {
+                Ok(tcp) if https => {
+                    let server_name = ServerName::try_from(host.to_string())
+                        .map_err(|_| anyhow::anyhow!("Invalid DNS name: {}", host))?;
+                    let tls = self
+                        .tls
+                        .connect(server_name, tcp)
+                        .await
+                        .with_context(|| format!("TLS handshake failed with {}", host))?;
+                    let h2 = tls.get_ref().1.alpn_protocol() == Some(b"h2");
+                    return Ok(TunedConn::new(tls, h2));
+                }
+                Ok(tcp) => return Ok(TunedConn::new(tcp, false)),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("No address for {}", host)))
+    }
+
+    async fn connect_tcp(&self, addr: SocketAddr) -> Result<tokio::net::TcpStream> {
+        let sock = Socket::new(Domain::for_address(addr), Type::STREAM, None)
+            .context("Failed to create socket")?;
+        // Options that fail here were accepted on the probe socket; count
+        // them and carry on with the kernel defaults
+        match self.tuning.apply(&sock) {
+            Ok(()) => self.tuning.tuned.fetch_add(1, Ordering::Relaxed),
+            Err(_) => self.tuning.failed.fetch_add(1, Ordering::Relaxed),
+        };
+        sock.set_nodelay(true)?;
+        sock.set_tcp_keepalive(&TcpKeepalive::new().with_time(TCP_KEEPALIVE))?;
+        if let Some(ip) = self.local_ip.filter(|ip| ip.is_ipv4() == addr.is_ipv4()) {
+            sock.bind(&SocketAddr::new(ip, 0).into())
+                .with_context(|| format!("Failed to bind to {}", ip))?;
+        }
+        sock.set_nonblocking(true)?;
+        TcpSocket::from_std_stream(std::net::TcpStream::from(sock))
+            .connect(addr)
+            .await
+            .with_context(|| format!("TCP connection failed to {}", addr))
+    }
+}
+
+impl tower::Service<http::Uri> for TunedConnector {
+    type Response = TunedConn;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = BoxFuture<'static, std::result::Result<TunedConn, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<std::result::Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: http::Uri) -> Self::Future {
+        let connector = self.clone();
+        Box::pin(async move { connector.connect(uri).await.map_err(Into::into) })
+    }
+}
+
+trait AsyncIo: AsyncRead + AsyncWrite + Send {}
+impl<T: AsyncRead + AsyncWrite + Send> AsyncIo for T {}
+
+/// A plain or TLS connection from [`TunedConnector`]
+struct TunedConn {
+    io: TokioIo<Pin<Box<dyn AsyncIo>>>,
+    h2: bool,
+}
+
+impl TunedConn {
+    fn new(io: impl AsyncIo + 'static, h2: bool) -> Self {
+        Self {
+            io: TokioIo::new(Box::pin(io)),
+            h2,
+        }
+    }
+}
+
+impl Connection for TunedConn {
+    fn connected(&self) -> Connected {
+        let connected = Connected::new();
+        if self.h2 {
+            connected.negotiated_h2()
+        } else {
+            connected
+        }
+    }
+}
+
+impl hyper::rt::Read for TunedConn {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: hyper::rt::ReadBufCursor<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl hyper::rt::Write for TunedConn {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_tuned_client_against_local_server() {
+        use clap::Parser;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = sock.read(&mut buf).await;
+            let _ = sock
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello")
+                .await;
+        });
+
+        let args = crate::cli::Cli::parse_from(["cloudflare-speed-cli", "--tcp-rcvbuf", "65536"]);
+        let cfg = crate::cli::build_config(&args);
+        let tuning = SocketTuning::from_config(&cfg).unwrap().unwrap();
+        let client = TunedClient::new(tuning, &cfg, None, None).unwrap();
+
+        let url = Url::parse(&format!("http://{}/", addr)).unwrap();
+        let (status, version, stream) = client.get(url).await.unwrap();
+        let body: Vec<Bytes> = stream.try_collect().await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(version, Version::HTTP_11);
+        assert_eq!(body.concat(), b"hello");
+
+        let settings = client.tuning.settings();
+        assert_eq!(settings.connections, 1);
+        assert_eq!(settings.failed, 0);
+    }
+}
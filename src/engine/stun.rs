@@ -0,0 +1,265 @@
+//! STUN message encoding and decoding (RFC 5389), shared by the TURN relay
+//! probe and NAT behavior discovery
+
+use crate::engine::turn_udp::STUN_HEADER_LEN;
+use ring::hmac;
+use std::net::{IpAddr, SocketAddr};
+
+pub(super) const MAGIC_COOKIE: [u8; 4] = [0x21, 0x12, 0xA4, 0x42];
+
+pub(super) const BINDING_REQUEST: u16 = 0x0001;
+/// Class bits that turn a request type into its success response type
+pub(super) const SUCCESS_CLASS: u16 = 0x0100;
+
+pub(super) const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+pub(super) const ATTR_CHANGE_REQUEST: u16 = 0x0003;
+const ATTR_MESSAGE_INTEGRITY: u16 = 0x0008;
+const ATTR_ERROR_CODE: u16 = 0x0009;
+pub(super) const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+/// RFC 5780: the server's alternate IP and port
+pub(super) const ATTR_OTHER_ADDRESS: u16 = 0x802C;
+
+/// STUN message under construction
+pub(super) struct MessageBuilder {
+    buf: Vec<u8>,
+    txid: [u8; 12],
+}
+
+impl MessageBuilder {
+    pub(super) fn new(msg_type: u16, txid: [u8; 12]) -> Self {
+        let mut buf = Vec::with_capacity(128);
+        buf.extend_from_slice(&msg_type.to_be_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(&MAGIC_COOKIE);
+        buf.extend_from_slice(&txid);
+        Self { buf, txid }
+    }
+
+    pub(super) fn attr(mut self, attr_type: u16, value: &[u8]) -> Self {
+        self.buf.extend_from_slice(&attr_type.to_be_bytes());
+        self.buf
+            .extend_from_slice(&(value.len() as u16).to_be_bytes());
+        self.buf.extend_from_slice(value);
+        self.buf.resize(self.buf.len().next_multiple_of(4), 0);
+        self.set_length(0);
+        self
+    }
+
+    pub(super) fn xor_address(self, attr_type: u16, addr: SocketAddr) -> Self {
+        let value = encode_xor_address(addr, &self.txid);
+        self.attr(attr_type, &value)
+    }
+
+    /// MESSAGE-INTEGRITY over everything so far. The length field has to
+    /// count the 24-byte attribute before the HMAC is taken.
+    pub(super) fn integrity(mut self, key: &[u8]) -> Self {
+        self.set_length(24);
+        let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+        let tag = hmac::sign(&key, &self.buf);
+        self.attr(ATTR_MESSAGE_INTEGRITY, tag.as_ref())
+    }
+
+    fn set_length(&mut self, extra: usize) {
+        let len = (self.buf.len() - STUN_HEADER_LEN + extra) as u16;
+        self.buf[2..4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    pub(super) fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// A received STUN message with its attributes in wire order
+pub(super) struct Message<'a> {
+    pub(super) msg_type: u16,
+    pub(super) txid: [u8; 12],
+    attrs: Vec<(u16, &'a [u8])>,
+}
+
+impl<'a> Message<'a> {
+    pub(super) fn parse(buf: &'a [u8]) -> Option<Self> {
+        if buf.len() < STUN_HEADER_LEN || buf[4..8] != MAGIC_COOKIE {
+            return None;
+        }
+        let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+        let mut rest = buf.get(STUN_HEADER_LEN..STUN_HEADER_LEN + len)?;
+        let mut attrs = Vec::new();
+        while rest.len() >= 4 {
+            let attr_type = u16::from_be_bytes([rest[0], rest[1]]);
+            let attr_len = u16::from_be_bytes([rest[2], rest[3]]) as usize;
+            attrs.push((attr_type, rest.get(4..4 + attr_len)?));
+            rest = rest
+                .get((4 + attr_len).next_multiple_of(4)..)
+                .unwrap_or(&[]);
+        }
+        Some(Self {
+            msg_type: u16::from_be_bytes([buf[0], buf[1]]),
+            txid: buf[8..20].try_into().ok()?,
+            attrs,
+        })
+    }
+
+    pub(super) fn attr(&self, attr_type: u16) -> Option<&'a [u8]> {
+        self.attrs
+            .iter()
+            .find(|(t, _)| *t == attr_type)
+            .map(|(_, v)| *v)
+    }
+
+    /// ERROR-CODE as a number, e.g. 401
+    pub(super) fn error_code(&self) -> Option<u16> {
+        let v = self.attr(ATTR_ERROR_CODE)?;
+        (v.len() >= 4).then(|| (v[2] & 0x07) as u16 * 100 + v[3] as u16)
+    }
+
+    /// Our address as the server saw it. Falls back to the plain
+    /// MAPPED-ADDRESS of servers that only speak RFC 3489.
+    pub(super) fn mapped_address(&self) -> Option<SocketAddr> {
+        self.attr(ATTR_XOR_MAPPED_ADDRESS)
+            .and_then(|v| decode_xor_address(v, &self.txid))
+            .or_else(|| self.attr(ATTR_MAPPED_ADDRESS).and_then(decode_address))
+    }
+}
+
+pub(super) fn encode_xor_address(addr: SocketAddr, txid: &[u8; 12]) -> Vec<u8> {
+    let port = addr.port() ^ 0x2112;
+    let (family, octets) = match addr.ip() {
+        IpAddr::V4(ip) => (0x01, ip.octets().to_vec()),
+        IpAddr::V6(ip) => (0x02, ip.octets().to_vec()),
+    };
+    let mut v = vec![0, family];
+    v.extend_from_slice(&port.to_be_bytes());
+    let mask = MAGIC_COOKIE.iter().chain(txid.iter());
+    v.extend(octets.iter().zip(mask).map(|(a, b)| a ^ b));
+    v
+}
+
+pub(super) fn decode_xor_address(value: &[u8], txid: &[u8; 12]) -> Option<SocketAddr> {
+    let mut mask = MAGIC_COOKIE.to_vec();
+    mask.extend_from_slice(txid);
+    decode_masked_address(value, &mask)
+}
+
+/// Address attribute without XOR, as in MAPPED-ADDRESS and OTHER-ADDRESS
+pub(super) fn decode_address(value: &[u8]) -> Option<SocketAddr> {
+    decode_masked_address(value, &[])
+}
+
+/// Port and address are XORed with the start of `mask`; the port with its
+/// first two bytes. An empty mask leaves them as they are.
+fn decode_masked_address(value: &[u8], mask: &[u8]) -> Option<SocketAddr> {
+    let unmask = |i: usize, b: u8| b ^ mask.get(i).copied().unwrap_or(0);
+    let port = u16::from_be_bytes([unmask(0, *value.get(2)?), unmask(1, *value.get(3)?)]);
+    let octets: Vec<u8> = value
+        .get(4..)?
+        .iter()
+        .enumerate()
+        .map(|(i, b)| unmask(i, *b))
+        .collect();
+    let ip = match (*value.get(1)?, octets.len()) {
+        (0x01, 4..) => IpAddr::from(<[u8; 4]>::try_from(&octets[..4]).ok()?),
+        (0x02, 16..) => IpAddr::from(<[u8; 16]>::try_from(&octets[..16]).ok()?),
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::model::{RunConfig, TurnInfo};
+    use tokio::net::UdpSocket;
+
+    /// STUN server on loopback that answers each Binding request with the
+    /// client's source address, and advertises `other` as OTHER-ADDRESS
+    /// without honoring CHANGE-REQUEST
+    pub(crate) async fn stun_responder(other: Option<SocketAddr>) -> SocketAddr {
+        let sock = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = sock.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1500];
+            while let Ok((n, peer)) = sock.recv_from(&mut buf).await {
+                let Some(req) = Message::parse(&buf[..n]) else {
+                    continue;
+                };
+                let mut resp = MessageBuilder::new(BINDING_REQUEST | SUCCESS_CLASS, req.txid)
+                    .xor_address(ATTR_XOR_MAPPED_ADDRESS, peer);
+                if let Some(SocketAddr::V4(other)) = other {
+                    let mut value = vec![0, 0x01];
+                    value.extend_from_slice(&other.port().to_be_bytes());
+                    value.extend_from_slice(&other.ip().octets());
+                    resp = resp.attr(ATTR_OTHER_ADDRESS, &value);
+                }
+                let _ = sock.send_to(&resp.finish(), peer).await;
+            }
+        });
+        addr
+    }
+
+    /// Config as built from these command-line arguments
+    pub(crate) fn config(args: &[&str]) -> RunConfig {
+        use clap::Parser;
+
+        let argv = std::iter::once("cloudflare-speed-cli").chain(args.iter().copied());
+        crate::cli::build_config(&crate::cli::Cli::parse_from(argv))
+    }
+
+    /// Unauthenticated target for a STUN URL
+    pub(crate) fn stun_target(url: String) -> TurnInfo {
+        TurnInfo {
+            urls: vec![url],
+            username: None,
+            credential: None,
+        }
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_xor_address_roundtrip() {
+        let txid = [3u8; 12];
+        for addr in ["192.0.2.1:32853", "[2001:db8::1]:3478"] {
+            let addr: SocketAddr = addr.parse().unwrap();
+            let encoded = encode_xor_address(addr, &txid);
+            assert_eq!(decode_xor_address(&encoded, &txid), Some(addr));
+        }
+        // RFC 5769 section 2.2: 192.0.2.1:32853 as XOR-MAPPED-ADDRESS
+        let encoded = encode_xor_address("192.0.2.1:32853".parse().unwrap(), &txid);
+        assert_eq!(hex(&encoded), "0001a147e112a643");
+        // Same address as a plain MAPPED-ADDRESS
+        let plain = [0x00, 0x01, 0x80, 0x55, 192, 0, 2, 1];
+        assert_eq!(decode_address(&plain), "192.0.2.1:32853".parse().ok());
+    }
+
+    #[test]
+    fn test_message_integrity_and_parse() {
+        let realm = 0x0014;
+        let msg = MessageBuilder::new(BINDING_REQUEST, [1; 12])
+            .attr(ATTR_CHANGE_REQUEST, &[0, 0, 0, 6])
+            .attr(realm, b"example.org")
+            .integrity(b"key")
+            .finish();
+        let parsed = Message::parse(&msg).unwrap();
+        assert_eq!(parsed.msg_type, BINDING_REQUEST);
+        assert_eq!(parsed.attr(realm), Some(&b"example.org"[..]));
+        // HMAC covers everything before the attribute, length included
+        let mi_start = msg.len() - 24;
+        let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, b"key");
+        assert!(hmac::verify(&key, &msg[..mi_start], &msg[mi_start + 4..]).is_ok());
+        assert_eq!(
+            u16::from_be_bytes([msg[2], msg[3]]) as usize,
+            msg.len() - 20
+        );
+    }
+
+    #[test]
+    fn test_mapped_address() {
+        let addr: SocketAddr = "198.51.100.7:40000".parse().unwrap();
+        let msg = MessageBuilder::new(BINDING_REQUEST | SUCCESS_CLASS, [2; 12])
+            .xor_address(ATTR_XOR_MAPPED_ADDRESS, addr)
+            .finish();
+        assert_eq!(Message::parse(&msg).unwrap().mapped_address(), Some(addr));
+    }
+}
@@ -0,0 +1,648 @@
+//! Kernel TCP statistics for this process's own connections
+//!
+//! reqwest does not hand out its sockets, so on Linux we ask the kernel for
+//! a sock_diag dump of all TCP sockets with their `TCP_INFO`, and keep the
+//! ones that belong to this process (by inode, from `/proc/self/fd`) and are
+//! connected to the speed-test server. Other platforms report nothing.
+
+use crate::model::{Phase, TcpLimit, TcpPhaseTelemetry, TcpTelemetrySample};
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// Retransmitted share of data segments above which loss is blamed
+const LOSS_RETRANSMIT_PCT: f64 = 1.0;
+/// Share of busy time a limit must account for to be blamed
+const LIMITED_SHARE_PCT: f64 = 50.0;
+/// Download rate relative to receive space / RTT that counts as window-bound
+const WINDOW_BOUND_RATIO: f64 = 0.8;
+/// Time between samples during a phase
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Selected `TCP_INFO` counters for one connection.
+#[derive(Debug, Clone)]
+pub struct TcpSocketStats {
+    pub local: SocketAddr,
+    pub peer: SocketAddr,
+    /// Payload bytes acknowledged by the peer (`tcpi_bytes_acked`)
+    pub bytes_acked: u64,
+    /// Payload bytes received from the peer (`tcpi_bytes_received`)
+    pub bytes_received: u64,
+    /// Smoothed RTT and its variation, microseconds
+    pub rtt_us: u32,
+    pub rttvar_us: u32,
+    /// Receiver-side RTT estimate, microseconds
+    pub rcv_rtt_us: u32,
+    /// Congestion window in segments
+    pub snd_cwnd: u32,
+    /// Receive space the kernel advertises, bytes
+    pub rcv_space: u32,
+    pub total_retrans: u32,
+    /// Bytes per second
+    pub pacing_rate: u64,
+    /// Newer counters, missing on older kernels
+    pub data_segs_out: Option<u32>,
+    /// Bytes per second (kernel 4.9+)
+    pub delivery_rate: Option<u64>,
+    /// Busy, receive-window-limited and send-buffer-limited time in
+    /// microseconds (kernel 4.10+)
+    pub limited_us: Option<(u64, u64, u64)>,
+    /// Congestion-control algorithm name
+    pub congestion: Option<String>,
+}
+
+impl TcpSocketStats {
+    fn key(&self) -> (SocketAddr, SocketAddr) {
+        (self.local, self.peer)
+    }
+}
+
+/// Snapshot all TCP sockets of this process connected to one of `peers`.
+/// The /proc walk and the kernel dump run on the blocking pool.
+///
+/// Returns `None` where per-socket statistics are unavailable.
+pub async fn snapshot(peers: &[SocketAddr]) -> Option<Vec<TcpSocketStats>> {
+    let peers = peers.to_vec();
+    tokio::task::spawn_blocking(move || imp::snapshot(&peers))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Sum of `bytes_acked` growth between two snapshots. Sockets opened in
+/// between count from zero; sockets closed in between are lost.
+pub fn acked_delta(before: &[TcpSocketStats], after: &[TcpSocketStats]) -> u64 {
+    after
+        .iter()
+        .map(|s| {
+            let start = before
+                .iter()
+                .find(|b| b.key() == s.key())
+                .map(|b| b.bytes_acked)
+                .unwrap_or(0);
+            s.bytes_acked.saturating_sub(start)
+        })
+        .sum()
+}
+
+/// Periodic `TCP_INFO` sampling over one throughput phase.
+///
+/// Only connections whose payload counter grows between samples count as
+/// active, so idle pooled connections do not dilute the averages.
+pub struct TelemetrySampler {
+    peers: Vec<SocketAddr>,
+    upload: bool,
+    start: Instant,
+    last_sample: Instant,
+    baseline: Vec<TcpSocketStats>,
+    /// Last state seen of every socket, including ones closed since
+    latest: Vec<TcpSocketStats>,
+    active: HashSet<(SocketAddr, SocketAddr)>,
+    samples: Vec<TcpTelemetrySample>,
+    congestion: Option<String>,
+    /// Download only: received bytes at the previous sample
+    last_received: Option<(Instant, u64)>,
+    /// Download only: measured rate relative to the receive-window bound
+    window_use: Vec<f64>,
+}
+
+impl TelemetrySampler {
+    /// Start sampling; `None` when statistics are unavailable or the server
+    /// addresses are unknown.
+    pub async fn start(peers: &[SocketAddr], phase: Phase) -> Option<Self> {
+        if peers.is_empty() {
+            return None;
+        }
+        let baseline = snapshot(peers).await?;
+        Some(Self {
+            peers: peers.to_vec(),
+            upload: phase == Phase::Upload,
+            start: Instant::now(),
+            last_sample: Instant::now(),
+            latest: baseline.clone(),
+            baseline,
+            active: HashSet::new(),
+            samples: Vec::new(),
+            congestion: None,
+            last_received: None,
+            window_use: Vec::new(),
+        })
+    }
+
+    /// Called on every throughput tick; samples once per `SAMPLE_INTERVAL`.
+    pub async fn tick(&mut self) {
+        if self.last_sample.elapsed() >= SAMPLE_INTERVAL {
+            self.sample().await;
+        }
+    }
+
+    async fn sample(&mut self) {
+        self.last_sample = Instant::now();
+        let Some(now) = snapshot(&self.peers).await else {
+            return;
+        };
+        let payload = |s: &TcpSocketStats| {
+            if self.upload {
+                s.bytes_acked
+            } else {
+                s.bytes_received
+            }
+        };
+        let active: Vec<&TcpSocketStats> = now
+            .iter()
+            .filter(|s| {
+                let prev = self
+                    .latest
+                    .iter()
+                    .find(|p| p.key() == s.key())
+                    .map(payload)
+                    .unwrap_or(0);
+                payload(s) > prev
+            })
+            .collect();
+
+        let at = Instant::now();
+        let mut sample = None;
+        if !active.is_empty() {
+            let n = active.len() as f64;
+            let mean = |f: fn(&TcpSocketStats) -> f64| active.iter().map(|s| f(s)).sum::<f64>() / n;
+            // A download socket sends nothing, so only its receiver-side
+            // estimate tracks the path
+            let srtt_ms = if self.upload {
+                mean(|s| s.rtt_us as f64 / 1000.0)
+            } else {
+                mean(|s| s.rcv_rtt_us.max(s.rtt_us) as f64 / 1000.0)
+            };
+            let delivery: Vec<u64> = active.iter().filter_map(|s| s.delivery_rate).collect();
+            if !self.upload {
+                // rcv_space bytes per RTT microseconds, in Mbps
+                let bound: f64 = active
+                    .iter()
+                    .filter(|s| s.rcv_rtt_us > 0)
+                    .map(|s| s.rcv_space as f64 * 8.0 / s.rcv_rtt_us as f64)
+                    .sum();
+                let received: u64 = now.iter().map(|s| s.bytes_received).sum();
+                if let Some((t, bytes)) = self.last_received {
+                    let secs = at.duration_since(t).as_secs_f64();
+                    if bound > 0.0 && secs > 0.0 {
+                        let mbps = received.saturating_sub(bytes) as f64 * 8.0 / secs / 1e6;
+                        self.window_use.push(mbps / bound);
+                    }
+                }
+                self.last_received = Some((at, received));
+            }
+            if self.congestion.is_none() {
+                self.congestion = active.iter().find_map(|s| s.congestion.clone());
+            }
+            self.active.extend(active.iter().map(|s| s.key()));
+            sample = Some(TcpTelemetrySample {
+                t_ms: at.duration_since(self.start).as_millis() as u64,
+                connections: active.len(),
+                srtt_ms,
+                rttvar_ms: mean(|s| s.rttvar_us as f64 / 1000.0),
+                cwnd: mean(|s| s.snd_cwnd as f64),
+                rcv_space: mean(|s| s.rcv_space as f64),
+                retransmits: 0,
+                delivery_mbps: (!delivery.is_empty())
+                    .then(|| delivery.iter().sum::<u64>() as f64 * 8.0 / 1e6),
+                pacing_mbps: active.iter().map(|s| s.pacing_rate).sum::<u64>() as f64 * 8.0 / 1e6,
+            });
+        }
+
+        for s in now {
+            match self.latest.iter_mut().find(|p| p.key() == s.key()) {
+                Some(p) => *p = s,
+                None => self.latest.push(s),
+            }
+        }
+        if let Some(mut sample) = sample {
+            sample.retransmits = self.growth(|s| Some(s.total_retrans as u64)).unwrap_or(0);
+            self.samples.push(sample);
+        }
+    }
+
+    /// Counter growth since the phase started, summed over active sockets;
+    /// `None` if any of them lacks the counter.
+    fn growth(&self, f: impl Fn(&TcpSocketStats) -> Option<u64>) -> Option<u64> {
+        let mut total = 0;
+        for s in self
+            .latest
+            .iter()
+            .filter(|s| self.active.contains(&s.key()))
+        {
+            let start = match self.baseline.iter().find(|b| b.key() == s.key()) {
+                Some(b) => f(b)?,
+                None => 0,
+            };
+            total += f(s)?.saturating_sub(start);
+        }
+        Some(total)
+    }
+
+    /// Take a final sample and aggregate; `None` if no connection carried data.
+    pub async fn finish(mut self) -> Option<TcpPhaseTelemetry> {
+        self.sample().await;
+        if self.samples.is_empty() {
+            return None;
+        }
+        let retransmits = self.growth(|s| Some(s.total_retrans as u64)).unwrap_or(0);
+        let (retransmit_pct, rwnd_limited_pct, sndbuf_limited_pct, limit) = if self.upload {
+            let segs = self.growth(|s| s.data_segs_out.map(u64::from));
+            let retransmit_pct = segs
+                .filter(|&n| n > 0)
+                .map(|n| retransmits as f64 * 100.0 / n as f64);
+            let share = |f: fn((u64, u64, u64)) -> u64| {
+                let busy = self.growth(|s| s.limited_us.map(|l| l.0))?;
+                let part = self.growth(|s| s.limited_us.map(f))?;
+                (busy > 0).then(|| part as f64 * 100.0 / busy as f64)
+            };
+            let rwnd = share(|l| l.1);
+            let sndbuf = share(|l| l.2);
+            (
+                retransmit_pct,
+                rwnd,
+                sndbuf,
+                classify_upload(retransmit_pct, rwnd, sndbuf),
+            )
+        } else {
+            (None, None, None, classify_download(&self.window_use))
+        };
+
+        Some(TcpPhaseTelemetry {
+            congestion_control: self.congestion,
+            connections: self.active.len(),
+            samples: self.samples,
+            retransmits,
+            retransmit_pct,
+            rwnd_limited_pct,
+            sndbuf_limited_pct,
+            limit,
+        })
+    }
+}
+
+/// Upload bottleneck from the client's sender-side counters.
+fn classify_upload(
+    retransmit_pct: Option<f64>,
+    rwnd_limited_pct: Option<f64>,
+    sndbuf_limited_pct: Option<f64>,
+) -> Option<TcpLimit> {
+    if retransmit_pct.is_some_and(|p| p >= LOSS_RETRANSMIT_PCT) {
+        return Some(TcpLimit::Loss);
+    }
+    if rwnd_limited_pct.is_some_and(|p| p >= LIMITED_SHARE_PCT) {
+        return Some(TcpLimit::ReceiveWindow);
+    }
+    if sndbuf_limited_pct.is_some_and(|p| p >= LIMITED_SHARE_PCT) {
+        return Some(TcpLimit::SendBuffer);
+    }
+    (retransmit_pct.is_some() || rwnd_limited_pct.is_some()).then_some(TcpLimit::CongestionWindow)
+}
+
+/// Download bottleneck: if the rate sits at the receive space / RTT bound the
+/// client's window is the cap, otherwise the server or the path is.
+fn classify_download(window_use: &[f64]) -> Option<TcpLimit> {
+    let (_, median, _, _) = crate::metrics::compute_metrics(window_use)?;
+    Some(if median >= WINDOW_BOUND_RATIO {
+        TcpLimit::ReceiveWindow
+    } else {
+        TcpLimit::Sender
+    })
+}
+
+fn is_peer(addr: &SocketAddr, peers: &[SocketAddr]) -> bool {
+    // Compare IPv4-mapped IPv6 peers by their IPv4 address
+    let ip = match addr.ip() {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(addr.ip()),
+        ip => ip,
+    };
+    peers
+        .iter()
+        .any(|p| p.ip() == ip && p.port() == addr.port())
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use super::{is_peer, TcpSocketStats};
+    use socket2::{Domain, Protocol, Socket, Type};
+    use std::collections::HashSet;
+    use std::io::Read;
+    use std::mem::{offset_of, size_of};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    /// sock_diag request type and `inet_diag` attributes (linux/sock_diag.h,
+    /// linux/inet_diag.h)
+    const SOCK_DIAG_BY_FAMILY: u16 = 20;
+    const INET_DIAG_INFO: u16 = 2;
+    const INET_DIAG_CONG: u16 = 4;
+    const NLMSG_HDR_LEN: usize = 16;
+    /// `struct inet_diag_msg` up to and including `idiag_inode`
+    const INET_DIAG_MSG_LEN: usize = 72;
+
+    /// Prefix of Linux `struct tcp_info` (include/uapi/linux/tcp.h) up to
+    /// `tcpi_sndbuf_limited`; libc's definition stops before the byte counters.
+    /// Older kernels fill less of it, which the returned length tells.
+    #[repr(C)]
+    #[derive(Default)]
+    struct RawTcpInfo {
+        state: u8,
+        ca_state: u8,
+        retransmits: u8,
+        probes: u8,
+        backoff: u8,
+        options: u8,
+        wscale: u8,
+        app_limited: u8,
+        rto: u32,
+        ato: u32,
+        snd_mss: u32,
+        rcv_mss: u32,
+        unacked: u32,
+        sacked: u32,
+        lost: u32,
+        retrans: u32,
+        fackets: u32,
+        last_data_sent: u32,
+        last_ack_sent: u32,
+        last_data_recv: u32,
+        last_ack_recv: u32,
+        pmtu: u32,
+        rcv_ssthresh: u32,
+        rtt: u32,
+        rttvar: u32,
+        snd_ssthresh: u32,
+        snd_cwnd: u32,
+        advmss: u32,
+        reordering: u32,
+        rcv_rtt: u32,
+        rcv_space: u32,
+        total_retrans: u32,
+        pacing_rate: u64,
+        max_pacing_rate: u64,
+        bytes_acked: u64,
+        bytes_received: u64,
+        segs_out: u32,
+        segs_in: u32,
+        notsent_bytes: u32,
+        min_rtt: u32,
+        data_segs_in: u32,
+        data_segs_out: u32,
+        delivery_rate: u64,
+        busy_time: u64,
+        rwnd_limited: u64,
+        sndbuf_limited: u64,
+    }
+
+    /// Statistics of this process's TCP sockets connected to one of `peers`,
+    /// from a sock_diag dump of all TCP sockets on the host. Our own sockets
+    /// are picked by inode, so no descriptor is touched.
+    pub fn snapshot(peers: &[SocketAddr]) -> Option<Vec<TcpSocketStats>> {
+        let inodes = socket_inodes()?;
+        let mut stats = Vec::new();
+        for family in [libc::AF_INET, libc::AF_INET6] {
+            stats.extend(
+                dump_tcp(family as u8)?
+                    .into_iter()
+                    .filter(|(inode, s)| inodes.contains(inode) && is_peer(&s.peer, peers))
+                    .map(|(_, s)| s),
+            );
+        }
+        Some(stats)
+    }
+
+    /// Inodes of this process's sockets, from the `socket:[inode]` link
+    /// targets in /proc/self/fd
+    fn socket_inodes() -> Option<HashSet<u32>> {
+        let entries = std::fs::read_dir("/proc/self/fd").ok()?;
+        Some(
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let target = std::fs::read_link(entry.path()).ok()?;
+                    let target = target.to_str()?;
+                    target
+                        .strip_prefix("socket:[")?
+                        .strip_suffix(']')?
+                        .parse()
+                        .ok()
+                })
+                .collect(),
+        )
+    }
+
+    /// All TCP sockets of one address family with their inode and `TCP_INFO`
+    fn dump_tcp(family: u8) -> Option<Vec<(u32, TcpSocketStats)>> {
+        let sock = Socket::new(
+            Domain::from(libc::AF_NETLINK),
+            Type::DGRAM,
+            Some(Protocol::from(libc::NETLINK_SOCK_DIAG)),
+        )
+        .ok()?;
+
+        // nlmsghdr + inet_diag_req_v2 with a zeroed socket id: every socket
+        let mut req = Vec::with_capacity(NLMSG_HDR_LEN + 56);
+        req.extend_from_slice(&((NLMSG_HDR_LEN + 56) as u32).to_ne_bytes());
+        req.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+        req.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+        req.extend_from_slice(&[0; 8]); // sequence number, port id
+        req.push(family);
+        req.push(libc::IPPROTO_TCP as u8);
+        req.push((1 << (INET_DIAG_INFO - 1)) | (1 << (INET_DIAG_CONG - 1)));
+        req.push(0);
+        req.extend_from_slice(&u32::MAX.to_ne_bytes()); // all TCP states
+        req.extend_from_slice(&[0; 48]);
+        sock.send(&req).ok()?;
+
+        let mut sockets = Vec::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = (&sock).read(&mut buf).ok()?;
+            let mut msgs = &buf[..n];
+            while msgs.len() >= NLMSG_HDR_LEN {
+                let len = u32::from_ne_bytes(msgs[0..4].try_into().ok()?) as usize;
+                let msg_type = u16::from_ne_bytes(msgs[4..6].try_into().ok()?);
+                if len < NLMSG_HDR_LEN || len > msgs.len() {
+                    return None;
+                }
+                match msg_type as i32 {
+                    libc::NLMSG_DONE => return Some(sockets),
+                    libc::NLMSG_ERROR => return None,
+                    _ => sockets.extend(parse_diag_msg(&msgs[NLMSG_HDR_LEN..len])),
+                }
+                msgs = msgs.get(len.next_multiple_of(4)..).unwrap_or(&[]);
+            }
+        }
+    }
+
+    /// One `inet_diag_msg` and its attributes
+    fn parse_diag_msg(msg: &[u8]) -> Option<(u32, TcpSocketStats)> {
+        if msg.len() < INET_DIAG_MSG_LEN {
+            return None;
+        }
+        let addr = |port: &[u8], ip: &[u8]| {
+            let port = u16::from_be_bytes([port[0], port[1]]);
+            let ip = match msg[0] as i32 {
+                libc::AF_INET => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(&ip[..4]).ok()?)),
+                _ => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(ip).ok()?)),
+            };
+            Some(SocketAddr::new(ip, port))
+        };
+        let local = addr(&msg[4..6], &msg[8..24])?;
+        let peer = addr(&msg[6..8], &msg[24..40])?;
+        let inode = u32::from_ne_bytes(msg[68..72].try_into().ok()?);
+
+        let mut info = None;
+        let mut congestion = None;
+        let mut attrs = &msg[INET_DIAG_MSG_LEN..];
+        while attrs.len() >= 4 {
+            let len = u16::from_ne_bytes([attrs[0], attrs[1]]) as usize;
+            let attr_type = u16::from_ne_bytes([attrs[2], attrs[3]]);
+            let value = attrs.get(4..len)?;
+            match attr_type {
+                INET_DIAG_INFO => info = Some(value),
+                INET_DIAG_CONG => {
+                    let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+                    congestion = Some(String::from_utf8_lossy(&value[..end]).into_owned())
+                        .filter(|n| !n.is_empty());
+                }
+                _ => {}
+            }
+            attrs = attrs.get(len.next_multiple_of(4)..).unwrap_or(&[]);
+        }
+        Some((inode, tcp_stats(local, peer, info?, congestion)?))
+    }
+
+    fn tcp_stats(
+        local: SocketAddr,
+        peer: SocketAddr,
+        raw: &[u8],
+        congestion: Option<String>,
+    ) -> Option<TcpSocketStats> {
+        let len = raw.len().min(size_of::<RawTcpInfo>());
+        // Kernels older than 4.1 return a shorter struct without the byte counters
+        if len < offset_of!(RawTcpInfo, segs_out) {
+            return None;
+        }
+        let mut info = RawTcpInfo::default();
+        // SAFETY: RawTcpInfo is a repr(C) struct of plain integers, so any
+        // bytes form a valid value, and `len` fits both buffers
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                raw.as_ptr(),
+                &mut info as *mut RawTcpInfo as *mut u8,
+                len,
+            );
+        }
+        let has = |end: usize| len >= end;
+
+        Some(TcpSocketStats {
+            local,
+            peer,
+            bytes_acked: info.bytes_acked,
+            bytes_received: info.bytes_received,
+            rtt_us: info.rtt,
+            rttvar_us: info.rttvar,
+            rcv_rtt_us: info.rcv_rtt,
+            snd_cwnd: info.snd_cwnd,
+            rcv_space: info.rcv_space,
+            total_retrans: info.total_retrans,
+            pacing_rate: info.pacing_rate,
+            data_segs_out: has(offset_of!(RawTcpInfo, delivery_rate)).then_some(info.data_segs_out),
+            delivery_rate: has(offset_of!(RawTcpInfo, busy_time)).then_some(info.delivery_rate),
+            limited_us: has(size_of::<RawTcpInfo>()).then_some((
+                info.busy_time,
+                info.rwnd_limited,
+                info.sndbuf_limited,
+            )),
+            congestion,
+        })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use super::TcpSocketStats;
+    use std::net::SocketAddr;
+
+    pub fn snapshot(_peers: &[SocketAddr]) -> Option<Vec<TcpSocketStats>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acked_delta() {
+        let peer: SocketAddr = "104.16.0.1:443".parse().unwrap();
+        let sock = |port: u16, acked: u64| TcpSocketStats {
+            local: SocketAddr::new("10.0.0.2".parse().unwrap(), port),
+            peer,
+            bytes_acked: acked,
+            bytes_received: 0,
+            rtt_us: 0,
+            rttvar_us: 0,
+            rcv_rtt_us: 0,
+            snd_cwnd: 0,
+            rcv_space: 0,
+            total_retrans: 0,
+            pacing_rate: 0,
+            data_segs_out: None,
+            delivery_rate: None,
+            limited_us: None,
+            congestion: None,
+        };
+        let before = [sock(40000, 1_000), sock(40001, 5_000)];
+        // 40000 grew, 40001 closed, 40002 is new
+        let after = [sock(40000, 4_000), sock(40002, 2_500)];
+        assert_eq!(acked_delta(&before, &after), 3_000 + 2_500);
+
+        let mapped: SocketAddr = "[::ffff:104.16.0.1]:443".parse().unwrap();
+        assert!(is_peer(&mapped, &[peer]));
+        assert!(!is_peer(&"104.16.0.1:80".parse().unwrap(), &[peer]));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_snapshot_own_socket() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap();
+        let client = std::net::TcpStream::connect(server).unwrap();
+        let local = client.local_addr().unwrap();
+
+        let stats = snapshot(&[server]).await.unwrap();
+        // The accepted end has the listener's address as its local side only
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].local, local);
+        assert_eq!(stats[0].peer, server);
+    }
+
+    #[test]
+    fn test_classify_limit() {
+        assert_eq!(
+            classify_upload(Some(2.5), Some(80.0), None),
+            Some(TcpLimit::Loss)
+        );
+        assert_eq!(
+            classify_upload(Some(0.1), Some(80.0), Some(0.0)),
+            Some(TcpLimit::ReceiveWindow)
+        );
+        assert_eq!(
+            classify_upload(Some(0.1), Some(5.0), Some(60.0)),
+            Some(TcpLimit::SendBuffer)
+        );
+        assert_eq!(
+            classify_upload(Some(0.1), None, None),
+            Some(TcpLimit::CongestionWindow)
+        );
+        assert_eq!(classify_upload(None, None, None), None);
+
+        assert_eq!(
+            classify_download(&[0.95, 0.9, 1.0]),
+            Some(TcpLimit::ReceiveWindow)
+        );
+        assert_eq!(classify_download(&[0.2, 0.3, 0.25]), Some(TcpLimit::Sender));
+        assert_eq!(classify_download(&[]), None);
+    }
+}
@@ -1,14 +1,20 @@
 use crate::engine::cloudflare::CloudflareClient;
 use crate::engine::latency::run_latency_probes;
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::engine::tcp_info::{self, TelemetrySampler};
 use crate::engine::wait_if_paused_or_cancelled;
-use crate::model::{LatencySummary, Phase, RunConfig, TestEvent, ThroughputSummary};
+use crate::model::{
+    BidirectionalSummary, LatencySummary, Phase, RunConfig, TcpPhaseTelemetry, TestEvent,
+    ThroughputSummary, UploadAccounting,
+};
 use anyhow::{Context, Result};
 use bytes::Bytes;
 use futures::{stream, StreamExt};
-use reqwest::StatusCode;
+use reqwest::{StatusCode, Version};
+use std::net::SocketAddr;
 use std::sync::{
     atomic::{AtomicBool, AtomicU64, Ordering},
-    Arc,
+    Arc, Mutex, OnceLock,
 };
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -18,7 +24,189 @@ use tokio::time::Instant;
 const UPLOAD_CHUNK_SIZE: u64 = 64 * 1024;
 const MIN_DOWNLOAD_BYTES_PER_REQ: u64 = 100_000;
 
-fn throughput_summary(bytes: u64, duration: Duration, mbps_samples: &[f64]) -> ThroughputSummary {
+/// Adaptive mode: starting point and ceilings for the ramp-up
+const ADAPTIVE_START_STREAMS: usize = 2;
+const ADAPTIVE_START_BYTES_PER_REQ: u64 = 100_000;
+const ADAPTIVE_MAX_STREAMS: usize = 32;
+const ADAPTIVE_MAX_DOWNLOAD_BYTES_PER_REQ: u64 = 100_000_000;
+const ADAPTIVE_MAX_UPLOAD_BYTES_PER_REQ: u64 = 25_000_000;
+/// How often the ramp re-evaluates throughput
+const RAMP_INTERVAL: Duration = Duration::from_secs(1);
+/// Requests shorter than this (per stream, at the current rate) are grown
+const RAMP_TARGET_REQUEST_SECS: f64 = 1.0;
+/// A step that gains less than this fraction counts as a plateau
+const RAMP_PLATEAU_GAIN: f64 = 0.10;
+/// How often the single-stream comparison reports its current rate
+const SINGLE_STREAM_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// State shared by all workers of one throughput phase.
+#[derive(Default)]
+struct PhaseShared {
+    stop: AtomicBool,
+    total: AtomicU64,
+    errors: AtomicU64,
+    /// Current request size; adaptive ramp-up and 429 back-off both adjust it
+    bytes_per_req: AtomicU64,
+    /// Adaptive mode: 429 back-off shrinks the shared request size. Otherwise
+    /// each worker halves only its own, as before adaptive mode existed.
+    adaptive: bool,
+    /// Upload only: bytes of requests the server completed successfully
+    confirmed: AtomicU64,
+    /// Upload only: start, end and size of each of those requests
+    completed: Mutex<Vec<CompletedRequest>>,
+    /// Protocol of the first successful response
+    version: OnceLock<Version>,
+}
+
+impl PhaseShared {
+    fn record_version(&self, version: Version) {
+        let _ = self.version.set(version);
+    }
+
+    /// e.g. "HTTP/2.0"
+    fn http_protocol(&self) -> Option<String> {
+        self.version.get().map(|v| format!("{:?}", v))
+    }
+}
+
+/// An upload request the server answered with a success status
+#[derive(Debug, Clone, Copy)]
+struct CompletedRequest {
+    start: Instant,
+    end: Instant,
+    bytes: u64,
+}
+
+/// Bytes of `completed` requests that fall between `from` and `to`, each
+/// request spread evenly over its own duration. Requests still running at
+/// either edge of the window count pro rata.
+fn confirmed_between(completed: &[CompletedRequest], from: Instant, to: Instant) -> f64 {
+    completed
+        .iter()
+        .map(|r| {
+            let overlap = r.end.min(to).saturating_duration_since(r.start.max(from));
+            let span = r.end.saturating_duration_since(r.start);
+            if span.is_zero() {
+                return if r.end > from && r.end <= to {
+                    r.bytes as f64
+                } else {
+                    0.0
+                };
+            }
+            r.bytes as f64 * overlap.as_secs_f64() / span.as_secs_f64()
+        })
+        .sum()
+}
+
+/// Upload figures from confirmed bytes, once the workers have drained: the
+/// bytes in the `window` that ends at the last tick, and a rate per tick over
+/// the same intervals as the sent-byte samples
+fn confirmed_upload(
+    shared: &PhaseShared,
+    start: Instant,
+    samples: &[(Instant, u64)],
+    window: Duration,
+) -> (u64, Vec<f64>) {
+    let completed = std::mem::take(&mut *shared.completed.lock().unwrap());
+    let window_end = samples.last().map_or(start + window, |s| s.0);
+    let bytes = confirmed_between(&completed, window_end - window, window_end) as u64;
+    let tick_starts = std::iter::once(start).chain(samples.iter().map(|s| s.0));
+    let mbps_samples = tick_starts
+        .zip(samples.iter().map(|s| s.0))
+        .map(|(from, to)| {
+            let secs = to.saturating_duration_since(from).as_secs_f64().max(1e-9);
+            confirmed_between(&completed, from, to) * 8.0 / secs / 1_000_000.0
+        })
+        .collect();
+    (bytes, mbps_samples)
+}
+
+/// Next move of the adaptive ramp.
+#[derive(Debug, PartialEq)]
+enum RampStep {
+    /// Requests finish too quickly to fill the pipe: raise the request size
+    GrowRequest(u64),
+    /// Throughput still scales with streams: add this many
+    AddStreams(usize),
+    /// Throughput stopped improving; hold the current shape
+    Plateau,
+    Hold,
+}
+
+/// Grows request size, then stream count, until throughput stops improving.
+struct Ramp {
+    max_bytes_per_req: u64,
+    last_eval: Instant,
+    last_bytes: u64,
+    prev_bps: f64,
+    plateaued: bool,
+}
+
+impl Ramp {
+    fn new(max_bytes_per_req: u64) -> Self {
+        Self {
+            max_bytes_per_req,
+            last_eval: Instant::now(),
+            last_bytes: 0,
+            prev_bps: 0.0,
+            plateaued: false,
+        }
+    }
+
+    /// Called on every sampling tick; decides at most once per `RAMP_INTERVAL`.
+    fn tick(&mut self, total_bytes: u64, streams: usize, bytes_per_req: u64) -> RampStep {
+        if self.plateaued || self.last_eval.elapsed() < RAMP_INTERVAL {
+            return RampStep::Hold;
+        }
+        let secs = self.last_eval.elapsed().as_secs_f64();
+        let bytes_per_sec = total_bytes.saturating_sub(self.last_bytes) as f64 / secs;
+        self.last_eval = Instant::now();
+        self.last_bytes = total_bytes;
+
+        let step = ramp_decision(
+            bytes_per_sec,
+            self.prev_bps,
+            streams,
+            bytes_per_req,
+            self.max_bytes_per_req,
+        );
+        self.prev_bps = bytes_per_sec;
+        if step == RampStep::Plateau {
+            self.plateaued = true;
+        }
+        step
+    }
+}
+
+/// Pure ramp policy, in bytes per second.
+fn ramp_decision(
+    bytes_per_sec: f64,
+    prev_bytes_per_sec: f64,
+    streams: usize,
+    bytes_per_req: u64,
+    max_bytes_per_req: u64,
+) -> RampStep {
+    let per_stream = bytes_per_sec / streams.max(1) as f64;
+    if bytes_per_req < max_bytes_per_req
+        && (bytes_per_req as f64) < per_stream * RAMP_TARGET_REQUEST_SECS
+    {
+        return RampStep::GrowRequest((bytes_per_req * 4).min(max_bytes_per_req));
+    }
+    if streams < ADAPTIVE_MAX_STREAMS
+        && bytes_per_sec > prev_bytes_per_sec * (1.0 + RAMP_PLATEAU_GAIN)
+    {
+        return RampStep::AddStreams(streams.min(ADAPTIVE_MAX_STREAMS - streams));
+    }
+    RampStep::Plateau
+}
+
+fn throughput_summary(
+    bytes: u64,
+    duration: Duration,
+    mbps_samples: &[f64],
+    streams: usize,
+    shared: &PhaseShared,
+) -> ThroughputSummary {
     // Compute metrics using the same method as metrics.rs for consistency
     let fallback_mbps = || {
         let secs = duration.as_secs_f64().max(1e-9);
@@ -40,9 +228,28 @@ fn throughput_summary(bytes: u64, duration: Duration, mbps_samples: &[f64]) -> T
         median_mbps: Some(median_mbps),
         p25_mbps: Some(p25_mbps),
         p75_mbps: Some(p75_mbps),
+        streams,
+        bytes_per_req: shared.bytes_per_req.load(Ordering::Relaxed),
+        accounting: None,
+        http_protocol: shared.http_protocol(),
     }
 }
 
+/// Addresses the HTTP client connects to, for matching kernel socket stats.
+/// Empty when a proxy sits in between.
+async fn server_peers(client: &CloudflareClient, cfg: &RunConfig) -> Vec<SocketAddr> {
+    let Some(host) = client.base_url.host_str() else {
+        return Vec::new();
+    };
+    if cfg.proxy.is_some() {
+        return Vec::new();
+    }
+    let port = client.base_url.port_or_known_default().unwrap_or(443);
+    network_bind::lookup_host(host, port, AddressFamily::from_config(cfg))
+        .await
+        .unwrap_or_default()
+}
+
 fn estimate_steady_window(
     samples: &[(Instant, u64)],
     total_duration: Duration,
@@ -62,74 +269,229 @@ fn estimate_steady_window(
     Some((b_end.saturating_sub(b_start), dt))
 }
 
-pub async fn run_download_with_loaded_latency(
+fn spawn_download_worker(
     client: &CloudflareClient,
-    cfg: &RunConfig,
-    event_tx: &mpsc::Sender<TestEvent>,
-    paused: Arc<AtomicBool>,
-    cancel: Arc<AtomicBool>,
-) -> Result<(ThroughputSummary, LatencySummary)> {
-    let stop = Arc::new(AtomicBool::new(false));
-    let total = Arc::new(AtomicU64::new(0));
-    let errors = Arc::new(AtomicU64::new(0));
-
-    let mut handles = Vec::new();
-    for _ in 0..cfg.concurrency {
-        let http = client.http.clone();
-        let base_url = client.down_url();
-        let meas_id = client.meas_id.clone();
-        let mut bytes_per_req = cfg.download_bytes_per_req;
-        let stop2 = stop.clone();
-        let total2 = total.clone();
-        let errors2 = errors.clone();
-        let ev_dl = event_tx.clone();
-
-        handles.push(tokio::spawn(async move {
-            while !stop2.load(Ordering::Relaxed) {
-                let mut url = base_url.clone();
-                url.query_pairs_mut()
-                    .append_pair("measId", &meas_id)
-                    .append_pair("bytes", &bytes_per_req.to_string());
-
-                let resp = match http.get(url).send().await {
-                    Ok(r) => r,
-                    Err(_) => {
-                        errors2.fetch_add(1, Ordering::Relaxed);
-                        continue;
-                    }
-                };
+    shared: Arc<PhaseShared>,
+    ev_dl: mpsc::Sender<TestEvent>,
+) -> tokio::task::JoinHandle<()> {
+    let client = client.clone();
+    let base_url = client.down_url();
+    let meas_id = client.meas_id.clone();
 
-                if !resp.status().is_success() {
-                    errors2.fetch_add(1, Ordering::Relaxed);
-                    if resp.status() == StatusCode::TOO_MANY_REQUESTS {
-                        let next = (bytes_per_req / 2).max(MIN_DOWNLOAD_BYTES_PER_REQ);
-                        if next < bytes_per_req {
-                            bytes_per_req = next;
-                            let _ = ev_dl
-                                .send(TestEvent::Info {
-                                    message: format!(
-                                        "Download: 429 from server, reducing bytes per request to {}",
-                                        bytes_per_req
-                                    ),
-                                })
-                                .await;
-                        }
-                    }
-                    tokio::time::sleep(Duration::from_millis(100)).await;
+    tokio::spawn(async move {
+        // Per-worker request size after a 429, outside adaptive mode
+        let mut backed_off: Option<u64> = None;
+        while !shared.stop.load(Ordering::Relaxed) {
+            let bytes_per_req =
+                backed_off.unwrap_or_else(|| shared.bytes_per_req.load(Ordering::Relaxed));
+            let mut url = base_url.clone();
+            url.query_pairs_mut()
+                .append_pair("measId", &meas_id)
+                .append_pair("bytes", &bytes_per_req.to_string());
+
+            let (status, version, mut stream) = match client.get_stream(url).await {
+                Ok(r) => r,
+                Err(_) => {
+                    shared.errors.fetch_add(1, Ordering::Relaxed);
                     continue;
                 }
+            };
 
-                let mut stream = resp.bytes_stream();
-                while let Some(chunk) = stream.next().await {
-                    let Ok(b) = chunk else { break };
-                    total2.fetch_add(b.len() as u64, Ordering::Relaxed);
-                    if stop2.load(Ordering::Relaxed) {
-                        break;
+            if !status.is_success() {
+                shared.errors.fetch_add(1, Ordering::Relaxed);
+                if status == StatusCode::TOO_MANY_REQUESTS {
+                    let next = (bytes_per_req / 2).max(MIN_DOWNLOAD_BYTES_PER_REQ);
+                    let reduced = next < bytes_per_req
+                        && if shared.adaptive {
+                            // Only the first worker to see this size halves it
+                            shared
+                                .bytes_per_req
+                                .compare_exchange(
+                                    bytes_per_req,
+                                    next,
+                                    Ordering::Relaxed,
+                                    Ordering::Relaxed,
+                                )
+                                .is_ok()
+                        } else {
+                            backed_off = Some(next);
+                            true
+                        };
+                    if reduced {
+                        let _ = ev_dl
+                            .send(TestEvent::Info {
+                                message: format!(
+                                    "Download: 429 from server, reducing bytes per request to {}",
+                                    next
+                                ),
+                            })
+                            .await;
                     }
                 }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+
+            shared.record_version(version);
+            while let Some(chunk) = stream.next().await {
+                let Ok(b) = chunk else { break };
+                shared.total.fetch_add(b.len() as u64, Ordering::Relaxed);
+                if shared.stop.load(Ordering::Relaxed) {
+                    break;
+                }
             }
-        }));
+        }
+    })
+}
+
+fn spawn_upload_worker(
+    client: &CloudflareClient,
+    shared: Arc<PhaseShared>,
+) -> tokio::task::JoinHandle<()> {
+    let client = client.clone();
+    let mut url = client.up_url();
+    url.query_pairs_mut().append_pair("measId", &client.meas_id);
+
+    tokio::spawn(async move {
+        while !shared.stop.load(Ordering::Relaxed) {
+            let bytes_per_req = shared.bytes_per_req.load(Ordering::Relaxed);
+
+            // Generate upload body as a bounded stream of bytes.
+            // `total` counts bytes as we *produce* chunks for the HTTP client, which gives a smooth
+            // realtime Mbps for the live graph but runs ahead of the wire by the socket buffers.
+            // The bytes this request's body actually produced are credited to
+            // `confirmed` only once the server answers, and drive the summary.
+            let chunk = Bytes::from(vec![0u8; UPLOAD_CHUNK_SIZE as usize]);
+            let sent = Arc::new(AtomicU64::new(0));
+
+            let full = bytes_per_req / UPLOAD_CHUNK_SIZE;
+            let tail = bytes_per_req % UPLOAD_CHUNK_SIZE;
+
+            let shared_a = shared.clone();
+            let sent_a = sent.clone();
+            let chunk_full = chunk.clone();
+            let s_full = stream::iter(0..full).map(move |_| {
+                shared_a
+                    .total
+                    .fetch_add(UPLOAD_CHUNK_SIZE, Ordering::Relaxed);
+                sent_a.fetch_add(UPLOAD_CHUNK_SIZE, Ordering::Relaxed);
+                Ok::<Bytes, std::io::Error>(chunk_full.clone())
+            });
+
+            let body_stream = if tail == 0 {
+                s_full.boxed()
+            } else {
+                let shared_b = shared.clone();
+                let sent_b = sent.clone();
+                let chunk_tail = chunk.slice(..tail as usize);
+                let s_tail = stream::once(async move {
+                    shared_b.total.fetch_add(tail, Ordering::Relaxed);
+                    sent_b.fetch_add(tail, Ordering::Relaxed);
+                    Ok::<Bytes, std::io::Error>(chunk_tail)
+                });
+                s_full.chain(s_tail).boxed()
+            };
+
+            let started = Instant::now();
+            match client.post_stream(url.clone(), body_stream).await {
+                Ok((status, version)) if status.is_success() => {
+                    shared.record_version(version);
+                    let bytes = sent.load(Ordering::Relaxed);
+                    shared.confirmed.fetch_add(bytes, Ordering::Relaxed);
+                    shared.completed.lock().unwrap().push(CompletedRequest {
+                        start: started,
+                        end: Instant::now(),
+                        bytes,
+                    });
+                }
+                _ => {
+                    shared.errors.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    })
+}
+
+/// Starting stream count and request size for a phase.
+fn initial_shape(cfg: &RunConfig, bytes_per_req: u64) -> (usize, u64) {
+    if cfg.adaptive {
+        (ADAPTIVE_START_STREAMS, ADAPTIVE_START_BYTES_PER_REQ)
+    } else {
+        (cfg.concurrency, bytes_per_req)
+    }
+}
+
+/// Apply one ramp step, spawning extra workers through `spawn` as needed.
+/// Returns true once the ramp has settled.
+async fn apply_ramp_step(
+    step: RampStep,
+    phase_label: &str,
+    shared: &PhaseShared,
+    handles: &mut Vec<tokio::task::JoinHandle<()>>,
+    event_tx: &mpsc::Sender<TestEvent>,
+    mut spawn: impl FnMut() -> tokio::task::JoinHandle<()>,
+) -> bool {
+    let message = match step {
+        RampStep::Hold => return false,
+        RampStep::GrowRequest(bytes) => {
+            shared.bytes_per_req.store(bytes, Ordering::Relaxed);
+            format!("{}: growing requests to {} bytes", phase_label, bytes)
+        }
+        RampStep::AddStreams(n) => {
+            handles.extend((0..n).map(|_| spawn()));
+            format!("{}: ramping up to {} streams", phase_label, handles.len())
+        }
+        RampStep::Plateau => format!(
+            "{}: throughput plateaued at {} streams x {} bytes",
+            phase_label,
+            handles.len(),
+            shared.bytes_per_req.load(Ordering::Relaxed)
+        ),
+    };
+    event_tx.send(TestEvent::Info { message }).await.ok();
+    matches!(step, RampStep::Plateau)
+}
+
+/// Throughput over the samples since the ramp settled, if there are enough of them.
+fn settled_window(
+    samples: &[(Instant, u64)],
+    mbps_samples: &[f64],
+    settled_at: Option<usize>,
+) -> Option<(u64, Duration, usize)> {
+    let idx = settled_at?;
+    let window = samples.get(idx..)?;
+    let (t_start, b_start) = *window.first()?;
+    let (t_end, b_end) = *window.last()?;
+    let dt = t_end.saturating_duration_since(t_start);
+    if window.len() < 2 || dt.as_millis() < 200 || mbps_samples.len() <= idx {
+        return None;
     }
+    Some((b_end.saturating_sub(b_start), dt, idx))
+}
+
+pub async fn run_download_with_loaded_latency(
+    client: &CloudflareClient,
+    cfg: &RunConfig,
+    event_tx: &mpsc::Sender<TestEvent>,
+    paused: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+) -> Result<(ThroughputSummary, LatencySummary, Option<TcpPhaseTelemetry>)> {
+    let (streams, bytes_per_req) = initial_shape(cfg, cfg.download_bytes_per_req);
+    let shared = Arc::new(PhaseShared {
+        bytes_per_req: AtomicU64::new(bytes_per_req),
+        adaptive: cfg.adaptive,
+        ..Default::default()
+    });
+
+    let mut telemetry =
+        TelemetrySampler::start(&server_peers(client, cfg).await, Phase::Download).await;
+
+    let mut handles: Vec<_> = (0..streams)
+        .map(|_| spawn_download_worker(client, shared.clone(), event_tx.clone()))
+        .collect();
+    let mut ramp = cfg
+        .adaptive
+        .then(|| Ramp::new(ADAPTIVE_MAX_DOWNLOAD_BYTES_PER_REQ));
 
     // Loaded latency task (during download).
     let (lat_tx, mut lat_rx) = mpsc::channel::<LatencySummary>(1);
@@ -160,13 +522,14 @@ pub async fn run_download_with_loaded_latency(
     let mut last_t = Instant::now();
     let mut samples: Vec<(Instant, u64)> = Vec::with_capacity(256);
     let mut mbps_samples: Vec<f64> = Vec::with_capacity(256);
+    let mut settled_at: Option<usize> = None;
 
     while start.elapsed() < cfg.download_duration {
         if wait_if_paused_or_cancelled(&paused, &cancel).await {
             break;
         }
 
-        let now_total = total.load(Ordering::Relaxed);
+        let now_total = shared.total.load(Ordering::Relaxed);
         let dt = last_t.elapsed().as_secs_f64().max(1e-9);
         let dbytes = now_total.saturating_sub(last_bytes);
         let bps_instant = (dbytes as f64) / dt;
@@ -185,17 +548,41 @@ pub async fn run_download_with_loaded_latency(
             .await
             .ok();
 
+        if let Some(ramp) = ramp.as_mut() {
+            let step = ramp.tick(
+                now_total,
+                handles.len(),
+                shared.bytes_per_req.load(Ordering::Relaxed),
+            );
+            if apply_ramp_step(step, "Download", &shared, &mut handles, event_tx, || {
+                spawn_download_worker(client, shared.clone(), event_tx.clone())
+            })
+            .await
+            {
+                settled_at = Some(samples.len());
+            }
+        }
+
+        if let Some(t) = telemetry.as_mut() {
+            t.tick().await;
+        }
+
         tokio::time::sleep(Duration::from_millis(200)).await;
     }
 
-    stop.store(true, Ordering::Relaxed);
+    let telemetry = match telemetry {
+        Some(t) => t.finish().await,
+        None => None,
+    };
+    shared.stop.store(true, Ordering::Relaxed);
+    let streams = handles.len();
     for h in handles {
         let _ = h.await;
     }
 
     let duration = start.elapsed();
-    let bytes_total = total.load(Ordering::Relaxed);
-    let error_count = errors.load(Ordering::Relaxed);
+    let bytes_total = shared.total.load(Ordering::Relaxed);
+    let error_count = shared.errors.load(Ordering::Relaxed);
     if error_count > 0 {
         event_tx
             .send(TestEvent::Info {
@@ -204,9 +591,20 @@ pub async fn run_download_with_loaded_latency(
             .await
             .ok();
     }
-    let (bytes, window) =
-        estimate_steady_window(&samples, duration).unwrap_or((bytes_total, duration));
-    let dl = throughput_summary(bytes, window, &mbps_samples);
+    // In adaptive mode only the post-ramp part is representative
+    let (bytes, window, first_sample) = settled_window(&samples, &mbps_samples, settled_at)
+        .unwrap_or_else(|| {
+            let (bytes, window) =
+                estimate_steady_window(&samples, duration).unwrap_or((bytes_total, duration));
+            (bytes, window, 0)
+        });
+    let dl = throughput_summary(
+        bytes,
+        window,
+        &mbps_samples[first_sample..],
+        streams,
+        &shared,
+    );
 
     // Wait for latency results with a timeout to prevent indefinite hangs
     let loaded_latency = tokio::time::timeout(Duration::from_secs(30), lat_rx.recv())
@@ -217,7 +615,7 @@ pub async fn run_download_with_loaded_latency(
     // Ensure the latency probe task has completed
     let _ = lat_handle.await;
 
-    Ok((dl, loaded_latency))
+    Ok((dl, loaded_latency, telemetry))
 }
 
 pub async fn run_upload_with_loaded_latency(
@@ -226,57 +624,29 @@ pub async fn run_upload_with_loaded_latency(
     event_tx: &mpsc::Sender<TestEvent>,
     paused: Arc<AtomicBool>,
     cancel: Arc<AtomicBool>,
-) -> Result<(ThroughputSummary, LatencySummary)> {
-    let stop = Arc::new(AtomicBool::new(false));
-    let total = Arc::new(AtomicU64::new(0));
-    let errors = Arc::new(AtomicU64::new(0));
-
-    let mut handles = Vec::new();
-    for _ in 0..cfg.concurrency {
-        let http = client.http.clone();
-        let mut url = client.up_url();
-        url.query_pairs_mut().append_pair("measId", &client.meas_id);
-        let stop2 = stop.clone();
-        let total2 = total.clone();
-        let errors2 = errors.clone();
-        let bytes_per_req = cfg.upload_bytes_per_req;
-
-        handles.push(tokio::spawn(async move {
-            while !stop2.load(Ordering::Relaxed) {
-                // Generate upload body as a bounded stream of bytes.
-                // We count bytes as we *produce* chunks for reqwest. This is a close approximation
-                // of bytes put on the wire and produces stable realtime Mbps for the UI.
-                let chunk = Bytes::from(vec![0u8; UPLOAD_CHUNK_SIZE as usize]);
-
-                let full = bytes_per_req / UPLOAD_CHUNK_SIZE;
-                let tail = bytes_per_req % UPLOAD_CHUNK_SIZE;
-
-                let total2a = total2.clone();
-                let chunk_full = chunk.clone();
-                let s_full = stream::iter(0..full).map(move |_| {
-                    total2a.fetch_add(UPLOAD_CHUNK_SIZE, Ordering::Relaxed);
-                    Ok::<Bytes, std::io::Error>(chunk_full.clone())
-                });
+) -> Result<(ThroughputSummary, LatencySummary, Option<TcpPhaseTelemetry>)> {
+    let (streams, bytes_per_req) = initial_shape(cfg, cfg.upload_bytes_per_req);
+    let shared = Arc::new(PhaseShared {
+        bytes_per_req: AtomicU64::new(bytes_per_req),
+        adaptive: cfg.adaptive,
+        ..Default::default()
+    });
 
-                let body_stream = if tail == 0 {
-                    s_full.boxed()
-                } else {
-                    let total2b = total2.clone();
-                    let chunk_tail = chunk.slice(..tail as usize);
-                    let s_tail = stream::once(async move {
-                        total2b.fetch_add(tail, Ordering::Relaxed);
-                        Ok::<Bytes, std::io::Error>(chunk_tail)
-                    });
-                    s_full.chain(s_tail).boxed()
-                };
+    // Kernel counters before any upload connection carries data
+    let peers = server_peers(client, cfg).await;
+    let acked_before = if peers.is_empty() {
+        None
+    } else {
+        tcp_info::snapshot(&peers).await
+    };
+    let mut telemetry = TelemetrySampler::start(&peers, Phase::Upload).await;
 
-                let body = reqwest::Body::wrap_stream(body_stream);
-                if http.post(url.clone()).body(body).send().await.is_err() {
-                    errors2.fetch_add(1, Ordering::Relaxed);
-                }
-            }
-        }));
-    }
+    let mut handles: Vec<_> = (0..streams)
+        .map(|_| spawn_upload_worker(client, shared.clone()))
+        .collect();
+    let mut ramp = cfg
+        .adaptive
+        .then(|| Ramp::new(ADAPTIVE_MAX_UPLOAD_BYTES_PER_REQ));
 
     // Loaded latency task (during upload).
     let (lat_tx, mut lat_rx) = mpsc::channel::<LatencySummary>(1);
@@ -307,13 +677,14 @@ pub async fn run_upload_with_loaded_latency(
     let mut last_t = Instant::now();
     let mut samples: Vec<(Instant, u64)> = Vec::with_capacity(256);
     let mut mbps_samples: Vec<f64> = Vec::with_capacity(256);
+    let mut settled_at: Option<usize> = None;
 
     while start.elapsed() < cfg.upload_duration {
         if wait_if_paused_or_cancelled(&paused, &cancel).await {
             break;
         }
 
-        let now_total = total.load(Ordering::Relaxed);
+        let now_total = shared.total.load(Ordering::Relaxed);
         let dt = last_t.elapsed().as_secs_f64().max(1e-9);
         let dbytes = now_total.saturating_sub(last_bytes);
         let bps_instant = (dbytes as f64) / dt;
@@ -332,17 +703,50 @@ pub async fn run_upload_with_loaded_latency(
             .await
             .ok();
 
+        if let Some(ramp) = ramp.as_mut() {
+            let step = ramp.tick(
+                now_total,
+                handles.len(),
+                shared.bytes_per_req.load(Ordering::Relaxed),
+            );
+            if apply_ramp_step(step, "Upload", &shared, &mut handles, event_tx, || {
+                spawn_upload_worker(client, shared.clone())
+            })
+            .await
+            {
+                settled_at = Some(samples.len());
+            }
+        }
+
+        if let Some(t) = telemetry.as_mut() {
+            t.tick().await;
+        }
+
         tokio::time::sleep(Duration::from_millis(200)).await;
     }
 
-    stop.store(true, Ordering::Relaxed);
+    // Close the accounting window before in-flight requests drain
+    let accounting_duration = start.elapsed();
+    let sent_bytes = shared.total.load(Ordering::Relaxed);
+    let confirmed_bytes = shared.confirmed.load(Ordering::Relaxed);
+    let kernel_acked_bytes = match acked_before {
+        Some(before) => tcp_info::snapshot(&peers)
+            .await
+            .map(|after| tcp_info::acked_delta(&before, &after)),
+        None => None,
+    };
+    let telemetry = match telemetry {
+        Some(t) => t.finish().await,
+        None => None,
+    };
+
+    shared.stop.store(true, Ordering::Relaxed);
+    let streams = handles.len();
     for h in handles {
         let _ = h.await;
     }
 
-    let duration = start.elapsed();
-    let bytes_total = total.load(Ordering::Relaxed);
-    let error_count = errors.load(Ordering::Relaxed);
+    let error_count = shared.errors.load(Ordering::Relaxed);
     if error_count > 0 {
         event_tx
             .send(TestEvent::Info {
@@ -351,9 +755,168 @@ pub async fn run_upload_with_loaded_latency(
             .await
             .ok();
     }
+    // Sent bytes only pick the window, as for the live graph. The figure
+    // counts confirmed bytes, including the requests drained above pro rata.
+    // In adaptive mode only the post-ramp part is representative.
+    let (window, first_sample) = settled_window(&samples, &mbps_samples, settled_at)
+        .map(|(_, window, first)| (window, first))
+        .or_else(|| estimate_steady_window(&samples, accounting_duration).map(|(_, w)| (w, 0)))
+        .unwrap_or((accounting_duration, 0));
+    let (bytes, confirmed_mbps) = confirmed_upload(&shared, start, &samples, window);
+    let mut up = throughput_summary(
+        bytes,
+        window,
+        &confirmed_mbps[first_sample..],
+        streams,
+        &shared,
+    );
+    up.accounting = Some(UploadAccounting {
+        sent_bytes,
+        confirmed_bytes,
+        kernel_acked_bytes,
+        duration_ms: accounting_duration.as_millis() as u64,
+    });
+
+    // Wait for latency results with a timeout to prevent indefinite hangs
+    let loaded_latency = tokio::time::timeout(Duration::from_secs(30), lat_rx.recv())
+        .await
+        .context("timed out waiting for loaded latency results")?
+        .context("loaded latency task ended unexpectedly")?;
+
+    // Ensure the latency probe task has completed
+    let _ = lat_handle.await;
+
+    Ok((up, loaded_latency, telemetry))
+}
+
+/// Download and upload worker pools running at the same time, with latency
+/// probes alongside. Both pools keep the configured shape; adaptive ramp-up
+/// only applies to the one-direction phases.
+pub async fn run_bidirectional_with_loaded_latency(
+    client: &CloudflareClient,
+    cfg: &RunConfig,
+    event_tx: &mpsc::Sender<TestEvent>,
+    paused: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+) -> Result<BidirectionalSummary> {
+    let duration = cfg.download_duration.max(cfg.upload_duration);
+    let dl_shared = Arc::new(PhaseShared {
+        bytes_per_req: AtomicU64::new(cfg.download_bytes_per_req),
+        ..Default::default()
+    });
+    let ul_shared = Arc::new(PhaseShared {
+        bytes_per_req: AtomicU64::new(cfg.upload_bytes_per_req),
+        ..Default::default()
+    });
+
+    let handles: Vec<_> = (0..cfg.concurrency)
+        .flat_map(|_| {
+            [
+                spawn_download_worker(client, dl_shared.clone(), event_tx.clone()),
+                spawn_upload_worker(client, ul_shared.clone()),
+            ]
+        })
+        .collect();
+
+    // Loaded latency task (during both directions).
+    let (lat_tx, mut lat_rx) = mpsc::channel::<LatencySummary>(1);
+    let client2 = client.clone();
+    let ev2 = event_tx.clone();
+    let paused2 = paused.clone();
+    let cancel2 = cancel.clone();
+    let cfg2 = cfg.clone();
+    let lat_handle = tokio::spawn(async move {
+        let res = run_latency_probes(
+            &client2,
+            Phase::Bidirectional,
+            Some(Phase::Bidirectional),
+            duration,
+            cfg2.probe_interval_ms,
+            cfg2.probe_timeout_ms,
+            &ev2,
+            paused2,
+            cancel2,
+        )
+        .await
+        .unwrap_or_else(|_| LatencySummary::failed());
+        let _ = lat_tx.send(res).await;
+    });
+
+    let start = Instant::now();
+    let mut last = (0u64, 0u64);
+    let mut last_t = Instant::now();
+    let mut dl_samples: Vec<(Instant, u64)> = Vec::with_capacity(256);
+    let mut dl_mbps_samples: Vec<f64> = Vec::with_capacity(256);
+    let mut ul_samples: Vec<(Instant, u64)> = Vec::with_capacity(256);
+
+    while start.elapsed() < duration {
+        if wait_if_paused_or_cancelled(&paused, &cancel).await {
+            break;
+        }
+
+        let now = (
+            dl_shared.total.load(Ordering::Relaxed),
+            ul_shared.total.load(Ordering::Relaxed),
+        );
+        let dt = last_t.elapsed().as_secs_f64().max(1e-9);
+        let download_bps = now.0.saturating_sub(last.0) as f64 / dt;
+        let upload_bps = now.1.saturating_sub(last.1) as f64 / dt;
+        last_t = Instant::now();
+        last = now;
+        dl_samples.push((Instant::now(), now.0));
+        dl_mbps_samples.push((download_bps * 8.0) / 1_000_000.0);
+        ul_samples.push((Instant::now(), now.1));
+
+        event_tx
+            .send(TestEvent::BidirectionalTick {
+                download_bps,
+                upload_bps,
+            })
+            .await
+            .ok();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    let elapsed = start.elapsed();
+    let dl_total = dl_shared.total.load(Ordering::Relaxed);
+    dl_shared.stop.store(true, Ordering::Relaxed);
+    ul_shared.stop.store(true, Ordering::Relaxed);
+    for h in handles {
+        let _ = h.await;
+    }
+
+    let error_count =
+        dl_shared.errors.load(Ordering::Relaxed) + ul_shared.errors.load(Ordering::Relaxed);
+    if error_count > 0 {
+        event_tx
+            .send(TestEvent::Info {
+                message: format!("Bidirectional: {} request(s) failed", error_count),
+            })
+            .await
+            .ok();
+    }
+
     let (bytes, window) =
-        estimate_steady_window(&samples, duration).unwrap_or((bytes_total, duration));
-    let up = throughput_summary(bytes, window, &mbps_samples);
+        estimate_steady_window(&dl_samples, elapsed).unwrap_or((dl_total, elapsed));
+    let download = throughput_summary(
+        bytes,
+        window,
+        // The first sample covers no time and reads zero, in both directions
+        dl_mbps_samples.get(1..).unwrap_or_default(),
+        cfg.concurrency,
+        &dl_shared,
+    );
+    // Upload counts confirmed bytes, as in the upload phase
+    let window = estimate_steady_window(&ul_samples, elapsed).map_or(elapsed, |(_, w)| w);
+    let (bytes, ul_mbps_samples) = confirmed_upload(&ul_shared, start, &ul_samples, window);
+    let upload = throughput_summary(
+        bytes,
+        window,
+        ul_mbps_samples.get(1..).unwrap_or_default(),
+        cfg.concurrency,
+        &ul_shared,
+    );
 
     // Wait for latency results with a timeout to prevent indefinite hangs
     let loaded_latency = tokio::time::timeout(Duration::from_secs(30), lat_rx.recv())
@@ -364,5 +927,151 @@ pub async fn run_upload_with_loaded_latency(
     // Ensure the latency probe task has completed
     let _ = lat_handle.await;
 
-    Ok((up, loaded_latency))
+    Ok(BidirectionalSummary {
+        download,
+        upload,
+        loaded_latency,
+    })
+}
+
+/// Download or upload over exactly one connection: a single worker issuing
+/// requests back to back on its own client, with no latency probes alongside.
+pub async fn run_single_stream(
+    client: &CloudflareClient,
+    cfg: &RunConfig,
+    phase: Phase,
+    event_tx: &mpsc::Sender<TestEvent>,
+    paused: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+) -> Result<ThroughputSummary> {
+    let (label, duration, bytes_per_req) = match phase {
+        Phase::Upload => ("upload", cfg.upload_duration, cfg.upload_bytes_per_req),
+        _ => (
+            "download",
+            cfg.download_duration,
+            cfg.download_bytes_per_req,
+        ),
+    };
+    event_tx
+        .send(TestEvent::Info {
+            message: format!("Single-stream {} ({}s)", label, duration.as_secs()),
+        })
+        .await
+        .ok();
+
+    let shared = Arc::new(PhaseShared {
+        bytes_per_req: AtomicU64::new(bytes_per_req),
+        ..Default::default()
+    });
+    let handle = match phase {
+        Phase::Upload => spawn_upload_worker(client, shared.clone()),
+        _ => spawn_download_worker(client, shared.clone(), event_tx.clone()),
+    };
+
+    // Uploads report progress from bytes handed to the connection, and
+    // their summary from confirmed bytes, as in the upload phase
+    let start = Instant::now();
+    let mut last_bytes = 0u64;
+    let mut last_t = start;
+    let mut last_report = (start, 0u64);
+    let mut samples: Vec<(Instant, u64)> = Vec::with_capacity(64);
+    let mut mbps_samples: Vec<f64> = Vec::with_capacity(64);
+    while start.elapsed() < duration {
+        if wait_if_paused_or_cancelled(&paused, &cancel).await {
+            break;
+        }
+        let now_total = shared.total.load(Ordering::Relaxed);
+        let dt = last_t.elapsed().as_secs_f64().max(1e-9);
+        mbps_samples.push((now_total.saturating_sub(last_bytes) as f64 * 8.0) / dt / 1_000_000.0);
+        last_t = Instant::now();
+        last_bytes = now_total;
+        samples.push((Instant::now(), now_total));
+
+        // The main phase views keep their results; report progress as info
+        if last_report.0.elapsed() >= SINGLE_STREAM_REPORT_INTERVAL {
+            let secs = last_report.0.elapsed().as_secs_f64();
+            let mbps = (now_total.saturating_sub(last_report.1) as f64 * 8.0) / secs / 1_000_000.0;
+            event_tx
+                .send(TestEvent::Info {
+                    message: format!("Single-stream {}: {:.2} Mbps", label, mbps),
+                })
+                .await
+                .ok();
+            last_report = (Instant::now(), now_total);
+        }
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    let elapsed = start.elapsed();
+    let bytes_total = shared.total.load(Ordering::Relaxed);
+
+    shared.stop.store(true, Ordering::Relaxed);
+    let _ = handle.await;
+
+    let (mut bytes, window) =
+        estimate_steady_window(&samples, elapsed).unwrap_or((bytes_total, elapsed));
+    if phase == Phase::Upload {
+        (bytes, mbps_samples) = confirmed_upload(&shared, start, &samples, window);
+    }
+    Ok(throughput_summary(
+        bytes,
+        window,
+        // The first sample covers no time and reads zero
+        mbps_samples.get(1..).unwrap_or_default(),
+        1,
+        &shared,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirmed_between() {
+        let t0 = Instant::now();
+        let secs = |n: u64| t0 + Duration::from_secs(n);
+        let completed = [
+            CompletedRequest {
+                start: secs(0),
+                end: secs(2),
+                bytes: 1000,
+            },
+            // Drained after the window closed at 4 s
+            CompletedRequest {
+                start: secs(3),
+                end: secs(7),
+                bytes: 4000,
+            },
+        ];
+        assert_eq!(confirmed_between(&completed, secs(0), secs(4)), 2000.0);
+        // Half of the first request, none of the second
+        assert_eq!(confirmed_between(&completed, secs(1), secs(3)), 500.0);
+        assert_eq!(confirmed_between(&completed, secs(7), secs(9)), 0.0);
+    }
+
+    #[test]
+    fn test_ramp_decision() {
+        const MB: f64 = 1_000_000.0;
+        // 2 streams at 20 MB/s: 100 kB requests finish in 10 ms, so grow them
+        assert_eq!(
+            ramp_decision(20.0 * MB, 0.0, 2, 100_000, 100_000_000),
+            RampStep::GrowRequest(400_000)
+        );
+        // Requests already take ~1 s per stream and throughput is still rising
+        assert_eq!(
+            ramp_decision(20.0 * MB, 10.0 * MB, 2, 25_000_000, 100_000_000),
+            RampStep::AddStreams(2)
+        );
+        // Stream growth capped at the maximum
+        assert_eq!(
+            ramp_decision(20.0 * MB, 10.0 * MB, 30, 100_000_000, 100_000_000),
+            RampStep::AddStreams(2)
+        );
+        // Less than 10% gain from the last step
+        assert_eq!(
+            ramp_decision(20.5 * MB, 20.0 * MB, 8, 25_000_000, 100_000_000),
+            RampStep::Plateau
+        );
+    }
 }
@@ -1,13 +1,32 @@
 //! TLS handshake time measurement module
+//!
+//! Besides the handshake time this records what was negotiated (version,
+//! cipher, ALPN, key-exchange group) and the presented certificate chain, so
+//! a TLS-inspection middlebox shows up as an unexpected issuer. A second and
+//! third connection reuse the session cache to time resumption and 0-RTT.
 
-use crate::model::TlsSummary;
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::model::{TlsCertificate, TlsSummary};
 use anyhow::{Context, Result};
-use rustls::pki_types::ServerName;
+use rustls::client::danger::ServerCertVerifier;
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::ClientConfig;
+use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio_rustls::TlsConnector;
 
+/// How long to wait for session tickets after the first handshake
+const TICKET_WAIT: Duration = Duration::from_millis(500);
+
+/// Upper bound for each resumption / 0-RTT exchange
+const RESUMPTION_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Install the ring crypto provider if not already installed.
 fn ensure_crypto_provider() {
     // Install the ring provider as the default crypto provider.
@@ -15,30 +34,76 @@ fn ensure_crypto_provider() {
     let _ = rustls::crypto::ring::default_provider().install_default();
 }
 
+/// Root certificate store from webpki-roots.
+fn public_root_store() -> rustls::RootCertStore {
+    let mut root_store = rustls::RootCertStore::empty();
+    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    root_store
+}
+
+/// Build a TLS connector trusting the webpki-roots store.
+pub(crate) fn webpki_connector() -> TlsConnector {
+    // Ensure the crypto provider is installed
+    ensure_crypto_provider();
+
+    // Build TLS client config
+    let config = ClientConfig::builder()
+        .with_root_certificates(public_root_store())
+        .with_no_client_auth();
+
+    TlsConnector::from(Arc::new(config))
+}
+
+/// Client config trusting the webpki-roots store plus any `certificate_path` roots.
+pub(crate) fn client_config(certificate_path: Option<&Path>) -> Result<ClientConfig> {
+    ensure_crypto_provider();
+
+    let mut root_store = public_root_store();
+    if let Some(path) = certificate_path {
+        root_store.add_parsable_certificates(load_certificates(path)?);
+    }
+
+    Ok(ClientConfig::builder()
+        .with_root_certificates(root_store)
+        .with_no_client_auth())
+}
+
 /// Measure TLS handshake time for a given hostname.
 ///
 /// This measures only the TLS handshake, not including TCP connection time.
-/// Returns a `TlsSummary` with handshake time, protocol version, and cipher suite.
-pub async fn measure_tls_handshake(hostname: &str, port: u16) -> Result<TlsSummary> {
+/// Returns a `TlsSummary` with handshake time, negotiated parameters, the
+/// certificate chain, and resumption timings. A `certificate_path` root is
+/// trusted in addition to the public roots, and flagged if the chain needs it.
+pub async fn measure_tls_handshake(
+    hostname: &str,
+    port: u16,
+    family: Option<AddressFamily>,
+    certificate_path: Option<&Path>,
+) -> Result<TlsSummary> {
     // Ensure the crypto provider is installed
     ensure_crypto_provider();
 
-    // Create root certificate store from webpki-roots
-    let mut root_store = rustls::RootCertStore::empty();
-    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let custom_roots = match certificate_path {
+        Some(path) => load_certificates(path)?,
+        None => Vec::new(),
+    };
+    let mut root_store = public_root_store();
+    root_store.add_parsable_certificates(custom_roots.iter().cloned());
 
-    // Build TLS client config
-    let config = rustls::ClientConfig::builder()
+    // Build TLS client config; the default in-memory session cache is shared
+    // by every connector built from clones of this config
+    let mut config = ClientConfig::builder()
         .with_root_certificates(root_store)
         .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    config.enable_early_data = true;
 
-    let connector = TlsConnector::from(Arc::new(config));
+    // Resumption probes send a plain HTTP/1.1 request, so offer only that
+    let mut http1_config = config.clone();
+    http1_config.alpn_protocols = vec![b"http/1.1".to_vec()];
 
-    // First establish TCP connection (we don't time this)
-    let addr = format!("{}:{}", hostname, port);
-    let tcp_stream = TcpStream::connect(&addr)
-        .await
-        .with_context(|| format!("TCP connection failed to {}", addr))?;
+    let connector = TlsConnector::from(Arc::new(config));
+    let http1_connector = TlsConnector::from(Arc::new(http1_config));
 
     // Parse server name for TLS
     let server_name: ServerName<'static> = hostname
@@ -46,10 +111,14 @@ pub async fn measure_tls_handshake(hostname: &str, port: u16) -> Result<TlsSumma
         .try_into()
         .map_err(|_| anyhow::anyhow!("Invalid DNS name: {}", hostname))?;
 
+    // First establish TCP connection (we don't time this)
+    let addrs = network_bind::lookup_host(hostname, port, family).await?;
+    let tcp_stream = connect(&addrs, hostname, port).await?;
+
     // Time only the TLS handshake
     let start = Instant::now();
-    let tls_stream = connector
-        .connect(server_name, tcp_stream)
+    let mut tls_stream = connector
+        .connect(server_name.clone(), tcp_stream)
         .await
         .with_context(|| format!("TLS handshake failed with {}", hostname))?;
     let handshake_time = start.elapsed();
@@ -63,11 +132,271 @@ pub async fn measure_tls_handshake(hostname: &str, port: u16) -> Result<TlsSumma
         .negotiated_cipher_suite()
         .map(|cs| format!("{:?}", cs.suite()));
 
-    Ok(TlsSummary {
+    let alpn = session
+        .alpn_protocol()
+        .map(|p| String::from_utf8_lossy(p).into_owned());
+
+    let key_exchange_group = session
+        .negotiated_key_exchange_group()
+        .map(|g| format!("{:?}", g.name()));
+
+    let chain = session.peer_certificates().unwrap_or_default().to_vec();
+    let certificates = chain.iter().map(describe_certificate).collect();
+
+    // Only meaningful when a custom root could have made the chain valid
+    let custom_root = !custom_roots.is_empty() && !publicly_trusted(&chain, &server_name);
+
+    // TLS 1.3 tickets arrive after the handshake; a read lets rustls store them
+    let mut buf = [0u8; 1024];
+    let _ = tokio::time::timeout(TICKET_WAIT, tls_stream.read(&mut buf)).await;
+    drop(tls_stream);
+
+    let mut summary = TlsSummary {
         handshake_time_ms: handshake_time.as_secs_f64() * 1000.0,
         protocol_version,
         cipher_suite,
-    })
+        alpn,
+        key_exchange_group,
+        certificates,
+        custom_root,
+        resumed_handshake_time_ms: None,
+        resumed: false,
+        resumed_first_byte_ms: None,
+        zero_rtt_first_byte_ms: None,
+        early_data_accepted: None,
+    };
+
+    // Resumption is a bonus measurement: failures leave the fields empty
+    let request = format!(
+        "HEAD / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        hostname
+    );
+    let resumed = tokio::time::timeout(
+        RESUMPTION_TIMEOUT,
+        time_resumed(
+            &http1_connector,
+            &server_name,
+            &addrs,
+            hostname,
+            port,
+            &request,
+        ),
+    )
+    .await;
+    if let Ok(Ok((handshake_ms, resumed, first_byte_ms))) = resumed {
+        summary.resumed_handshake_time_ms = Some(handshake_ms);
+        summary.resumed = resumed;
+        summary.resumed_first_byte_ms = Some(first_byte_ms);
+
+        let zero_rtt = tokio::time::timeout(
+            RESUMPTION_TIMEOUT,
+            time_zero_rtt(
+                &http1_connector,
+                &server_name,
+                &addrs,
+                hostname,
+                port,
+                &request,
+            ),
+        )
+        .await;
+        if let Ok(Ok(Some((first_byte_ms, accepted)))) = zero_rtt {
+            summary.zero_rtt_first_byte_ms = Some(first_byte_ms);
+            summary.early_data_accepted = Some(accepted);
+        }
+    }
+
+    Ok(summary)
+}
+
+async fn connect(addrs: &[SocketAddr], hostname: &str, port: u16) -> Result<TcpStream> {
+    TcpStream::connect(addrs)
+        .await
+        .with_context(|| format!("TCP connection failed to {}:{}", hostname, port))
+}
+
+/// Resumed handshake: returns (handshake ms, resumed?, handshake start to first response byte ms).
+async fn time_resumed(
+    connector: &TlsConnector,
+    server_name: &ServerName<'static>,
+    addrs: &[SocketAddr],
+    hostname: &str,
+    port: u16,
+    request: &str,
+) -> Result<(f64, bool, f64)> {
+    let tcp_stream = connect(addrs, hostname, port).await?;
+
+    let start = Instant::now();
+    let mut stream = connector
+        .connect(server_name.clone(), tcp_stream)
+        .await
+        .context("Resumed TLS handshake failed")?;
+    let handshake_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let resumed = stream.get_ref().1.handshake_kind() == Some(rustls::HandshakeKind::Resumed);
+
+    stream.write_all(request.as_bytes()).await?;
+    let mut buf = [0u8; 4096];
+    let n = stream.read(&mut buf).await?;
+    anyhow::ensure!(n > 0, "Connection closed before response");
+    let first_byte_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    // Drain the response so fresh tickets for the 0-RTT attempt are stored
+    while let Ok(n) = stream.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+    }
+
+    Ok((handshake_ms, resumed, first_byte_ms))
+}
+
+/// 0-RTT attempt: returns (handshake start to first response byte ms, early data accepted?),
+/// or None when the cached session does not allow early data.
+async fn time_zero_rtt(
+    connector: &TlsConnector,
+    server_name: &ServerName<'static>,
+    addrs: &[SocketAddr],
+    hostname: &str,
+    port: u16,
+    request: &str,
+) -> Result<Option<(f64, bool)>> {
+    let tcp_stream = connect(addrs, hostname, port).await?;
+
+    // With early data available the connector returns before the handshake
+    // completes and the first write travels with the ClientHello
+    let start = Instant::now();
+    let mut stream = connector
+        .clone()
+        .early_data(true)
+        .connect(server_name.clone(), tcp_stream)
+        .await
+        .context("0-RTT TLS handshake failed")?;
+    if stream.get_mut().1.early_data().is_none() {
+        return Ok(None);
+    }
+
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+    let mut buf = [0u8; 4096];
+    let n = stream.read(&mut buf).await?;
+    anyhow::ensure!(n > 0, "Connection closed before response");
+    let first_byte_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    Ok(Some((
+        first_byte_ms,
+        stream.get_ref().1.is_early_data_accepted(),
+    )))
+}
+
+/// Load the `--certificate` file (DER by extension, PEM otherwise).
+fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("failed to read certificate from {}", path.display()))?;
+    let is_der = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("der"));
+    if is_der {
+        return Ok(vec![CertificateDer::from(data)]);
+    }
+    CertificateDer::pem_slice_iter(&data)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("{:?}", e))
+        .with_context(|| format!("failed to parse PEM certificate from {}", path.display()))
+}
+
+/// Would the chain validate against the public web PKI alone?
+fn publicly_trusted(chain: &[CertificateDer<'static>], server_name: &ServerName<'static>) -> bool {
+    let Some((end_entity, intermediates)) = chain.split_first() else {
+        return false;
+    };
+    let Ok(verifier) = WebPkiServerVerifier::builder(Arc::new(public_root_store())).build() else {
+        return false;
+    };
+    verifier
+        .verify_server_cert(end_entity, intermediates, server_name, &[], UnixTime::now())
+        .is_ok()
+}
+
+fn describe_certificate(der: &CertificateDer<'_>) -> TlsCertificate {
+    let (issuer, subject) = certificate_names(der).unwrap_or_default();
+    let digest = ring::digest::digest(&ring::digest::SHA256, der);
+    let sha256 = digest
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":");
+    TlsCertificate {
+        subject,
+        issuer,
+        sha256,
+    }
+}
+
+/// Split one DER element into (tag, contents, remaining input).
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)? as usize;
+    let (len, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7f;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let len = data
+            .get(2..2 + count)?
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, 2 + count)
+    };
+    let end = header.checked_add(len)?;
+    Some((tag, data.get(header..end)?, data.get(end..)?))
+}
+
+/// Extract (issuer, subject) from an X.509 certificate.
+fn certificate_names(der: &[u8]) -> Option<(String, String)> {
+    let (_, cert, _) = der_element(der)?;
+    let (_, tbs, _) = der_element(cert)?;
+
+    // Optional explicit [0] version precedes the serial number
+    let (tag, _, mut rest) = der_element(tbs)?;
+    if tag == 0xa0 {
+        (_, _, rest) = der_element(rest)?; // serial
+    }
+    let (_, _, rest) = der_element(rest)?; // signature algorithm
+    let (_, issuer, rest) = der_element(rest)?;
+    let (_, _, rest) = der_element(rest)?; // validity
+    let (_, subject, _) = der_element(rest)?;
+    Some((format_name(issuer), format_name(subject)))
+}
+
+/// Render an X.501 Name as "CN=..., O=..., C=..." (most specific first).
+fn format_name(mut rdns: &[u8]) -> String {
+    let mut parts = Vec::new();
+    while let Some((_, set, rest)) = der_element(rdns) {
+        rdns = rest;
+        let mut attrs = set;
+        while let Some((_, attr, rest)) = der_element(attrs) {
+            attrs = rest;
+            let Some((_, oid, value)) = der_element(attr) else {
+                continue;
+            };
+            let label = match oid {
+                [0x55, 0x04, 0x03] => "CN",
+                [0x55, 0x04, 0x0a] => "O",
+                [0x55, 0x04, 0x0b] => "OU",
+                [0x55, 0x04, 0x06] => "C",
+                _ => continue,
+            };
+            if let Some((_, text, _)) = der_element(value) {
+                parts.push(format!("{}={}", label, String::from_utf8_lossy(text)));
+            }
+        }
+    }
+    parts.reverse();
+    parts.join(", ")
 }
 
 /// Extract hostname and port from a URL string.
@@ -98,4 +427,31 @@ mod tests {
             Some(("example.com".to_string(), 80))
         );
     }
+
+    #[test]
+    fn test_certificate_names() {
+        // Minimal TBSCertificate: version, serial, alg, issuer, validity, subject
+        let cn = |s: &str| {
+            let mut v = vec![0x31, (s.len() + 9) as u8, 0x30, (s.len() + 7) as u8];
+            v.extend_from_slice(&[0x06, 0x03, 0x55, 0x04, 0x03, 0x0c, s.len() as u8]);
+            v.extend_from_slice(s.as_bytes());
+            let mut name = vec![0x30, v.len() as u8];
+            name.extend(v);
+            name
+        };
+        let mut tbs = vec![0xa0, 0x03, 0x02, 0x01, 0x02, 0x02, 0x01, 0x01, 0x30, 0x00];
+        tbs.extend(cn("Issuer CA"));
+        tbs.extend_from_slice(&[0x30, 0x00]);
+        tbs.extend(cn("leaf.example"));
+        let mut tbs_seq = vec![0x30, tbs.len() as u8];
+        tbs_seq.extend(tbs);
+        let mut cert = vec![0x30, 0x81, tbs_seq.len() as u8];
+        cert.extend(tbs_seq);
+
+        assert_eq!(
+            certificate_names(&cert),
+            Some(("CN=Issuer CA".to_string(), "CN=leaf.example".to_string()))
+        );
+        assert_eq!(certificate_names(&cert[..cert.len() - 3]), None);
+    }
 }
@@ -1,16 +1,23 @@
 //! Traceroute functionality module
 //!
 //! Provides traceroute functionality to measure network path to Cloudflare edge.
-//! Uses raw ICMP sockets when available (requires CAP_NET_RAW or root),
-//! with fallback to system traceroute command.
+//! Probes are ICMP Echo Requests, UDP datagrams to high ports, or TCP SYNs to the
+//! speed-test port. Replies are read from raw ICMP/ICMPv6 sockets when available
+//! (requires CAP_NET_RAW or root), with fallback to system traceroute command.
 
-use crate::model::{TestEvent, TracerouteHop, TracerouteSummary};
+use crate::engine::network_bind::AddressFamily;
+use crate::model::{TestEvent, TracerouteHop, TracerouteMode, TracerouteSummary};
 use anyhow::{Context, Result};
 use pnet_packet::icmp::IcmpTypes;
-use socket2::{Domain, Protocol, Socket, Type};
-use std::io::ErrorKind;
+use pnet_packet::icmpv6::echo_reply::EchoReplyPacket;
+use pnet_packet::icmpv6::echo_request::{EchoRequestPacket, MutableEchoRequestPacket};
+use pnet_packet::icmpv6::{Icmpv6Code, Icmpv6Packet, Icmpv6Types};
+use pnet_packet::ip::IpNextHeaderProtocols;
+use pnet_packet::ipv6::Ipv6Packet;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::io::{self, ErrorKind};
 use std::mem::MaybeUninit;
-use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, ToSocketAddrs};
 use std::process::Command;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -21,38 +28,68 @@ const PROBES_PER_HOP: usize = 3;
 /// Timeout for each probe
 const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
 
+/// How often a pending TCP probe is checked for a SYN-ACK or RST
+const TCP_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// First destination port for UDP probes, as used by classic traceroute
+const UDP_BASE_PORT: u16 = 33434;
+
+/// Timeout for each reverse-DNS lookup of a hop address
+const PTR_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Run traceroute to the destination.
 ///
-/// Tries raw ICMP first, falls back to system traceroute if that fails.
+/// `port` is the TCP port probed in [`TracerouteMode::Tcp`]. Tries the native
+/// probe first, falls back to system traceroute if that fails.
 pub async fn run_traceroute(
     destination: &str,
+    port: u16,
     max_hops: u8,
+    mode: TracerouteMode,
+    family: Option<AddressFamily>,
     event_tx: &mpsc::Sender<TestEvent>,
 ) -> Result<TracerouteSummary> {
     // Resolve destination to IP
-    let ip = resolve_destination(destination)?;
+    let ip = resolve_destination(destination, family)?;
 
-    // Try raw ICMP first
-    match run_icmp_traceroute(&ip, max_hops, event_tx).await {
-        Ok(summary) => return Ok(summary),
+    let native = match mode {
+        TracerouteMode::Icmp => run_icmp_traceroute(&ip, max_hops, event_tx).await,
+        TracerouteMode::Udp | TracerouteMode::Tcp => {
+            run_transport_traceroute(&ip, port, mode, max_hops, event_tx).await
+        }
+    };
+    let mut summary = match native {
+        Ok(summary) => summary,
         Err(e) => {
             // Send info about fallback
             let _ = event_tx
                 .send(TestEvent::Info {
-                    message: format!("ICMP traceroute unavailable ({}), using system command", e),
+                    message: format!(
+                        "{} traceroute unavailable ({}), using system command",
+                        mode.label(),
+                        e
+                    ),
                 })
                 .await;
+
+            // Fall back to system traceroute
+            run_system_traceroute(destination, &ip, port, mode, max_hops, event_tx).await?
         }
-    }
+    };
 
-    // Fall back to system traceroute
-    run_system_traceroute(destination, &ip, max_hops, event_tx).await
+    resolve_hostnames(&mut summary.hops).await;
+    Ok(summary)
 }
 
-/// Resolve destination hostname to IP address.
-fn resolve_destination(destination: &str) -> Result<IpAddr> {
+/// Resolve destination hostname to IP address, restricted to `family` if given.
+pub fn resolve_destination(destination: &str, family: Option<AddressFamily>) -> Result<IpAddr> {
+    let allowed = |ip: &IpAddr| family.map(|f| f.matches(ip)).unwrap_or(true);
+
     // Try to parse as IP first
     if let Ok(ip) = destination.parse::<IpAddr>() {
+        if let Some(f) = family.filter(|_| !allowed(&ip)) {
+            return Err(anyhow::anyhow!("{} is not an {} address", ip, f.label()));
+        }
         return Ok(ip);
     }
 
@@ -60,8 +97,11 @@ fn resolve_destination(destination: &str) -> Result<IpAddr> {
     let addr = format!("{}:0", destination)
         .to_socket_addrs()
         .with_context(|| format!("Failed to resolve {}", destination))?
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("No addresses found for {}", destination))?;
+        .find(|a| allowed(&a.ip()))
+        .ok_or_else(|| match family {
+            Some(f) => anyhow::anyhow!("No {} addresses found for {}", f.label(), destination),
+            None => anyhow::anyhow!("No addresses found for {}", destination),
+        })?;
 
     Ok(addr.ip())
 }
@@ -72,14 +112,9 @@ async fn run_icmp_traceroute(
     max_hops: u8,
     event_tx: &mpsc::Sender<TestEvent>,
 ) -> Result<TracerouteSummary> {
-    // Check if we're dealing with IPv4 - IPv6 traceroute is more complex
     let dest_v4 = match destination {
         IpAddr::V4(v4) => *v4,
-        IpAddr::V6(_) => {
-            return Err(anyhow::anyhow!(
-                "IPv6 traceroute not yet supported via raw sockets"
-            ));
-        }
+        IpAddr::V6(v6) => return run_icmpv6_traceroute(v6, max_hops, event_tx).await,
     };
 
     // Try to create raw ICMP socket
@@ -154,7 +189,9 @@ async fn run_icmp_traceroute(
         let hop = TracerouteHop {
             hop_number: ttl,
             ip_address: hop_ip.map(|ip| ip.to_string()),
-            hostname: hop_ip.and_then(|ip| resolve_hostname(&ip)),
+            hostname: None,
+            asn: None,
+            as_org: None,
             rtt_ms: rtts,
             timeout: timeout && hop_ip.is_none(),
         };
@@ -178,11 +215,432 @@ async fn run_icmp_traceroute(
         destination: destination.to_string(),
         hops,
         completed,
+        mode: TracerouteMode::Icmp,
+    })
+}
+
+/// Run traceroute over a raw ICMPv6 socket (requires elevated privileges).
+///
+/// Unlike raw ICMPv4 sockets, the kernel strips the IPv6 header from received
+/// packets and fills in the ICMPv6 checksum (it depends on the pseudo-header).
+async fn run_icmpv6_traceroute(
+    destination: &Ipv6Addr,
+    max_hops: u8,
+    event_tx: &mpsc::Sender<TestEvent>,
+) -> Result<TracerouteSummary> {
+    let socket = Socket::new(Domain::IPV6, Type::RAW, Some(Protocol::ICMPV6))
+        .context("Failed to create raw ICMPv6 socket (need CAP_NET_RAW or root)")?;
+    socket.set_nonblocking(false)?;
+
+    let icmp_id = std::process::id() as u16;
+    let dest_addr = SocketAddr::new(IpAddr::V6(*destination), 0);
+
+    let mut hops = Vec::new();
+    let mut completed = false;
+
+    for hop_limit in 1..=max_hops {
+        socket.set_unicast_hops_v6(hop_limit as u32)?;
+
+        let mut rtts = Vec::new();
+        let mut hop_ip: Option<IpAddr> = None;
+
+        for probe_num in 0..PROBES_PER_HOP {
+            let icmp_seq = ((hop_limit as u16) << 8) | (probe_num as u16);
+            let packet = build_icmpv6_packet(icmp_id, icmp_seq);
+
+            let start = Instant::now();
+            if socket.send_to(&packet, &dest_addr.into()).is_err() {
+                continue;
+            }
+
+            // Other ICMPv6 traffic (neighbor discovery, other pings) arrives on the
+            // same socket, so keep reading until our reply shows up or we time out.
+            let deadline = start + PROBE_TIMEOUT;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                socket.set_read_timeout(Some(remaining))?;
+
+                let (data, from) = match recv_icmp(&socket, 1280) {
+                    Ok(r) => r,
+                    Err(_) => break,
+                };
+
+                let Some(reply) = parse_icmpv6_reply(&data, icmp_id, icmp_seq) else {
+                    continue;
+                };
+
+                rtts.push(start.elapsed().as_secs_f64() * 1000.0);
+                let from_ip = from.as_socket().map(|a| a.ip()).unwrap_or(dest_addr.ip());
+                if hop_ip.is_none() {
+                    hop_ip = Some(from_ip);
+                }
+                if reply == Icmpv6Reply::EchoReply || from_ip == dest_addr.ip() {
+                    completed = true;
+                }
+                break;
+            }
+        }
+
+        let hop = TracerouteHop {
+            hop_number: hop_limit,
+            ip_address: hop_ip.map(|ip| ip.to_string()),
+            hostname: None,
+            asn: None,
+            as_org: None,
+            timeout: hop_ip.is_none(),
+            rtt_ms: rtts,
+        };
+
+        let _ = event_tx
+            .send(TestEvent::TracerouteHop {
+                hop_number: hop_limit,
+                hop: hop.clone(),
+            })
+            .await;
+
+        hops.push(hop);
+
+        if completed {
+            break;
+        }
+    }
+
+    Ok(TracerouteSummary {
+        destination: destination.to_string(),
+        hops,
+        completed,
+        mode: TracerouteMode::Icmp,
+    })
+}
+
+/// Run a UDP or TCP SYN traceroute.
+///
+/// Probes go out on ordinary sockets with a limited TTL; routers answer with
+/// ICMP Time Exceeded, which is read from a raw ICMP socket and matched to the
+/// probe by the ports quoted in the error. The destination answers a UDP probe
+/// with Port Unreachable and a TCP probe with SYN-ACK or RST.
+async fn run_transport_traceroute(
+    destination: &IpAddr,
+    port: u16,
+    mode: TracerouteMode,
+    max_hops: u8,
+    event_tx: &mpsc::Sender<TestEvent>,
+) -> Result<TracerouteSummary> {
+    let icmp_socket = match destination {
+        IpAddr::V4(_) => Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4))
+            .context("Failed to create raw ICMP socket (need CAP_NET_RAW or root)")?,
+        IpAddr::V6(_) => Socket::new(Domain::IPV6, Type::RAW, Some(Protocol::ICMPV6))
+            .context("Failed to create raw ICMPv6 socket (need CAP_NET_RAW or root)")?,
+    };
+    icmp_socket.set_nonblocking(false)?;
+
+    let mut hops = Vec::new();
+    let mut completed = false;
+
+    for ttl in 1..=max_hops {
+        let mut rtts = Vec::new();
+        let mut hop_ip: Option<IpAddr> = None;
+
+        for probe_num in 0..PROBES_PER_HOP {
+            let dest_port = match mode {
+                TracerouteMode::Tcp => port,
+                _ => UDP_BASE_PORT
+                    .wrapping_add((ttl as u16 - 1) * PROBES_PER_HOP as u16 + probe_num as u16),
+            };
+            let dest_addr = SocketAddr::new(*destination, dest_port);
+
+            let start = Instant::now();
+            let (probe, src_port) = match send_transport_probe(mode, dest_addr, ttl) {
+                Ok(sent) => sent,
+                Err(_) => continue,
+            };
+
+            let deadline = start + PROBE_TIMEOUT;
+            let mut draining = false;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+
+                // A SYN-ACK or RST from the destination never reaches the raw
+                // socket, so TCP probes read it in short slices and check the
+                // probe socket in between. Any other connect error (e.g. the
+                // EHOSTUNREACH a Time Exceeded sets) leaves the ICMP copy in
+                // the raw socket: read what is queued there before giving up.
+                if mode == TracerouteMode::Tcp && !draining {
+                    match tcp_probe_answered(&probe) {
+                        Some(true) => {
+                            rtts.push(start.elapsed().as_secs_f64() * 1000.0);
+                            hop_ip.get_or_insert(*destination);
+                            completed = true;
+                            break;
+                        }
+                        Some(false) => {
+                            draining = true;
+                            icmp_socket.set_nonblocking(true)?;
+                        }
+                        None => {
+                            icmp_socket.set_read_timeout(Some(remaining.min(TCP_POLL_INTERVAL)))?;
+                        }
+                    }
+                } else if !draining {
+                    icmp_socket.set_read_timeout(Some(remaining))?;
+                }
+
+                let (data, from) = match recv_icmp(&icmp_socket, 1280) {
+                    Ok(r) => r,
+                    Err(e)
+                        if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut =>
+                    {
+                        if mode == TracerouteMode::Tcp && !draining {
+                            continue;
+                        }
+                        break;
+                    }
+                    Err(_) => break,
+                };
+
+                let quoted = match destination {
+                    IpAddr::V4(_) => parse_quoted_probe_v4(&data),
+                    IpAddr::V6(_) => parse_quoted_probe_v6(&data),
+                };
+                let Some(quoted) = quoted else {
+                    continue;
+                };
+                let protocol = match mode {
+                    TracerouteMode::Tcp => IpNextHeaderProtocols::Tcp,
+                    _ => IpNextHeaderProtocols::Udp,
+                };
+                if quoted.protocol != protocol.0
+                    || quoted.src_port != src_port
+                    || quoted.dst_port != dest_port
+                {
+                    continue;
+                }
+
+                rtts.push(start.elapsed().as_secs_f64() * 1000.0);
+                let from_ip = from.as_socket().map(|a| a.ip()).unwrap_or(*destination);
+                if hop_ip.is_none() {
+                    hop_ip = Some(from_ip);
+                }
+                if quoted.unreachable || from_ip == *destination {
+                    completed = true;
+                }
+                break;
+            }
+            if draining {
+                icmp_socket.set_nonblocking(false)?;
+            }
+        }
+
+        let hop = TracerouteHop {
+            hop_number: ttl,
+            ip_address: hop_ip.map(|ip| ip.to_string()),
+            hostname: None,
+            asn: None,
+            as_org: None,
+            timeout: hop_ip.is_none(),
+            rtt_ms: rtts,
+        };
+
+        let _ = event_tx
+            .send(TestEvent::TracerouteHop {
+                hop_number: ttl,
+                hop: hop.clone(),
+            })
+            .await;
+
+        hops.push(hop);
+
+        if completed {
+            break;
+        }
+    }
+
+    Ok(TracerouteSummary {
+        destination: destination.to_string(),
+        hops,
+        completed,
+        mode,
+    })
+}
+
+/// Open a socket with the given TTL and send one UDP datagram or TCP SYN.
+///
+/// Returns the socket (which must stay open until the probe is answered) and
+/// its local port, used to recognize the probe quoted in ICMP errors.
+fn send_transport_probe(mode: TracerouteMode, dest: SocketAddr, ttl: u8) -> Result<(Socket, u16)> {
+    let domain = Domain::for_address(dest);
+    let socket = match mode {
+        TracerouteMode::Tcp => Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?,
+        _ => Socket::new(domain, Type::DGRAM, Some(Protocol::UDP))?,
+    };
+    if dest.is_ipv4() {
+        socket.set_ttl(ttl as u32)?;
+    } else {
+        socket.set_unicast_hops_v6(ttl as u32)?;
+    }
+
+    let unspecified = if dest.is_ipv4() {
+        AddressFamily::V4
+    } else {
+        AddressFamily::V6
+    }
+    .unspecified();
+    socket.bind(&SocketAddr::new(unspecified, 0).into())?;
+    let src_port = socket
+        .local_addr()?
+        .as_socket()
+        .map(|a| a.port())
+        .context("Probe socket has no local port")?;
+
+    match mode {
+        TracerouteMode::Tcp => {
+            // Non-blocking connect sends the SYN and returns immediately
+            socket.set_nonblocking(true)?;
+            match socket.connect(&dest.into()) {
+                Ok(()) => {}
+                Err(e)
+                    if e.kind() == ErrorKind::WouldBlock
+                        || e.raw_os_error() == Some(libc::EINPROGRESS) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        _ => {
+            socket.send_to(&[0u8; 32], &dest.into())?;
+        }
+    }
+
+    Ok((socket, src_port))
+}
+
+/// Check whether a pending TCP probe has finished.
+///
+/// `Some(true)` means the destination answered (SYN-ACK or RST), `Some(false)`
+/// that the connect failed for another reason, such as an ICMP error from a
+/// router, `None` that it is still pending.
+fn tcp_probe_answered(socket: &Socket) -> Option<bool> {
+    match socket.take_error() {
+        Ok(Some(e)) => return Some(e.kind() == ErrorKind::ConnectionRefused),
+        Ok(None) => {}
+        Err(_) => return Some(false),
+    }
+    // getpeername only succeeds once the handshake has completed
+    socket.peer_addr().ok().map(|_| true)
+}
+
+/// ICMP error quoting the transport header of one of our UDP/TCP probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QuotedProbe {
+    /// Destination Unreachable rather than Time Exceeded
+    unreachable: bool,
+    /// IP protocol number of the quoted packet
+    protocol: u8,
+    src_port: u16,
+    dst_port: u16,
+}
+
+/// Parse an ICMPv4 error as received on a raw socket (outer IPv4 header included).
+fn parse_quoted_probe_v4(data: &[u8]) -> Option<QuotedProbe> {
+    let outer_len = (*data.first()? as usize & 0x0f) * 4;
+    let icmp = data.get(outer_len..)?;
+    let unreachable = match *icmp.first()? {
+        t if t == IcmpTypes::TimeExceeded.0 => false,
+        t if t == IcmpTypes::DestinationUnreachable.0 => true,
+        _ => return None,
+    };
+
+    let inner = icmp.get(8..)?;
+    let inner_len = (*inner.first()? as usize & 0x0f) * 4;
+    let protocol = *inner.get(9)?;
+    let ports = inner.get(inner_len..inner_len + 4)?;
+    Some(QuotedProbe {
+        unreachable,
+        protocol,
+        src_port: u16::from_be_bytes([ports[0], ports[1]]),
+        dst_port: u16::from_be_bytes([ports[2], ports[3]]),
     })
 }
 
+/// Parse an ICMPv6 error as received on a raw socket (no outer IPv6 header).
+fn parse_quoted_probe_v6(data: &[u8]) -> Option<QuotedProbe> {
+    let unreachable = match *data.first()? {
+        t if t == Icmpv6Types::TimeExceeded.0 => false,
+        t if t == Icmpv6Types::DestinationUnreachable.0 => true,
+        _ => return None,
+    };
+
+    let inner = Ipv6Packet::new(data.get(8..)?)?;
+    let ports = data.get(48..52)?;
+    Some(QuotedProbe {
+        unreachable,
+        protocol: inner.get_next_header().0,
+        src_port: u16::from_be_bytes([ports[0], ports[1]]),
+        dst_port: u16::from_be_bytes([ports[2], ports[3]]),
+    })
+}
+
+/// Kind of ICMPv6 message that answered one of our probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Icmpv6Reply {
+    /// Hop limit exceeded at an intermediate router
+    TimeExceeded,
+    /// Destination (or a router in front of it) rejected the probe
+    Unreachable,
+    /// The destination answered the echo request
+    EchoReply,
+}
+
+/// Build an ICMPv6 echo request. The checksum is left zero for the kernel to fill.
+pub fn build_icmpv6_packet(id: u16, seq: u16) -> Vec<u8> {
+    let mut packet = vec![0u8; 64];
+    let payload: Vec<u8> = (0..56).map(|i| i as u8).collect();
+
+    let mut echo = MutableEchoRequestPacket::new(&mut packet).expect("buffer fits echo header");
+    echo.set_icmpv6_type(Icmpv6Types::EchoRequest);
+    echo.set_icmpv6_code(Icmpv6Code::new(0));
+    echo.set_identifier(id);
+    echo.set_sequence_number(seq);
+    echo.set_payload(&payload);
+
+    packet
+}
+
+/// Check whether an ICMPv6 message answers the probe with the given id/seq.
+///
+/// Echo Replies carry id/seq directly; Time Exceeded and Destination
+/// Unreachable quote the original IPv6 header plus our echo request after a
+/// 4-byte unused field.
+fn parse_icmpv6_reply(data: &[u8], id: u16, seq: u16) -> Option<Icmpv6Reply> {
+    let icmp = Icmpv6Packet::new(data)?;
+    let kind = match icmp.get_icmpv6_type() {
+        Icmpv6Types::EchoReply => {
+            let reply = EchoReplyPacket::new(data)?;
+            return (reply.get_identifier() == id && reply.get_sequence_number() == seq)
+                .then_some(Icmpv6Reply::EchoReply);
+        }
+        Icmpv6Types::TimeExceeded => Icmpv6Reply::TimeExceeded,
+        Icmpv6Types::DestinationUnreachable => Icmpv6Reply::Unreachable,
+        _ => return None,
+    };
+
+    let quoted = Ipv6Packet::new(data.get(8..)?)?;
+    if quoted.get_next_header() != IpNextHeaderProtocols::Icmpv6 {
+        return None;
+    }
+    let original = EchoRequestPacket::new(data.get(8 + 40..)?)?;
+    (original.get_icmpv6_type() == Icmpv6Types::EchoRequest
+        && original.get_identifier() == id
+        && original.get_sequence_number() == seq)
+        .then_some(kind)
+}
+
 /// Build an ICMP echo request packet.
-fn build_icmp_packet(id: u16, seq: u16) -> Vec<u8> {
+pub fn build_icmp_packet(id: u16, seq: u16) -> Vec<u8> {
     let mut packet = vec![0u8; 64];
 
     // ICMP header
@@ -208,8 +666,20 @@ fn build_icmp_packet(id: u16, seq: u16) -> Vec<u8> {
     packet
 }
 
+/// Receive one packet of up to `buf_len` bytes from a raw socket.
+pub fn recv_icmp(socket: &Socket, buf_len: usize) -> io::Result<(Vec<u8>, SockAddr)> {
+    let mut buf = vec![MaybeUninit::<u8>::uninit(); buf_len];
+    let (len, from) = socket.recv_from(&mut buf)?;
+    // Safe to read the first `len` bytes: they were written by recv_from
+    let data = buf[..len]
+        .iter()
+        .map(|b| unsafe { b.assume_init() })
+        .collect();
+    Ok((data, from))
+}
+
 /// Calculate ICMP checksum.
-fn calculate_icmp_checksum(data: &[u8]) -> u16 {
+pub fn calculate_icmp_checksum(data: &[u8]) -> u16 {
     let mut sum: u32 = 0;
     let mut i = 0;
 
@@ -229,48 +699,114 @@ fn calculate_icmp_checksum(data: &[u8]) -> u16 {
     !sum as u16
 }
 
-/// Try to resolve an IP address to a hostname.
-fn resolve_hostname(_ip: &IpAddr) -> Option<String> {
-    // Skip hostname resolution for now to keep it simple
-    // In production, we'd want async reverse DNS resolution
+/// Fill in hop hostnames with concurrent reverse-DNS (PTR) lookups.
+///
+/// Each lookup is bounded by [`PTR_TIMEOUT`]; hops whose address has no PTR
+/// record, or whose lookup times out, keep `hostname: None`.
+async fn resolve_hostnames(hops: &mut [TracerouteHop]) {
+    let lookups = hops.iter().map(|hop| {
+        let ip = hop
+            .ip_address
+            .as_deref()
+            .and_then(|s| s.parse::<IpAddr>().ok());
+        async move {
+            let ip = ip?;
+            tokio::time::timeout(
+                PTR_TIMEOUT,
+                tokio::task::spawn_blocking(move || lookup_ptr(ip)),
+            )
+            .await
+            .ok()?
+            .ok()?
+        }
+    });
+    let names = futures::future::join_all(lookups).await;
+
+    for (hop, name) in hops.iter_mut().zip(names) {
+        if name.is_some() {
+            hop.hostname = name;
+        }
+    }
+}
+
+/// Reverse-resolve one address through the system resolver (blocking).
+#[cfg(unix)]
+fn lookup_ptr(ip: IpAddr) -> Option<String> {
+    let addr = socket2::SockAddr::from(SocketAddr::new(ip, 0));
+    let mut host = [0 as libc::c_char; 1025]; // NI_MAXHOST
+
+    // NI_NAMEREQD: fail instead of returning the numeric address
+    let rc = unsafe {
+        libc::getnameinfo(
+            addr.as_ptr(),
+            addr.len(),
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if rc != 0 {
+        return None;
+    }
+
+    // Safe: getnameinfo wrote a NUL-terminated string into `host`
+    let name = unsafe { std::ffi::CStr::from_ptr(host.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn lookup_ptr(_ip: IpAddr) -> Option<String> {
     None
 }
 
 /// Fall back to system traceroute command.
+///
+/// `tracert` only sends ICMP; `traceroute` sends UDP unless asked for TCP.
 async fn run_system_traceroute(
     destination: &str,
     destination_ip: &IpAddr,
+    port: u16,
+    mode: TracerouteMode,
     max_hops: u8,
     event_tx: &mpsc::Sender<TestEvent>,
 ) -> Result<TracerouteSummary> {
-    // Clone strings to avoid lifetime issues with spawn_blocking
-    let dest = destination.to_string();
+    // Clone strings to avoid lifetime issues with spawn_blocking.
+    // Trace the resolved IP so the system tool uses the same address family.
     let dest_ip_str = destination_ip.to_string();
+    let dest = dest_ip_str.clone();
 
     // Determine which command to use based on OS
-    let (cmd, args): (&'static str, Vec<String>) = if cfg!(target_os = "windows") {
-        (
-            "tracert",
-            vec![
-                "-h".to_string(),
-                max_hops.to_string(),
-                "-d".to_string(),
-                dest.clone(),
-            ],
-        )
-    } else {
-        (
-            "traceroute",
-            vec![
+    let (cmd, args, used_mode): (&'static str, Vec<String>, TracerouteMode) =
+        if cfg!(target_os = "windows") {
+            (
+                "tracert",
+                vec![
+                    "-h".to_string(),
+                    max_hops.to_string(),
+                    "-d".to_string(),
+                    dest.clone(),
+                ],
+                TracerouteMode::Icmp,
+            )
+        } else {
+            let mut args = vec![
                 "-m".to_string(),
                 max_hops.to_string(),
                 "-n".to_string(),
                 "-q".to_string(),
                 "3".to_string(),
-                dest.clone(),
-            ],
-        )
-    };
+            ];
+            let used_mode = if mode == TracerouteMode::Tcp {
+                args.extend(["-T".to_string(), "-p".to_string(), port.to_string()]);
+                TracerouteMode::Tcp
+            } else {
+                TracerouteMode::Udp
+            };
+            args.push(dest.clone());
+            ("traceroute", args, used_mode)
+        };
 
     let output = tokio::task::spawn_blocking(move || Command::new(cmd).args(&args).output())
         .await
@@ -289,6 +825,7 @@ async fn run_system_traceroute(
         destination: destination.to_string(),
         hops,
         completed,
+        mode: used_mode,
     })
 }
 
@@ -346,6 +883,8 @@ fn parse_hop_line(line: &str) -> Option<TracerouteHop> {
             hop_number,
             ip_address: None,
             hostname: None,
+            asn: None,
+            as_org: None,
             rtt_ms: Vec::new(),
             timeout: true,
         });
@@ -393,7 +932,87 @@ fn parse_hop_line(line: &str) -> Option<TracerouteHop> {
         hop_number,
         ip_address,
         hostname: None,
+        asn: None,
+        as_org: None,
         rtt_ms: rtts,
         timeout: false,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_icmpv6_reply() {
+        // Echo Reply: same layout as the request, type 129
+        let mut reply = build_icmpv6_packet(0x1234, 0x0102);
+        reply[0] = Icmpv6Types::EchoReply.0;
+        assert_eq!(
+            parse_icmpv6_reply(&reply, 0x1234, 0x0102),
+            Some(Icmpv6Reply::EchoReply)
+        );
+        assert_eq!(parse_icmpv6_reply(&reply, 0x1234, 0x0103), None);
+
+        // Time Exceeded: type, code, checksum, 4 unused bytes, then the quoted
+        // IPv6 header (next header = 58) followed by our echo request
+        let mut exceeded = vec![Icmpv6Types::TimeExceeded.0, 0, 0, 0, 0, 0, 0, 0];
+        let mut ipv6_header = vec![0u8; 40];
+        ipv6_header[0] = 0x60;
+        ipv6_header[6] = IpNextHeaderProtocols::Icmpv6.0;
+        exceeded.extend_from_slice(&ipv6_header);
+        exceeded.extend_from_slice(&build_icmpv6_packet(0x1234, 0x0102));
+        assert_eq!(
+            parse_icmpv6_reply(&exceeded, 0x1234, 0x0102),
+            Some(Icmpv6Reply::TimeExceeded)
+        );
+        assert_eq!(parse_icmpv6_reply(&exceeded, 0x9999, 0x0102), None);
+
+        // Truncated quote is ignored
+        assert_eq!(parse_icmpv6_reply(&exceeded[..20], 0x1234, 0x0102), None);
+    }
+
+    #[test]
+    fn test_parse_quoted_probe() {
+        // ICMPv4 Time Exceeded quoting a UDP probe 40000 -> 33434
+        let mut packet = vec![0u8; 20];
+        packet[0] = 0x45;
+        packet.extend_from_slice(&[IcmpTypes::TimeExceeded.0, 0, 0, 0, 0, 0, 0, 0]);
+        let mut inner = vec![0u8; 20];
+        inner[0] = 0x45;
+        inner[9] = IpNextHeaderProtocols::Udp.0;
+        packet.extend_from_slice(&inner);
+        packet.extend_from_slice(&[0x9c, 0x40, 0x82, 0x9a, 0, 8, 0, 0]);
+        assert_eq!(
+            parse_quoted_probe_v4(&packet),
+            Some(QuotedProbe {
+                unreachable: false,
+                protocol: IpNextHeaderProtocols::Udp.0,
+                src_port: 40000,
+                dst_port: 33434,
+            })
+        );
+        assert_eq!(parse_quoted_probe_v4(&packet[..50]), None);
+
+        // ICMPv6 Destination Unreachable quoting a TCP SYN 40000 -> 443
+        let mut packet = vec![Icmpv6Types::DestinationUnreachable.0, 4, 0, 0, 0, 0, 0, 0];
+        let mut ipv6_header = vec![0u8; 40];
+        ipv6_header[0] = 0x60;
+        ipv6_header[6] = IpNextHeaderProtocols::Tcp.0;
+        packet.extend_from_slice(&ipv6_header);
+        packet.extend_from_slice(&[0x9c, 0x40, 0x01, 0xbb, 0, 0, 0, 0]);
+        assert_eq!(
+            parse_quoted_probe_v6(&packet),
+            Some(QuotedProbe {
+                unreachable: true,
+                protocol: IpNextHeaderProtocols::Tcp.0,
+                src_port: 40000,
+                dst_port: 443,
+            })
+        );
+
+        // Echo replies are not probe errors
+        let reply = build_icmpv6_packet(1, 1);
+        assert_eq!(parse_quoted_probe_v6(&reply), None);
+    }
+}
@@ -0,0 +1,249 @@
+//! UDP loss and RTT through a TURN relay (RFC 5766), authenticated with the
+//! STUN long-term credential mechanism (RFC 5389 section 10.2)
+//!
+//! One allocation relays to itself: after a CreatePermission for its own
+//! relayed address, Send indications addressed to that relayed address come
+//! back to us as Data indications. Every probe therefore travels client ->
+//! relay -> client. Servers that refuse to relay to their own addresses
+//! reject the CreatePermission.
+
+use crate::engine::network_bind::{self, AddressFamily};
+use crate::engine::stun::{decode_xor_address, Message, MessageBuilder, SUCCESS_CLASS};
+use crate::engine::turn_udp::{self, STUN_HEADER_LEN};
+use crate::model::{ExperimentalUdpSummary, RunConfig, TestEvent, TurnInfo};
+use anyhow::{Context, Result};
+use md5::{Digest, Md5};
+use rand::RngCore;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+
+// Message types: method plus class bits
+const ALLOCATE_REQUEST: u16 = 0x0003;
+const REFRESH_REQUEST: u16 = 0x0004;
+const CREATE_PERMISSION_REQUEST: u16 = 0x0008;
+const SEND_INDICATION: u16 = 0x0016;
+const DATA_INDICATION: u16 = 0x0017;
+
+const ATTR_USERNAME: u16 = 0x0006;
+const ATTR_LIFETIME: u16 = 0x000D;
+const ATTR_XOR_PEER_ADDRESS: u16 = 0x0012;
+const ATTR_DATA: u16 = 0x0013;
+const ATTR_REALM: u16 = 0x0014;
+const ATTR_NONCE: u16 = 0x0015;
+const ATTR_XOR_RELAYED_ADDRESS: u16 = 0x0016;
+const ATTR_REQUESTED_TRANSPORT: u16 = 0x0019;
+
+/// REQUESTED-TRANSPORT value for UDP: protocol number 17, then reserved bytes
+const TRANSPORT_UDP: [u8; 4] = [17, 0, 0, 0];
+
+/// Transmissions of each request, and how long to wait after each one
+const REQUEST_ATTEMPTS: u32 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Long-term credential key: MD5(username ":" realm ":" password)
+fn long_term_key(username: &str, realm: &[u8], password: &str) -> [u8; 16] {
+    let mut input = format!("{}:", username).into_bytes();
+    input.extend_from_slice(realm);
+    input.extend_from_slice(format!(":{}", password).as_bytes());
+    Md5::digest(&input).into()
+}
+
+/// First `turn:` URL that relays over UDP: no transport given, or UDP
+fn pick_turn_url(turn: &TurnInfo) -> Option<&str> {
+    turn.urls.iter().map(String::as_str).find(|u| {
+        u.starts_with("turn:") && (!u.contains("transport=") || u.contains("transport=udp"))
+    })
+}
+
+/// Authenticated request/response exchange with one TURN server
+struct TurnSession {
+    sock: Arc<UdpSocket>,
+    username: String,
+    password: String,
+    /// Learned from the server's 401 challenge
+    realm: Option<Vec<u8>>,
+    nonce: Option<Vec<u8>>,
+}
+
+impl TurnSession {
+    /// Send a request and return its success response. The first 401 (or a
+    /// 438 stale nonce) supplies realm and nonce, and the request is
+    /// repeated with credentials.
+    async fn request(
+        &mut self,
+        msg_type: u16,
+        attrs: impl Fn(MessageBuilder) -> MessageBuilder,
+    ) -> Result<Vec<u8>> {
+        for _ in 0..3 {
+            let mut txid = [0u8; 12];
+            rand::thread_rng().fill_bytes(&mut txid);
+            let mut msg = attrs(MessageBuilder::new(msg_type, txid));
+            if let (Some(realm), Some(nonce)) = (&self.realm, &self.nonce) {
+                let key = long_term_key(&self.username, realm, &self.password);
+                msg = msg
+                    .attr(ATTR_USERNAME, self.username.as_bytes())
+                    .attr(ATTR_REALM, realm)
+                    .attr(ATTR_NONCE, nonce)
+                    .integrity(&key);
+            }
+
+            let buf = self.transact(&msg.finish(), txid).await?;
+            let resp = Message::parse(&buf).context("malformed TURN response")?;
+            if resp.msg_type == msg_type | SUCCESS_CLASS {
+                return Ok(buf);
+            }
+            let code = resp.error_code();
+            let challenged = match code {
+                Some(401) => self.realm.is_none(),
+                Some(438) => true,
+                _ => false,
+            };
+            if !challenged {
+                anyhow::bail!(
+                    "server answered with error {}",
+                    code.map_or_else(|| "without a code".to_string(), |c| c.to_string())
+                );
+            }
+            self.realm = resp.attr(ATTR_REALM).map(<[u8]>::to_vec);
+            self.nonce = resp.attr(ATTR_NONCE).map(<[u8]>::to_vec);
+            anyhow::ensure!(
+                self.realm.is_some() && self.nonce.is_some(),
+                "auth challenge without realm or nonce"
+            );
+        }
+        anyhow::bail!("server kept rejecting the credentials")
+    }
+
+    /// Send `msg` until a response with the same transaction ID arrives
+    async fn transact(&self, msg: &[u8], txid: [u8; 12]) -> Result<Vec<u8>> {
+        let mut buf = [0u8; 1500];
+        for _ in 0..REQUEST_ATTEMPTS {
+            self.sock.send(msg).await?;
+            let deadline = tokio::time::Instant::now() + REQUEST_TIMEOUT;
+            while let Ok(n) = tokio::time::timeout_at(deadline, self.sock.recv(&mut buf)).await {
+                let n = n?;
+                if n >= STUN_HEADER_LEN && buf[8..20] == txid {
+                    return Ok(buf[..n].to_vec());
+                }
+            }
+        }
+        anyhow::bail!("no response from TURN server")
+    }
+}
+
+/// Send indication carrying `token` to `peer`, DATA zero-padded to `data_len`
+fn send_indication(peer: SocketAddr, token: [u8; 12], data_len: usize) -> Vec<u8> {
+    let mut txid = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut txid);
+    let mut data = vec![0u8; data_len.max(token.len())];
+    data[..token.len()].copy_from_slice(&token);
+    MessageBuilder::new(SEND_INDICATION, txid)
+        .xor_address(ATTR_XOR_PEER_ADDRESS, peer)
+        .attr(ATTR_DATA, &data)
+        .finish()
+}
+
+/// Probe token from a Data indication the relay delivered back to us
+fn data_indication_token(buf: &[u8]) -> Option<[u8; 12]> {
+    let msg = Message::parse(buf)?;
+    if msg.msg_type != DATA_INDICATION {
+        return None;
+    }
+    msg.attr(ATTR_DATA)?.get(..12)?.try_into().ok()
+}
+
+/// Allocate a relay with `turn`'s credentials and send `attempts` probes
+/// through it at `cfg.udp_pps`.
+pub async fn run_relay_probe(
+    turn: &TurnInfo,
+    cfg: &RunConfig,
+    attempts: u64,
+    event_tx: &mpsc::Sender<TestEvent>,
+) -> Result<ExperimentalUdpSummary> {
+    let target_url = pick_turn_url(turn).context("no turn: url with UDP transport")?;
+    let (host, port) = turn_udp::parse_host_port(target_url)?;
+    let username = turn.username.clone().context("TURN username missing")?;
+    let password = turn.credential.clone().context("TURN credential missing")?;
+
+    let family = AddressFamily::from_config(cfg);
+    let server = network_bind::lookup_host(&host, port, family)
+        .await?
+        .into_iter()
+        .next()
+        .context("dns returned no addresses")?;
+    let sock = turn_udp::bind_udp_socket(cfg, server).await?;
+    sock.connect(server).await?;
+
+    let mut session = TurnSession {
+        sock: Arc::new(sock),
+        username,
+        password,
+        realm: None,
+        nonce: None,
+    };
+
+    let buf = session
+        .request(ALLOCATE_REQUEST, |m| {
+            m.attr(ATTR_REQUESTED_TRANSPORT, &TRANSPORT_UDP)
+        })
+        .await
+        .context("TURN Allocate failed")?;
+    let resp = Message::parse(&buf).context("malformed TURN response")?;
+    let relayed = resp
+        .attr(ATTR_XOR_RELAYED_ADDRESS)
+        .and_then(|v| decode_xor_address(v, &resp.txid))
+        .context("Allocate response without a relayed address")?;
+
+    let probe = async {
+        session
+            .request(CREATE_PERMISSION_REQUEST, |m| {
+                m.xor_address(ATTR_XOR_PEER_ADDRESS, relayed)
+            })
+            .await
+            .context("TURN CreatePermission failed")?;
+
+        // Size the DATA payload so the whole Send indication matches the
+        // configured probe size
+        let overhead = send_indication(relayed, [0; 12], 0).len() - 12;
+        let data_len = cfg.udp_packet_size.saturating_sub(overhead);
+        turn_udp::run_probe_schedule(
+            session.sock.clone(),
+            attempts,
+            cfg.udp_pps,
+            |token| send_indication(relayed, token, data_len),
+            data_indication_token,
+            event_tx,
+        )
+        .await
+    };
+    let summary = probe.await;
+
+    // Release the allocation rather than leaving it to expire
+    let _ = session
+        .request(REFRESH_REQUEST, |m| {
+            m.attr(ATTR_LIFETIME, &0u32.to_be_bytes())
+        })
+        .await;
+
+    Ok(ExperimentalUdpSummary {
+        target: Some(target_url.to_string()),
+        ..summary?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_indication_token() {
+        let token = [5u8; 12];
+        let mut msg = send_indication("192.0.2.1:3478".parse().unwrap(), token, 100);
+        assert_eq!(data_indication_token(&msg), None);
+        msg[..2].copy_from_slice(&DATA_INDICATION.to_be_bytes());
+        assert_eq!(data_indication_token(&msg), Some(token));
+    }
+}
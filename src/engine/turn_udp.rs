@@ -1,13 +1,15 @@
-use crate::engine::network_bind;
+use crate::engine::network_bind::{self, AddressFamily};
 use crate::model::{ExperimentalUdpSummary, RunConfig, TestEvent, TurnInfo};
 use crate::stats::{latency_summary_from_samples, OnlineStats};
 use anyhow::{Context, Result};
 use rand::RngCore;
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::net::UdpSocket;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
 /// Calculate Mean Opinion Score (MOS) using simplified ITU-T G.107 E-model.
 /// (this is lifted from Claude I haven't verified it yet)
@@ -63,38 +65,68 @@ fn quality_label(loss_pct: f64) -> &'static str {
     }
 }
 
+pub(super) const STUN_HEADER_LEN: usize = 20;
+/// SOFTWARE attribute: comprehension-optional, so servers that do not care
+/// about its contents still answer, which makes it usable as padding
+const STUN_ATTR_SOFTWARE: u16 = 0x8022;
+/// RFC 5389 caps the SOFTWARE value at 763 bytes, so padded requests stop at
+/// header + attribute header + 763. (PADDING from RFC 5780 has no such cap,
+/// but servers without RFC 5780 support reject it.)
+const STUN_MAX_REQUEST_LEN: usize = STUN_HEADER_LEN + 4 + 763;
+/// Replies slower than this count as lost
+const REPLY_TIMEOUT: Duration = Duration::from_millis(600);
+
+/// Outstanding probes by token: sequence number and send time
+type PendingProbes = Arc<Mutex<HashMap<[u8; 12], (u64, Instant)>>>;
+
 // Minimal STUN binding request (RFC5389):
 // - type: 0x0001
-// - length: 0
+// - length: attribute bytes after the header
 // - magic cookie: 0x2112A442
 // - transaction id: 12 bytes random
-fn build_stun_binding_request(txid: [u8; 12]) -> [u8; 20] {
-    let mut b = [0u8; 20];
+// Requests larger than the bare header carry a SOFTWARE attribute as filler;
+// `size` is capped at `STUN_MAX_REQUEST_LEN` and otherwise rounded up to the
+// 4-byte attribute alignment.
+fn build_stun_binding_request(txid: [u8; 12], size: usize) -> Vec<u8> {
+    let size = size.min(STUN_MAX_REQUEST_LEN);
+    let mut b = vec![0u8; STUN_HEADER_LEN];
     b[0] = 0x00;
     b[1] = 0x01;
-    b[2] = 0x00;
-    b[3] = 0x00;
     b[4] = 0x21;
     b[5] = 0x12;
     b[6] = 0xA4;
     b[7] = 0x42;
     b[8..20].copy_from_slice(&txid);
+
+    // The attribute header alone takes 4 bytes
+    if size > STUN_HEADER_LEN {
+        // The length field carries the value length, the padding to the
+        // next 4-byte boundary is not part of it
+        let value_len = (size - STUN_HEADER_LEN).saturating_sub(4);
+        b.extend_from_slice(&STUN_ATTR_SOFTWARE.to_be_bytes());
+        b.extend_from_slice(&(value_len as u16).to_be_bytes());
+        b.resize(b.len() + value_len, b' ');
+        b.resize(b.len().div_ceil(4) * 4, 0);
+        let attrs_len = (b.len() - STUN_HEADER_LEN) as u16;
+        b[2..4].copy_from_slice(&attrs_len.to_be_bytes());
+    }
     b
 }
 
-fn is_stun_binding_response(buf: &[u8], txid: [u8; 12]) -> bool {
-    if buf.len() < 20 {
-        return false;
+/// Transaction ID of a binding success response, if `buf` is one.
+fn stun_binding_response_txid(buf: &[u8]) -> Option<[u8; 12]> {
+    if buf.len() < STUN_HEADER_LEN {
+        return None;
     }
     // binding success response
     if buf[0] != 0x01 || buf[1] != 0x01 {
-        return false;
+        return None;
     }
     // magic cookie
     if buf[4] != 0x21 || buf[5] != 0x12 || buf[6] != 0xA4 || buf[7] != 0x42 {
-        return false;
+        return None;
     }
-    buf[8..20] == txid
+    buf[8..20].try_into().ok()
 }
 
 fn pick_stun_target(turn: &TurnInfo) -> Option<String> {
@@ -112,16 +144,23 @@ fn pick_stun_target(turn: &TurnInfo) -> Option<String> {
     None
 }
 
-fn parse_host_port(url: &str) -> Result<(String, u16)> {
+pub(super) fn parse_host_port(url: &str) -> Result<(String, u16)> {
     // Accept forms:
     // - stun:host:port
     // - stun:host
     // - turn:host:port?transport=udp
+    // - stun:[2001:db8::1]:port
     const DEFAULT_STUN_PORT: u16 = 3478;
 
     let (_, rest) = url.split_once(':').context("bad stun/turn url")?;
     let (hostport, _) = rest.split_once('?').unwrap_or((rest, ""));
-    let (host, port_str) = hostport.split_once(':').unwrap_or((hostport, ""));
+    let (host, port_str) = match hostport.strip_prefix('[') {
+        Some(v6) => {
+            let (host, after) = v6.split_once(']').context("unclosed [ in stun/turn url")?;
+            (host, after.strip_prefix(':').unwrap_or(after))
+        }
+        None => hostport.split_once(':').unwrap_or((hostport, "")),
+    };
 
     anyhow::ensure!(!host.is_empty(), "empty host in stun/turn url");
 
@@ -136,26 +175,63 @@ fn parse_host_port(url: &str) -> Result<(String, u16)> {
     Ok((host.to_string(), port))
 }
 
+/// Host and port of the server the STUN probe talks to
+pub(super) fn stun_host_port(turn: &TurnInfo) -> Result<(String, u16)> {
+    parse_host_port(&pick_stun_target(turn).context("no stun/turn url in /__turn")?)
+}
+
+/// Send `attempts` STUN binding requests at `cfg.udp_pps` and summarize
+/// loss, latency and jitter from the replies.
 pub async fn run_udp_like_loss_probe(
     turn: &TurnInfo,
     cfg: &RunConfig,
+    attempts: u64,
     event_tx: &mpsc::Sender<TestEvent>,
     pre_resolved: Option<SocketAddr>,
 ) -> Result<ExperimentalUdpSummary> {
     let target_url = pick_stun_target(turn).context("no stun/turn url in /__turn")?;
     let (host, port) = parse_host_port(&target_url)?;
 
+    let family = AddressFamily::from_config(cfg);
     let addr: SocketAddr = if let Some(a) = pre_resolved {
         a
     } else {
-        let mut addrs = tokio::net::lookup_host((host.as_str(), port)).await?;
-        addrs.next().context("dns returned no addresses")?
+        network_bind::lookup_host(&host, port, family)
+            .await?
+            .into_iter()
+            .next()
+            .context("dns returned no addresses")?
     };
 
+    let sock = bind_udp_socket(cfg, addr).await?;
+    sock.connect(addr).await?;
+
+    let packet_size = cfg.udp_packet_size.max(STUN_HEADER_LEN);
+    let summary = run_probe_schedule(
+        Arc::new(sock),
+        attempts,
+        cfg.udp_pps,
+        |txid| build_stun_binding_request(txid, packet_size),
+        stun_binding_response_txid,
+        event_tx,
+    )
+    .await?;
+    Ok(ExperimentalUdpSummary {
+        target: Some(target_url),
+        ..summary
+    })
+}
+
+/// UDP socket for talking to `addr`, bound to the configured interface or
+/// source IP, if any.
+pub(super) async fn bind_udp_socket(cfg: &RunConfig, addr: SocketAddr) -> Result<UdpSocket> {
     // Bind UDP socket to interface or source IP if specified
     let sock = if cfg.interface.is_some() || cfg.source_ip.is_some() {
-        let bind_addr =
-            network_bind::resolve_bind_address(cfg.interface.as_ref(), cfg.source_ip.as_ref())?;
+        let bind_addr = network_bind::resolve_bind_address(
+            cfg.interface.as_ref(),
+            cfg.source_ip.as_ref(),
+            AddressFamily::from_config(cfg),
+        )?;
 
         if let Some(addr) = bind_addr {
             // Create socket using socket2 for binding
@@ -209,78 +285,83 @@ pub async fn run_udp_like_loss_probe(
         UdpSocket::bind(bind_addr).await?
     };
 
-    sock.connect(addr).await?;
-
-    let timeout = Duration::from_millis(600);
-    let interval = Duration::from_millis(80);
-    let attempts = cfg.udp_packets;
-
-    let mut sent = 0u64;
-    let mut received = 0u64;
-    let mut samples = Vec::<f64>::new();
-    let mut online = OnlineStats::default();
-
-    // Out-of-order tracking: map transaction ID to sequence number
-    let mut txid_to_seq: HashMap<[u8; 12], u64> = HashMap::new();
-    let mut next_expected_seq: u64 = 1;
-    let mut out_of_order: u64 = 0;
+    Ok(sock)
+}
 
+/// Send `attempts` probes at `pps` on a connected socket and summarize the
+/// replies. `build` makes a probe carrying a random 12-byte token, which
+/// `reply_token` has to find in the matching reply.
+pub(super) async fn run_probe_schedule(
+    sock: Arc<UdpSocket>,
+    attempts: u64,
+    pps: u32,
+    build: impl Fn([u8; 12]) -> Vec<u8>,
+    reply_token: fn(&[u8]) -> Option<[u8; 12]>,
+    event_tx: &mpsc::Sender<TestEvent>,
+) -> Result<ExperimentalUdpSummary> {
+    let pps = pps.max(1);
+    let mut packet_size = None;
+
+    // Probes in flight, by token; the receiver removes answered ones
+    let pending: PendingProbes = Default::default();
+    let sent = Arc::new(AtomicU64::new(0));
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let receiver = tokio::spawn(receive_replies(
+        sock.clone(),
+        pending.clone(),
+        sent.clone(),
+        attempts,
+        event_tx.clone(),
+        stop_rx,
+        reply_token,
+    ));
+
+    // Send on a fixed schedule, independent of when (or whether) replies arrive
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / pps as f64));
     for seq in 1..=attempts {
-        sent += 1;
+        ticker.tick().await;
 
-        let mut txid = [0u8; 12];
-        rand::thread_rng().fill_bytes(&mut txid);
-        txid_to_seq.insert(txid, seq);
-        let pkt = build_stun_binding_request(txid);
+        let mut token = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut token);
+        let pkt = build(token);
+        packet_size = Some(pkt.len());
 
-        let start = std::time::Instant::now();
+        pending.lock().unwrap().insert(token, (seq, Instant::now()));
         let _ = sock.send(&pkt).await;
+        sent.fetch_add(1, Ordering::Relaxed);
+    }
 
-        let mut buf = [0u8; 1500];
-        let recv = tokio::time::timeout(timeout, sock.recv(&mut buf)).await;
-        match recv {
-            Ok(Ok(n)) if is_stun_binding_response(&buf[..n], txid) => {
-                received += 1;
-                let ms = start.elapsed().as_secs_f64() * 1000.0;
-                samples.push(ms);
-                online.push(ms);
-
-                // Check for out-of-order: if this packet's seq < expected, it's reordered
-                if let Some(&pkt_seq) = txid_to_seq.get(&txid) {
-                    if pkt_seq < next_expected_seq {
-                        out_of_order += 1;
-                    } else {
-                        // Update expected to next after this one
-                        next_expected_seq = pkt_seq + 1;
-                    }
-                }
+    // Give the last probes the same time to answer as every other one
+    tokio::time::sleep(REPLY_TIMEOUT).await;
+    let _ = stop_tx.send(());
+    let replies = receiver.await.context("UDP receive task failed")?;
 
-                event_tx
-                    .send(TestEvent::UdpLossProgress {
-                        sent,
-                        received,
-                        total: attempts,
-                        rtt_ms: Some(ms),
-                    })
-                    .await
-                    .ok();
-            }
-            _ => {
-                // loss/timeout
-                event_tx
-                    .send(TestEvent::UdpLossProgress {
-                        sent,
-                        received,
-                        total: attempts,
-                        rtt_ms: None,
-                    })
-                    .await
-                    .ok();
-            }
+    let sent = sent.load(Ordering::Relaxed);
+    let received = replies.len() as u64;
+    let mut samples = Vec::<f64>::with_capacity(replies.len());
+    let mut online = OnlineStats::default();
+    // A reply is out of order when a later request was answered before it
+    let mut highest_seq = 0u64;
+    let mut out_of_order: u64 = 0;
+    for (seq, ms) in replies {
+        samples.push(ms);
+        online.push(ms);
+        if seq < highest_seq {
+            out_of_order += 1;
+        } else {
+            highest_seq = seq;
         }
-
-        tokio::time::sleep(interval).await;
     }
+    // Final counts, including requests that were never answered
+    event_tx
+        .send(TestEvent::UdpLossProgress {
+            sent,
+            received,
+            total: attempts,
+            rtt_ms: samples.last().copied(),
+        })
+        .await
+        .ok();
 
     let latency = latency_summary_from_samples(sent, received, &samples, online.stddev());
 
@@ -308,11 +389,152 @@ pub async fn run_udp_like_loss_probe(
     let label = quality_label(loss_pct);
 
     Ok(ExperimentalUdpSummary {
-        target: Some(target_url),
+        target: None,
         latency,
         out_of_order,
         out_of_order_pct,
         mos,
         quality_label: label.to_string(),
+        pps: Some(pps),
+        packet_size,
     })
 }
+
+/// Match replies to pending probes by token until told to stop. Returns
+/// `(sequence, rtt_ms)` in arrival order; replies slower than
+/// `REPLY_TIMEOUT` count as lost, duplicates are ignored.
+async fn receive_replies(
+    sock: Arc<UdpSocket>,
+    pending: PendingProbes,
+    sent: Arc<AtomicU64>,
+    total: u64,
+    event_tx: mpsc::Sender<TestEvent>,
+    mut stop_rx: oneshot::Receiver<()>,
+    reply_token: fn(&[u8]) -> Option<[u8; 12]>,
+) -> Vec<(u64, f64)> {
+    let mut replies = Vec::new();
+    let mut buf = [0u8; 1500];
+    loop {
+        let n = tokio::select! {
+            _ = &mut stop_rx => break,
+            r = sock.recv(&mut buf) => match r {
+                Ok(n) => n,
+                Err(_) => continue,
+            },
+        };
+        let Some(token) = reply_token(&buf[..n]) else {
+            continue;
+        };
+        let Some((seq, sent_at)) = pending.lock().unwrap().remove(&token) else {
+            continue;
+        };
+        let rtt = sent_at.elapsed();
+        if rtt > REPLY_TIMEOUT {
+            continue;
+        }
+        let ms = rtt.as_secs_f64() * 1000.0;
+        replies.push((seq, ms));
+
+        // Never wait on a slow consumer here: queued datagrams would age
+        // and their RTTs read high. A dropped progress update is harmless.
+        event_tx
+            .try_send(TestEvent::UdpLossProgress {
+                sent: sent.load(Ordering::Relaxed),
+                received: replies.len() as u64,
+                total,
+                rtt_ms: Some(ms),
+            })
+            .ok();
+    }
+    replies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::stun::tests::{config, stun_responder, stun_target};
+
+    #[test]
+    fn test_stun_binding_request_padding() {
+        let txid = [7u8; 12];
+        let bare = build_stun_binding_request(txid, STUN_HEADER_LEN);
+        assert_eq!(bare.len(), 20);
+        assert_eq!(&bare[2..4], &[0, 0]);
+
+        // 160-byte payload, like a 20 ms G.711 voice frame
+        let padded = build_stun_binding_request(txid, 160);
+        assert_eq!(padded.len(), 160);
+        assert_eq!(u16::from_be_bytes([padded[2], padded[3]]), 140);
+        assert_eq!(
+            u16::from_be_bytes([padded[20], padded[21]]),
+            STUN_ATTR_SOFTWARE
+        );
+        assert_eq!(u16::from_be_bytes([padded[22], padded[23]]), 136);
+
+        // Rounded up to attribute alignment
+        let aligned = build_stun_binding_request(txid, 101);
+        assert_eq!(aligned.len(), 104);
+        assert_eq!(u16::from_be_bytes([aligned[22], aligned[23]]), 77);
+
+        // SOFTWARE values stop at 763 bytes
+        let largest = build_stun_binding_request(txid, 1400);
+        assert_eq!(largest.len(), 788);
+        assert_eq!(u16::from_be_bytes([largest[22], largest[23]]), 763);
+    }
+
+    #[test]
+    fn test_stun_binding_response_txid() {
+        let mut resp = build_stun_binding_request([9u8; 12], STUN_HEADER_LEN);
+        assert_eq!(stun_binding_response_txid(&resp), None);
+        resp[0] = 0x01;
+        resp[1] = 0x01;
+        assert_eq!(stun_binding_response_txid(&resp), Some([9u8; 12]));
+        assert_eq!(stun_binding_response_txid(&resp[..19]), None);
+    }
+
+    #[test]
+    fn test_parse_host_port() {
+        assert_eq!(
+            parse_host_port("stun:stun.example.com").unwrap(),
+            ("stun.example.com".to_string(), 3478)
+        );
+        assert_eq!(
+            parse_host_port("turn:192.0.2.1:50000?transport=udp").unwrap(),
+            ("192.0.2.1".to_string(), 50000)
+        );
+        assert_eq!(
+            parse_host_port("stun:[2001:db8::1]:19302").unwrap(),
+            ("2001:db8::1".to_string(), 19302)
+        );
+        assert!(parse_host_port("stun::3478").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_loss_probe_against_local_responder() {
+        let servers = [stun_responder(None).await, stun_responder(None).await];
+        let cfg = config(&[
+            "--udp-pps",
+            "1000",
+            "--udp-packet-size",
+            "160",
+            "--stun-server",
+            &servers[0].to_string(),
+            "--stun-server",
+            &format!("stun:{}", servers[1]),
+        ]);
+        // Receiver dropped: progress events are not needed here
+        let (event_tx, _) = mpsc::channel(1);
+
+        for (server, url) in servers.iter().zip(&cfg.stun_servers) {
+            let turn = stun_target(url.clone());
+            let summary = run_udp_like_loss_probe(&turn, &cfg, 20, &event_tx, None)
+                .await
+                .unwrap();
+            assert_eq!(summary.target, Some(format!("stun:{}", server)));
+            assert_eq!(summary.latency.sent, 20);
+            assert_eq!(summary.latency.received, 20);
+            assert_eq!(summary.packet_size, Some(160));
+            assert_eq!(summary.quality_label, "Excellent");
+        }
+    }
+}
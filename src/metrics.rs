@@ -28,6 +28,35 @@ pub fn compute_jitter(samples: &[f64]) -> Option<f64> {
     Some(variance.sqrt())
 }
 
+/// Median of the samples, averaging the middle pair for an even count.
+/// Unlike `compute_metrics`, a single sample is its own median.
+pub fn median(samples: &[f64]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let n = sorted.len();
+    Some(if n % 2 == 1 {
+        sorted[n / 2]
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    })
+}
+
+/// Mean of the samples up to the 95th percentile, the trimmed mean the IETF
+/// responsiveness draft uses so a few stragglers do not dominate.
+pub fn trimmed_mean_95(samples: &[f64]) -> Option<f64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let keep = ((sorted.len() as f64) * 0.95).ceil() as usize;
+    let kept = &sorted[..keep.max(1)];
+    Some(kept.iter().sum::<f64>() / kept.len() as f64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,6 +104,25 @@ mod tests {
         assert!((jitter - 1.5811).abs() < 0.001);
     }
 
+    #[test]
+    fn test_trimmed_mean_95() {
+        // 20 samples: the single outlier is the top 5% and gets dropped
+        let mut samples = vec![10.0; 19];
+        samples.push(1000.0);
+        assert!((trimmed_mean_95(&samples).unwrap() - 10.0).abs() < 0.001);
+        // Too few samples to trim anything
+        assert!((trimmed_mean_95(&[10.0, 20.0]).unwrap() - 15.0).abs() < 0.001);
+        assert!(trimmed_mean_95(&[]).is_none());
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[7.0]), Some(7.0));
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), Some(2.5));
+        assert!(median(&[]).is_none());
+    }
+
     #[test]
     fn test_compute_jitter_insufficient_samples() {
         assert!(compute_jitter(&[1.0]).is_none());
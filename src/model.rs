@@ -29,6 +29,26 @@ pub struct RunConfig {
     pub download_bytes_per_req: u64,
     pub upload_bytes_per_req: u64,
     pub concurrency: usize,
+    #[serde(default)]
+    pub adaptive: bool,
+    /// Also measure download and upload over a single connection
+    #[serde(default)]
+    pub single_stream: bool,
+    /// Also saturate download and upload at the same time
+    #[serde(default)]
+    pub bidirectional: bool,
+    /// TCP congestion-control algorithm for HTTP connections (Linux only)
+    #[serde(default)]
+    pub tcp_congestion: Option<String>,
+    /// SO_RCVBUF / SO_SNDBUF for HTTP connections, in bytes (Linux only)
+    #[serde(default)]
+    pub tcp_rcvbuf: Option<usize>,
+    #[serde(default)]
+    pub tcp_sndbuf: Option<usize>,
+    #[serde(default)]
+    pub http_version: HttpVersion,
+    #[serde(default)]
+    pub bufferbloat_thresholds: BufferbloatThresholds,
     #[serde(with = "humantime_serde")]
     pub idle_latency_duration: Duration,
     #[serde(with = "humantime_serde")]
@@ -46,12 +66,91 @@ pub struct RunConfig {
     // Diagnostic options
     pub measure_dns: bool,
     pub measure_tls: bool,
+    /// NAT behavior discovery over STUN
+    #[serde(default)]
+    pub measure_nat: bool,
+    #[serde(default)]
+    pub dns_public_resolvers: bool,
+    #[serde(default)]
+    pub doh_url: Option<String>,
+    #[serde(default)]
+    pub dot_server: Option<String>,
+    #[serde(default)]
+    pub measure_pmtu: bool,
+    #[serde(default)]
+    pub measure_connection_timing: bool,
+    #[serde(default)]
+    pub connection_timing_samples: u32,
+    /// Also probe idle latency over new and over pinned warm connections
+    #[serde(default)]
+    pub connection_probes: bool,
+    #[serde(default)]
+    pub connection_probe_samples: u32,
+    /// Time new connections during download and upload for the full RPM figure
+    #[serde(default)]
+    pub measure_responsiveness: bool,
+    /// Fetch TURN credentials and measure UDP through a TURN relay
+    #[serde(default)]
+    pub measure_turn_relay: bool,
     pub compare_ip_versions: bool,
     pub traceroute: bool,
     pub traceroute_max_hops: u8,
+    #[serde(default)]
+    pub traceroute_mode: TracerouteMode,
+    #[serde(default)]
+    pub path_monitor: bool,
+    #[serde(default)]
+    pub asn_table: Option<std::path::PathBuf>,
     pub ipv4_only: bool,
     pub ipv6_only: bool,
     pub udp_packets: u64,
+    /// UDP probe send rate, packets per second
+    #[serde(default)]
+    pub udp_pps: u32,
+    /// UDP probe request size in bytes (STUN message, without IP/UDP headers)
+    #[serde(default)]
+    pub udp_packet_size: usize,
+    /// Also run the UDP probe alongside the download and upload phases
+    #[serde(default)]
+    pub udp_under_load: bool,
+    /// STUN targets given on the command line, probed one after another
+    #[serde(default)]
+    pub stun_servers: Vec<String>,
+}
+
+/// HTTP version used for the speed-test requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum HttpVersion {
+    /// HTTP/1.1 only
+    #[value(name = "1.1")]
+    #[serde(rename = "1.1")]
+    Http1,
+    /// HTTP/2 only
+    #[value(name = "2")]
+    #[serde(rename = "2")]
+    Http2,
+    /// HTTP/3 over QUIC (UDP/443); needs the `http3` build feature
+    #[cfg(feature = "http3")]
+    #[value(name = "3")]
+    #[serde(rename = "3")]
+    Http3,
+    /// HTTP/2 or HTTP/1.1, whichever TLS ALPN settles on. Never HTTP/3:
+    /// Alt-Svc advertisements are not followed.
+    #[default]
+    #[value(name = "auto")]
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+impl HttpVersion {
+    /// Pinned to HTTP/3; always false without the `http3` feature
+    pub fn is_http3(self) -> bool {
+        match self {
+            #[cfg(feature = "http3")]
+            HttpVersion::Http3 => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -59,6 +158,10 @@ pub enum Phase {
     IdleLatency,
     Download,
     Upload,
+    /// One-connection download and upload comparison
+    SingleStream,
+    /// Download and upload running concurrently
+    Bidirectional,
     PacketLoss,
     Summary,
 }
@@ -90,6 +193,11 @@ pub enum TestEvent {
         bytes_total: u64,
         bps_instant: f64,
     },
+    /// Both directions of the bidirectional phase, in bytes per second
+    BidirectionalTick {
+        download_bps: f64,
+        upload_bps: f64,
+    },
     UdpLossProgress {
         sent: u64,
         received: u64,
@@ -109,6 +217,12 @@ pub enum TestEvent {
     DiagnosticTls {
         summary: TlsSummary,
     },
+    DiagnosticPmtu {
+        summary: PmtuSummary,
+    },
+    DiagnosticConnectionTiming {
+        summary: ConnectionTimingSummary,
+    },
     DiagnosticIpComparison {
         comparison: IpVersionComparison,
     },
@@ -119,6 +233,9 @@ pub enum TestEvent {
     TracerouteComplete {
         summary: TracerouteSummary,
     },
+    PathMonitorUpdate {
+        summary: PathMonitorSummary,
+    },
     ExternalIps {
         ipv4: Option<String>,
         ipv6: Option<String>,
@@ -138,6 +255,9 @@ pub struct LatencySummary {
     pub p75_ms: Option<f64>,
     pub max_ms: Option<f64>,
     pub jitter_ms: Option<f64>,
+    /// Mean without the slowest 5% of samples
+    #[serde(default)]
+    pub trimmed_mean_ms: Option<f64>,
 }
 
 impl Default for LatencySummary {
@@ -153,6 +273,7 @@ impl Default for LatencySummary {
             p75_ms: None,
             max_ms: None,
             jitter_ms: None,
+            trimmed_mean_ms: None,
         }
     }
 }
@@ -176,6 +297,72 @@ pub struct ThroughputSummary {
     pub median_mbps: Option<f64>,
     pub p25_mbps: Option<f64>,
     pub p75_mbps: Option<f64>,
+    /// Streams running at the end of the phase
+    #[serde(default)]
+    pub streams: usize,
+    /// Request size at the end of the phase
+    #[serde(default)]
+    pub bytes_per_req: u64,
+    /// Upload only: sent vs confirmed vs kernel-acknowledged byte counts
+    #[serde(default)]
+    pub accounting: Option<UploadAccounting>,
+    /// Protocol the phase's requests were answered with, e.g. "HTTP/2.0"
+    #[serde(default)]
+    pub http_protocol: Option<String>,
+}
+
+impl ThroughputSummary {
+    /// This rate as a percentage of `other`'s
+    pub fn percent_of(&self, other: &ThroughputSummary) -> Option<f64> {
+        (other.mbps > 0.0).then(|| self.mbps * 100.0 / other.mbps)
+    }
+}
+
+/// Download and upload saturated at the same time (`--bidirectional`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BidirectionalSummary {
+    pub download: ThroughputSummary,
+    pub upload: ThroughputSummary,
+    /// Latency probes while both directions are loaded
+    pub loaded_latency: LatencySummary,
+}
+
+/// Upload byte counts from three vantage points over the same phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadAccounting {
+    /// Bytes handed to the HTTP client (what the live graph shows)
+    pub sent_bytes: u64,
+    /// Bytes in requests the server completed with a success status
+    pub confirmed_bytes: u64,
+    /// Growth of `tcpi_bytes_acked` on connections to the server (Linux only)
+    pub kernel_acked_bytes: Option<u64>,
+    pub duration_ms: u64,
+}
+
+impl UploadAccounting {
+    fn mbps(&self, bytes: u64) -> f64 {
+        let secs = (self.duration_ms as f64 / 1000.0).max(1e-9);
+        bytes as f64 * 8.0 / secs / 1_000_000.0
+    }
+
+    pub fn sent_mbps(&self) -> f64 {
+        self.mbps(self.sent_bytes)
+    }
+
+    pub fn confirmed_mbps(&self) -> f64 {
+        self.mbps(self.confirmed_bytes)
+    }
+
+    pub fn kernel_acked_mbps(&self) -> Option<f64> {
+        self.kernel_acked_bytes.map(|b| self.mbps(b))
+    }
+
+    /// Kernel-acked relative to confirmed bytes, in percent
+    pub fn difference_pct(&self) -> Option<f64> {
+        let acked = self.kernel_acked_bytes? as f64;
+        (self.confirmed_bytes > 0)
+            .then(|| (acked - self.confirmed_bytes as f64) * 100.0 / self.confirmed_bytes as f64)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -201,6 +388,28 @@ pub struct ExperimentalUdpSummary {
     /// Quality label based on packet loss: Excellent/Good/Acceptable/Poor/Bad
     #[serde(default)]
     pub quality_label: String,
+    /// Send rate and request size the probe ran with
+    #[serde(default)]
+    pub pps: Option<u32>,
+    #[serde(default)]
+    pub packet_size: Option<usize>,
+}
+
+/// UDP probe against one `--stun-server`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StunServerResult {
+    /// The server as given, e.g. "stun:stun.example.com:3478"
+    pub target: String,
+    /// None when the probe failed
+    pub summary: Option<ExperimentalUdpSummary>,
+    pub error: Option<String>,
+}
+
+/// UDP probe run while a throughput phase saturates the link
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadedUdpSummary {
+    pub download: Option<ExperimentalUdpSummary>,
+    pub upload: Option<ExperimentalUdpSummary>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -219,10 +428,34 @@ pub struct RunResult {
     pub idle_latency: LatencySummary,
     pub download: ThroughputSummary,
     pub upload: ThroughputSummary,
+    /// Same phases over a single connection (`--single-stream`)
+    #[serde(default)]
+    pub single_stream_download: Option<ThroughputSummary>,
+    #[serde(default)]
+    pub single_stream_upload: Option<ThroughputSummary>,
+    #[serde(default)]
+    pub bidirectional: Option<BidirectionalSummary>,
     pub loaded_latency_download: LatencySummary,
     pub loaded_latency_upload: LatencySummary,
+    /// Idle probes that each open a new connection (DNS, TCP, TLS and HTTP)
+    #[serde(default)]
+    pub fresh_connection_latency: Option<LatencySummary>,
+    /// Idle probes pinned to one already-open connection
+    #[serde(default)]
+    pub warm_connection_latency: Option<LatencySummary>,
+    /// TURN servers from the speed-test service (credential not kept)
     pub turn: Option<TurnInfo>,
+    /// UDP probe on the idle link, after the throughput phases
     pub experimental_udp: Option<ExperimentalUdpSummary>,
+    /// One probe per `--stun-server`, in the order given
+    #[serde(default)]
+    pub stun_results: Vec<StunServerResult>,
+    /// UDP probe during download and upload (`--udp-under-load`)
+    #[serde(default)]
+    pub loaded_udp: Option<LoadedUdpSummary>,
+    /// UDP probe relayed through a TURN allocation
+    #[serde(default)]
+    pub turn_relay: Option<ExperimentalUdpSummary>,
     /// Error message when TURN fetch or UDP probe failed (for UI display)
     #[serde(skip, default)]
     pub udp_error: Option<String>,
@@ -257,9 +490,29 @@ pub struct RunResult {
     #[serde(default)]
     pub tls: Option<TlsSummary>,
     #[serde(default)]
+    pub pmtu: Option<PmtuSummary>,
+    #[serde(default)]
+    pub connection_timing: Option<ConnectionTimingSummary>,
+    #[serde(default)]
+    pub tcp_telemetry: Option<TcpTelemetry>,
+    #[serde(default)]
+    pub tcp_socket: Option<TcpSocketSettings>,
+    #[serde(default)]
+    pub nat: Option<NatSummary>,
+    /// Protocol the download requests were answered with, e.g. "HTTP/2.0",
+    /// or the initial probe request's if none completed
+    #[serde(default)]
+    pub http_protocol: Option<String>,
+    #[serde(default)]
+    pub responsiveness: Option<Responsiveness>,
+    #[serde(default)]
+    pub bufferbloat: Option<BufferbloatSummary>,
+    #[serde(default)]
     pub ip_comparison: Option<IpVersionComparison>,
     #[serde(default)]
     pub traceroute: Option<TracerouteSummary>,
+    #[serde(default)]
+    pub path_monitor: Option<PathMonitorSummary>,
 }
 
 // ============================================================================
@@ -277,6 +530,74 @@ pub struct DnsSummary {
     /// System DNS servers used for resolution
     #[serde(default)]
     pub dns_servers: Vec<String>,
+    /// Direct UDP/53 timings, one entry per resolver
+    #[serde(default)]
+    pub per_server: Vec<DnsServerTiming>,
+    /// DNS-over-HTTPS / DNS-over-TLS timings for the same hostname
+    #[serde(default)]
+    pub encrypted: Vec<EncryptedDnsTiming>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptedDnsProtocol {
+    Doh,
+    Dot,
+}
+
+impl EncryptedDnsProtocol {
+    pub fn label(self) -> &'static str {
+        match self {
+            EncryptedDnsProtocol::Doh => "DoH",
+            EncryptedDnsProtocol::Dot => "DoT",
+        }
+    }
+}
+
+/// Lookup timing against one encrypted DNS endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedDnsTiming {
+    pub protocol: EncryptedDnsProtocol,
+    /// DoH URL or DoT host:port
+    pub target: String,
+    /// First query, including TCP and TLS setup
+    pub first_ms: Option<f64>,
+    /// Second query over the already established connection
+    pub reused_ms: Option<f64>,
+    pub answers: usize,
+    pub error: Option<String>,
+}
+
+/// Direct query timings against a single resolver
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsServerTiming {
+    pub server: String,
+    /// Well-known public resolver added for comparison (not system-configured)
+    pub public: bool,
+    pub a: DnsRecordTiming,
+    pub aaaa: DnsRecordTiming,
+}
+
+/// First and repeated lookup time for one record type. The host has
+/// usually been resolved already by then, so the first query may well be
+/// answered from the resolver's cache too; it is not a cold lookup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DnsRecordTiming {
+    pub first_ms: Option<f64>,
+    pub repeat_ms: Option<f64>,
+    /// Records of this type in the answer
+    pub answers: usize,
+    pub error: Option<String>,
+}
+
+impl DnsServerTiming {
+    /// Slowest first lookup across record types, used to rank resolvers
+    pub fn worst_first_ms(&self) -> Option<f64> {
+        match (self.a.first_ms, self.aaaa.first_ms) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        }
+    }
 }
 
 /// Summary of TLS handshake time measurement
@@ -285,6 +606,361 @@ pub struct TlsSummary {
     pub handshake_time_ms: f64,
     pub protocol_version: Option<String>,
     pub cipher_suite: Option<String>,
+    /// Negotiated application protocol (e.g. "h2")
+    #[serde(default)]
+    pub alpn: Option<String>,
+    #[serde(default)]
+    pub key_exchange_group: Option<String>,
+    /// Chain as presented by the server, leaf first
+    #[serde(default)]
+    pub certificates: Vec<TlsCertificate>,
+    /// The chain only validates against the `--certificate` root, not the
+    /// public web PKI (typical of TLS-inspection middleboxes)
+    #[serde(default)]
+    pub custom_root: bool,
+    /// Second handshake using the session cache from the first
+    #[serde(default)]
+    pub resumed_handshake_time_ms: Option<f64>,
+    /// The server actually accepted the resumption attempt
+    #[serde(default)]
+    pub resumed: bool,
+    /// Handshake start to first response byte, request sent as 1-RTT data
+    #[serde(default)]
+    pub resumed_first_byte_ms: Option<f64>,
+    /// Handshake start to first response byte, request sent as 0-RTT early data
+    #[serde(default)]
+    pub zero_rtt_first_byte_ms: Option<f64>,
+    /// None when the server offered no early data on the resumed session
+    #[serde(default)]
+    pub early_data_accepted: Option<bool>,
+}
+
+/// One certificate from the presented chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsCertificate {
+    pub subject: String,
+    pub issuer: String,
+    /// SHA-256 of the DER encoding, colon-separated hex
+    pub sha256: String,
+}
+
+/// Technique that produced a path MTU figure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PmtuMethod {
+    /// Binary search with Don't Fragment ICMP echo requests
+    Icmp,
+    /// Derived from the MSS negotiated on a TCP connection
+    TcpMss,
+}
+
+impl PmtuMethod {
+    pub fn label(self) -> &'static str {
+        match self {
+            PmtuMethod::Icmp => "ICMP DF probes",
+            PmtuMethod::TcpMss => "TCP MSS",
+        }
+    }
+}
+
+/// Summary of path MTU discovery toward the speed-test server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PmtuSummary {
+    pub method: PmtuMethod,
+    /// Largest IP packet that reaches the server unfragmented
+    pub pmtu: u32,
+    /// TCP payload per segment that fits the path MTU
+    pub mss: u32,
+    /// MTU of the local route (link MTU or cached PMTU)
+    pub local_mtu: Option<u32>,
+    /// MSS negotiated on a TCP connection to the server
+    pub tcp_mss: Option<u32>,
+    /// Oversized probes were dropped silently and TCP would still send
+    /// segments that large
+    pub black_hole_suspected: bool,
+}
+
+/// Time spent in each stage of one fresh HTTPS request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionStages {
+    pub dns_ms: f64,
+    pub tcp_ms: f64,
+    pub tls_ms: f64,
+    /// Request sent until the first response byte
+    pub ttfb_ms: f64,
+    /// First response byte until the body is complete
+    pub transfer_ms: f64,
+}
+
+impl ConnectionStages {
+    pub fn total_ms(&self) -> f64 {
+        self.dns_ms + self.tcp_ms + self.tls_ms + self.ttfb_ms + self.transfer_ms
+    }
+
+    /// Stage labels and durations in waterfall order
+    pub fn stages(&self) -> [(&'static str, f64); 5] {
+        [
+            ("DNS", self.dns_ms),
+            ("TCP", self.tcp_ms),
+            ("TLS", self.tls_ms),
+            ("TTFB", self.ttfb_ms),
+            ("Transfer", self.transfer_ms),
+        ]
+    }
+}
+
+/// Connection-establishment breakdown over several fresh connections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionTimingSummary {
+    pub url: String,
+    /// Response body size per sample
+    pub bytes: u64,
+    /// Successful samples only
+    pub samples: Vec<ConnectionStages>,
+    /// Samples that failed or timed out and were left out
+    #[serde(default)]
+    pub failed: u32,
+    /// Per-stage median across samples
+    pub median: ConnectionStages,
+}
+
+/// Upper bounds of the latency increase under load, in ms, for grades A+
+/// through D; anything above the last one is an F
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BufferbloatThresholds(pub [f64; 5]);
+
+impl Default for BufferbloatThresholds {
+    /// Waveform's bufferbloat test bands
+    fn default() -> Self {
+        Self([5.0, 30.0, 60.0, 200.0, 400.0])
+    }
+}
+
+impl BufferbloatThresholds {
+    pub fn grade(&self, increase_ms: f64) -> BufferbloatGrade {
+        const GRADES: [BufferbloatGrade; 5] = [
+            BufferbloatGrade::APlus,
+            BufferbloatGrade::A,
+            BufferbloatGrade::B,
+            BufferbloatGrade::C,
+            BufferbloatGrade::D,
+        ];
+        GRADES
+            .into_iter()
+            .zip(self.0)
+            .find(|(_, limit)| increase_ms < *limit)
+            .map(|(grade, _)| grade)
+            .unwrap_or(BufferbloatGrade::F)
+    }
+}
+
+/// Letter grade for latency increase under load, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BufferbloatGrade {
+    #[serde(rename = "A+")]
+    APlus,
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl BufferbloatGrade {
+    pub fn label(self) -> &'static str {
+        match self {
+            BufferbloatGrade::APlus => "A+",
+            BufferbloatGrade::A => "A",
+            BufferbloatGrade::B => "B",
+            BufferbloatGrade::C => "C",
+            BufferbloatGrade::D => "D",
+            BufferbloatGrade::F => "F",
+        }
+    }
+}
+
+/// How much median latency rises under load compared with idle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferbloatSummary {
+    /// Worse of the two directions
+    pub grade: BufferbloatGrade,
+    pub download_increase_ms: Option<f64>,
+    pub download_grade: Option<BufferbloatGrade>,
+    pub upload_increase_ms: Option<f64>,
+    pub upload_grade: Option<BufferbloatGrade>,
+    pub thresholds: BufferbloatThresholds,
+}
+
+/// Round-trips per minute under load, per the IETF responsiveness draft
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpmSummary {
+    /// Draft formula combining fresh-connection and in-flight probes; None
+    /// without fresh-connection samples
+    pub rpm: Option<f64>,
+    /// From fresh-connection probes alone (TCP, TLS and HTTP round trips)
+    pub fresh_rpm: Option<f64>,
+    /// From the loaded-latency probes alone
+    pub in_flight_rpm: f64,
+    pub fresh_probes: usize,
+    pub in_flight_probes: u64,
+    /// Trimmed means of the fresh-connection stages
+    pub tcp_ms: Option<f64>,
+    pub tls_ms: Option<f64>,
+    pub http_ms: Option<f64>,
+}
+
+impl RpmSummary {
+    /// The combined figure where available, otherwise the in-flight one
+    pub fn best(&self) -> f64 {
+        self.rpm.unwrap_or(self.in_flight_rpm)
+    }
+}
+
+/// Responsiveness while each throughput phase saturates the link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Responsiveness {
+    pub download: Option<RpmSummary>,
+    pub upload: Option<RpmSummary>,
+}
+
+/// Socket options applied to the HTTP connections of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpSocketSettings {
+    pub congestion_control: Option<String>,
+    pub rcvbuf_requested: Option<usize>,
+    /// Size the kernel reports back (Linux doubles the request and caps it
+    /// at `net.core.rmem_max`)
+    pub rcvbuf: Option<usize>,
+    pub sndbuf_requested: Option<usize>,
+    pub sndbuf: Option<usize>,
+    /// Connections the options were applied to
+    pub connections: u64,
+    /// Connections the kernel rejected an option on; they ran with defaults
+    #[serde(default)]
+    pub failed: u64,
+}
+
+impl TcpSocketSettings {
+    /// One-line summary, e.g. "bbr, rcvbuf 1000000 -> 2000000 B, 8 conns"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(ref cc) = self.congestion_control {
+            parts.push(cc.clone());
+        }
+        for (name, requested, granted) in [
+            ("rcvbuf", self.rcvbuf_requested, self.rcvbuf),
+            ("sndbuf", self.sndbuf_requested, self.sndbuf),
+        ] {
+            if let Some(requested) = requested {
+                parts.push(match granted {
+                    Some(granted) => format!("{} {} -> {} B", name, requested, granted),
+                    None => format!("{} {} B", name, requested),
+                });
+            }
+        }
+        parts.push(format!("{} conns", self.connections));
+        if self.failed > 0 {
+            parts.push(format!("{} failed", self.failed));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Kernel TCP statistics of the throughput connections (Linux only)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpTelemetry {
+    pub download: Option<TcpPhaseTelemetry>,
+    pub upload: Option<TcpPhaseTelemetry>,
+}
+
+/// `TCP_INFO` samples aggregated over the connections active in one phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpPhaseTelemetry {
+    /// Congestion-control algorithm of the connections (e.g. "cubic", "bbr")
+    pub congestion_control: Option<String>,
+    /// Distinct connections that carried data
+    pub connections: usize,
+    pub samples: Vec<TcpTelemetrySample>,
+    /// Segments retransmitted during the phase
+    pub retransmits: u64,
+    /// Retransmitted share of data segments sent (upload only)
+    pub retransmit_pct: Option<f64>,
+    /// Share of busy time limited by the server's receive window (upload only)
+    pub rwnd_limited_pct: Option<f64>,
+    /// Share of busy time limited by the local send buffer (upload only)
+    pub sndbuf_limited_pct: Option<f64>,
+    /// What most likely capped throughput
+    pub limit: Option<TcpLimit>,
+}
+
+impl TcpPhaseTelemetry {
+    /// Median smoothed RTT over the samples
+    pub fn median_srtt_ms(&self) -> Option<f64> {
+        self.median(|s| s.srtt_ms)
+    }
+
+    /// Median mean congestion window over the samples, in segments
+    pub fn median_cwnd(&self) -> Option<f64> {
+        self.median(|s| s.cwnd)
+    }
+
+    fn median(&self, f: fn(&TcpTelemetrySample) -> f64) -> Option<f64> {
+        let values: Vec<f64> = self.samples.iter().map(f).collect();
+        match values.as_slice() {
+            [only] => Some(*only),
+            _ => crate::metrics::compute_metrics(&values).map(|(_, median, _, _)| median),
+        }
+    }
+}
+
+/// One sampling instant across the active connections. RTT comes from the
+/// receiver-side estimate during download, where the client sends no data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpTelemetrySample {
+    /// Milliseconds since the phase started
+    pub t_ms: u64,
+    pub connections: usize,
+    /// Mean smoothed RTT and RTT variation
+    pub srtt_ms: f64,
+    pub rttvar_ms: f64,
+    /// Mean congestion window in segments
+    pub cwnd: f64,
+    /// Mean receive space the client advertises, in bytes
+    pub rcv_space: f64,
+    /// Retransmitted segments since the phase started
+    pub retransmits: u64,
+    /// Summed kernel delivery rate (kernel 4.9+)
+    pub delivery_mbps: Option<f64>,
+    /// Summed pacing rate
+    pub pacing_mbps: f64,
+}
+
+/// Most likely throughput bottleneck, judged from the client's sockets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TcpLimit {
+    /// Retransmissions point at packet loss
+    Loss,
+    /// The receiver's advertised window was full
+    ReceiveWindow,
+    /// The client's socket send buffer ran dry
+    SendBuffer,
+    /// The client's congestion window (path capacity or queueing)
+    CongestionWindow,
+    /// The client kept up; the server or the path held the rate down
+    Sender,
+}
+
+impl TcpLimit {
+    pub fn label(self) -> &'static str {
+        match self {
+            TcpLimit::Loss => "packet loss",
+            TcpLimit::ReceiveWindow => "receive window",
+            TcpLimit::SendBuffer => "send buffer",
+            TcpLimit::CongestionWindow => "congestion window",
+            TcpLimit::Sender => "sender",
+        }
+    }
 }
 
 /// Comparison of IPv4 vs IPv6 performance
@@ -305,12 +981,130 @@ pub struct IpVersionResult {
     pub error: Option<String>,
 }
 
+/// How a NAT's mapping or filtering depends on the remote endpoint (RFC 4787)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NatBehavior {
+    EndpointIndependent,
+    AddressDependent,
+    AddressAndPortDependent,
+    /// Depends on the remote endpoint, but the server offered no alternate
+    /// port to tell address- from address-and-port-dependent
+    EndpointDependent,
+}
+
+impl NatBehavior {
+    pub fn label(self) -> &'static str {
+        match self {
+            NatBehavior::EndpointIndependent => "endpoint-independent",
+            NatBehavior::AddressDependent => "address-dependent",
+            NatBehavior::AddressAndPortDependent => "address- and port-dependent",
+            NatBehavior::EndpointDependent => "endpoint-dependent",
+        }
+    }
+}
+
+/// NAT behavior discovery results (RFC 5780)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NatSummary {
+    /// STUN server the tests ran against
+    pub server: String,
+    /// Address of the probing socket on this host
+    pub local_address: String,
+    /// Public address and port the server saw
+    pub mapped_address: String,
+    pub behind_nat: bool,
+    /// None when no second server address was available to compare against
+    pub mapping: Option<NatBehavior>,
+    /// None unless the server supports RFC 5780 (OTHER-ADDRESS, CHANGE-REQUEST)
+    pub filtering: Option<NatBehavior>,
+    /// A request sent to our own mapped address came back to us
+    pub hairpinning: Option<bool>,
+    /// The public port equals the local port
+    pub port_preserved: bool,
+    /// Classic name: Full cone, Restricted cone, Port-restricted cone, Symmetric
+    pub nat_type: String,
+}
+
+impl NatSummary {
+    /// One-line summary, e.g. "Symmetric, mapped 203.0.113.5:40000,
+    /// mapping endpoint-dependent, hairpinning no"
+    pub fn describe(&self) -> String {
+        let mut parts = vec![
+            self.nat_type.clone(),
+            format!("mapped {}", self.mapped_address),
+        ];
+        if let Some(mapping) = self.mapping {
+            parts.push(format!("mapping {}", mapping.label()));
+        }
+        if let Some(filtering) = self.filtering {
+            parts.push(format!("filtering {}", filtering.label()));
+        }
+        if let Some(hairpinning) = self.hairpinning {
+            parts.push(format!(
+                "hairpinning {}",
+                if hairpinning { "yes" } else { "no" }
+            ));
+        }
+        if self.behind_nat && self.port_preserved {
+            parts.push("port preserved".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// Probe type used by traceroute
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TracerouteMode {
+    /// ICMP Echo Request
+    #[default]
+    Icmp,
+    /// UDP datagrams to high ports (classic traceroute)
+    Udp,
+    /// TCP SYN to the speed-test port, following the path HTTP traffic takes
+    Tcp,
+}
+
+impl TracerouteMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            TracerouteMode::Icmp => "ICMP",
+            TracerouteMode::Udp => "UDP",
+            TracerouteMode::Tcp => "TCP",
+        }
+    }
+}
+
 /// Summary of traceroute results
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TracerouteSummary {
     pub destination: String,
     pub hops: Vec<TracerouteHop>,
     pub completed: bool,
+    /// Probe type that produced these hops
+    #[serde(default)]
+    pub mode: TracerouteMode,
+}
+
+impl TracerouteSummary {
+    /// AS-level path, one entry per run of consecutive hops in the same AS
+    /// (e.g. `AS7922 COMCAST`). Empty unless hops were annotated.
+    pub fn as_path(&self) -> Vec<String> {
+        let mut path: Vec<String> = Vec::new();
+        let mut last_asn = None;
+        for hop in &self.hops {
+            let Some(asn) = hop.asn else { continue };
+            if last_asn != Some(asn) {
+                path.push(match hop.as_org.as_deref() {
+                    Some(org) => format!("AS{} {}", asn, org),
+                    None => format!("AS{}", asn),
+                });
+                last_asn = Some(asn);
+            }
+        }
+        path
+    }
 }
 
 /// A single hop in a traceroute
@@ -319,6 +1113,36 @@ pub struct TracerouteHop {
     pub hop_number: u8,
     pub ip_address: Option<String>,
     pub hostname: Option<String>,
+    /// Origin AS from the offline ASN table, if one was given
+    #[serde(default)]
+    pub asn: Option<u32>,
+    #[serde(default)]
+    pub as_org: Option<String>,
     pub rtt_ms: Vec<f64>,
     pub timeout: bool,
 }
+
+/// Per-hop statistics from the continuous path monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathMonitorSummary {
+    pub destination: String,
+    /// Number of completed probing rounds (one probe per hop each)
+    pub rounds: u64,
+    pub hops: Vec<PathMonitorHop>,
+}
+
+/// Loss and RTT statistics for one hop of the path monitor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathMonitorHop {
+    pub hop_number: u8,
+    pub ip_address: Option<String>,
+    pub sent: u64,
+    pub received: u64,
+    #[serde(with = "loss_percent_serde")]
+    pub loss: f64,
+    pub best_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub worst_ms: Option<f64>,
+    pub stddev_ms: Option<f64>,
+    pub last_ms: Option<f64>,
+}
@@ -1,4 +1,4 @@
-use crate::model::LatencySummary;
+use crate::model::{BufferbloatSummary, BufferbloatThresholds, LatencySummary};
 
 #[derive(Debug, Default, Clone)]
 pub struct OnlineStats {
@@ -71,6 +71,7 @@ pub fn latency_summary_from_samples(
             p75_ms: Some(p75),
             max_ms,
             jitter_ms: jitter,
+            trimmed_mean_ms: crate::metrics::trimmed_mean_95(samples_ms),
         }
     } else {
         LatencySummary {
@@ -82,3 +83,71 @@ pub fn latency_summary_from_samples(
         }
     }
 }
+
+/// Grade how far median latency rises under load over idle, per direction,
+/// with the worse direction as the overall grade. None without an idle
+/// median or any loaded one.
+pub fn bufferbloat_summary(
+    idle: &LatencySummary,
+    download: &LatencySummary,
+    upload: &LatencySummary,
+    thresholds: BufferbloatThresholds,
+) -> Option<BufferbloatSummary> {
+    let idle_ms = idle.median_ms?;
+    let increase = |loaded: &LatencySummary| loaded.median_ms.map(|ms| (ms - idle_ms).max(0.0));
+    let download_increase_ms = increase(download);
+    let upload_increase_ms = increase(upload);
+    let download_grade = download_increase_ms.map(|ms| thresholds.grade(ms));
+    let upload_grade = upload_increase_ms.map(|ms| thresholds.grade(ms));
+
+    Some(BufferbloatSummary {
+        grade: download_grade.max(upload_grade)?,
+        download_increase_ms,
+        download_grade,
+        upload_increase_ms,
+        upload_grade,
+        thresholds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::BufferbloatGrade;
+
+    fn median(ms: f64) -> LatencySummary {
+        LatencySummary {
+            median_ms: Some(ms),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bufferbloat_summary() {
+        let thresholds = BufferbloatThresholds::default();
+        let summary =
+            bufferbloat_summary(&median(10.0), &median(12.0), &median(80.0), thresholds).unwrap();
+        assert_eq!(summary.download_grade, Some(BufferbloatGrade::APlus));
+        assert_eq!(summary.upload_grade, Some(BufferbloatGrade::C));
+        assert_eq!(summary.grade, BufferbloatGrade::C);
+
+        // Failed upload probes leave the download grade on its own
+        let summary = bufferbloat_summary(
+            &median(10.0),
+            &median(500.0),
+            &LatencySummary::failed(),
+            thresholds,
+        )
+        .unwrap();
+        assert_eq!(summary.grade, BufferbloatGrade::F);
+        assert!(summary.upload_grade.is_none());
+
+        assert!(bufferbloat_summary(
+            &LatencySummary::failed(),
+            &median(12.0),
+            &median(12.0),
+            thresholds
+        )
+        .is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::model::RunResult;
+use crate::model::{ConnectionStages, EncryptedDnsProtocol, RunResult, TcpPhaseTelemetry};
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
@@ -59,7 +59,7 @@ pub fn export_csv(path: &Path, result: &RunResult) -> Result<()> {
     }
     let mut out = String::new();
     // Header row with all fields including diagnostics
-    out.push_str("timestamp_utc,base_url,meas_id,comments,server,download_mbps,upload_mbps,idle_mean_ms,idle_median_ms,idle_p25_ms,idle_p75_ms,idle_loss,dl_loaded_mean_ms,dl_loaded_median_ms,dl_loaded_p25_ms,dl_loaded_p75_ms,dl_loaded_loss,ul_loaded_mean_ms,ul_loaded_median_ms,ul_loaded_p25_ms,ul_loaded_p75_ms,ul_loaded_loss,ip,colo,asn,as_org,interface_name,network_name,is_wireless,interface_mac,local_ipv4,local_ipv6,external_ipv4,external_ipv6,dns_resolution_ms,dns_ipv4_count,dns_ipv6_count,dns_servers,tls_handshake_ms,tls_protocol,tls_cipher,ipv4_download_mbps,ipv4_upload_mbps,ipv4_latency_ms,ipv6_download_mbps,ipv6_upload_mbps,ipv6_latency_ms,traceroute_hops\n");
+    out.push_str("timestamp_utc,base_url,meas_id,comments,server,download_mbps,upload_mbps,download_streams,download_bytes_per_req,upload_streams,upload_bytes_per_req,single_download_mbps,single_upload_mbps,bidir_download_mbps,bidir_upload_mbps,bidir_loaded_median_ms,upload_sent_mbps,upload_confirmed_mbps,upload_kernel_acked_mbps,idle_mean_ms,idle_median_ms,idle_p25_ms,idle_p75_ms,idle_loss,dl_loaded_mean_ms,dl_loaded_median_ms,dl_loaded_p25_ms,dl_loaded_p75_ms,dl_loaded_loss,ul_loaded_mean_ms,ul_loaded_median_ms,ul_loaded_p25_ms,ul_loaded_p75_ms,ul_loaded_loss,ip,colo,asn,as_org,interface_name,network_name,is_wireless,interface_mac,local_ipv4,local_ipv6,external_ipv4,external_ipv6,dns_resolution_ms,dns_ipv4_count,dns_ipv6_count,dns_servers,doh_ms,dot_ms,tls_handshake_ms,tls_protocol,tls_cipher,tls_alpn,tls_kx_group,tls_issuer,tls_custom_root,tls_resumed_ms,ipv4_download_mbps,ipv4_upload_mbps,ipv4_latency_ms,ipv6_download_mbps,ipv6_upload_mbps,ipv6_latency_ms,traceroute_hops,traceroute_mode,traceroute_hostnames,traceroute_as_path,pmtu,pmtu_mss,conn_dns_ms,conn_tcp_ms,conn_tls_ms,conn_ttfb_ms,conn_transfer_ms,tcp_congestion,download_tcp_srtt_ms,download_tcp_limit,upload_tcp_srtt_ms,upload_tcp_retrans_pct,upload_tcp_limit,tcp_rcvbuf,tcp_sndbuf,http_protocol,download_rpm,download_rpm_fresh,download_rpm_in_flight,upload_rpm,upload_rpm_fresh,upload_rpm_in_flight,bufferbloat_grade,dl_latency_increase_ms,ul_latency_increase_ms,fresh_conn_median_ms,warm_conn_median_ms,nat_type,nat_mapping,nat_filtering,nat_mapped_address\n");
 
     // Extract diagnostic values
     let dns_resolution_ms = result.dns.as_ref().map(|d| d.resolution_time_ms);
@@ -70,7 +70,47 @@ pub fn export_csv(path: &Path, result: &RunResult) -> Result<()> {
         .as_ref()
         .map(|d| d.dns_servers.join("; "))
         .unwrap_or_default();
+    let encrypted_dns_ms = |protocol: EncryptedDnsProtocol| {
+        result
+            .dns
+            .as_ref()
+            .and_then(|d| d.encrypted.iter().find(|e| e.protocol == protocol))
+            .and_then(|e| e.first_ms)
+    };
+    let upload_acc = result.upload.accounting.as_ref();
+    let bidir = result.bidirectional.as_ref();
+    let responsiveness = result.responsiveness.as_ref();
+    let download_rpm = responsiveness.and_then(|r| r.download.as_ref());
+    let upload_rpm = responsiveness.and_then(|r| r.upload.as_ref());
+    let bufferbloat = result.bufferbloat.as_ref();
+    let nat = result.nat.as_ref();
+    let rpm = |v: Option<f64>| v.map(|v| format!("{:.0}", v)).unwrap_or_default();
+    let tls = result.tls.as_ref();
     let tls_handshake_ms = result.tls.as_ref().map(|t| t.handshake_time_ms);
+    let conn_stage = |f: fn(&ConnectionStages) -> f64| {
+        result
+            .connection_timing
+            .as_ref()
+            .map(|c| format!("{:.3}", f(&c.median)))
+            .unwrap_or_default()
+    };
+    let tcp = result.tcp_telemetry.as_ref();
+    let tcp_socket = result.tcp_socket.as_ref();
+    let tcp_download = tcp.and_then(|t| t.download.as_ref());
+    let tcp_upload = tcp.and_then(|t| t.upload.as_ref());
+    let tcp_congestion = tcp_upload
+        .or(tcp_download)
+        .and_then(|t| t.congestion_control.clone());
+    let tcp_limit = |t: Option<&TcpPhaseTelemetry>| {
+        t.and_then(|t| t.limit)
+            .map(|l| l.label())
+            .unwrap_or_default()
+    };
+    let tcp_srtt = |t: Option<&TcpPhaseTelemetry>| {
+        t.and_then(|t| t.median_srtt_ms())
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default()
+    };
     let tls_protocol = result.tls.as_ref().and_then(|t| t.protocol_version.clone());
     let tls_cipher = result.tls.as_ref().and_then(|t| t.cipher_suite.clone());
 
@@ -116,9 +156,32 @@ pub fn export_csv(path: &Path, result: &RunResult) -> Result<()> {
 
     // Traceroute hop count
     let traceroute_hops = result.traceroute.as_ref().map(|t| t.hops.len());
+    let traceroute_mode = result.traceroute.as_ref().map(|t| t.mode.label());
+    // Per-hop names (PTR, else address) and the AS-level path
+    let traceroute_hostnames = result
+        .traceroute
+        .as_ref()
+        .map(|t| {
+            t.hops
+                .iter()
+                .map(|h| {
+                    h.hostname
+                        .as_deref()
+                        .or(h.ip_address.as_deref())
+                        .unwrap_or("*")
+                })
+                .collect::<Vec<_>>()
+                .join(" > ")
+        })
+        .unwrap_or_default();
+    let traceroute_as_path = result
+        .traceroute
+        .as_ref()
+        .map(|t| t.as_path().join(" > "))
+        .unwrap_or_default();
 
     out.push_str(&format!(
-        "{},{},{},{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.6},{:.3},{:.3},{:.3},{:.3},{:.6},{:.3},{:.3},{:.3},{:.3},{:.6},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+        "{},{},{},{},{},{:.3},{:.3},{},{},{},{},{},{},{},{},{},{:.3},{:.3},{:.3},{:.3},{:.6},{:.3},{:.3},{:.3},{:.3},{:.6},{:.3},{:.3},{:.3},{:.3},{:.6},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
         csv_escape(&result.timestamp_utc),
         csv_escape(&result.base_url),
         csv_escape(&result.meas_id),
@@ -126,6 +189,40 @@ pub fn export_csv(path: &Path, result: &RunResult) -> Result<()> {
         csv_escape(result.server.as_deref().unwrap_or("")),
         result.download.mbps,
         result.upload.mbps,
+        result.download.streams,
+        result.download.bytes_per_req,
+        result.upload.streams,
+        result.upload.bytes_per_req,
+        result
+            .single_stream_download
+            .as_ref()
+            .map(|t| format!("{:.3}", t.mbps))
+            .unwrap_or_default(),
+        result
+            .single_stream_upload
+            .as_ref()
+            .map(|t| format!("{:.3}", t.mbps))
+            .unwrap_or_default(),
+        bidir
+            .map(|b| format!("{:.3}", b.download.mbps))
+            .unwrap_or_default(),
+        bidir
+            .map(|b| format!("{:.3}", b.upload.mbps))
+            .unwrap_or_default(),
+        bidir
+            .and_then(|b| b.loaded_latency.median_ms)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
+        upload_acc
+            .map(|a| format!("{:.3}", a.sent_mbps()))
+            .unwrap_or_default(),
+        upload_acc
+            .map(|a| format!("{:.3}", a.confirmed_mbps()))
+            .unwrap_or_default(),
+        upload_acc
+            .and_then(|a| a.kernel_acked_mbps())
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
         result.idle_latency.mean_ms.unwrap_or(f64::NAN),
         result.idle_latency.median_ms.unwrap_or(f64::NAN),
         result.idle_latency.p25_ms.unwrap_or(f64::NAN),
@@ -158,9 +255,26 @@ pub fn export_csv(path: &Path, result: &RunResult) -> Result<()> {
         dns_ipv4_count.map(|v| v.to_string()).unwrap_or_default(),
         dns_ipv6_count.map(|v| v.to_string()).unwrap_or_default(),
         csv_escape(&dns_servers),
+        encrypted_dns_ms(EncryptedDnsProtocol::Doh)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
+        encrypted_dns_ms(EncryptedDnsProtocol::Dot)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
         tls_handshake_ms.map(|v| format!("{:.3}", v)).unwrap_or_default(),
         csv_escape(tls_protocol.as_deref().unwrap_or("")),
         csv_escape(tls_cipher.as_deref().unwrap_or("")),
+        csv_escape(tls.and_then(|t| t.alpn.as_deref()).unwrap_or("")),
+        csv_escape(tls.and_then(|t| t.key_exchange_group.as_deref()).unwrap_or("")),
+        csv_escape(
+            tls.and_then(|t| t.certificates.first())
+                .map(|c| c.issuer.as_str())
+                .unwrap_or("")
+        ),
+        tls.map(|t| t.custom_root.to_string()).unwrap_or_default(),
+        tls.and_then(|t| t.resumed_handshake_time_ms)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
         ipv4_download.map(|v| format!("{:.3}", v)).unwrap_or_default(),
         ipv4_upload.map(|v| format!("{:.3}", v)).unwrap_or_default(),
         ipv4_latency.map(|v| format!("{:.3}", v)).unwrap_or_default(),
@@ -168,6 +282,65 @@ pub fn export_csv(path: &Path, result: &RunResult) -> Result<()> {
         ipv6_upload.map(|v| format!("{:.3}", v)).unwrap_or_default(),
         ipv6_latency.map(|v| format!("{:.3}", v)).unwrap_or_default(),
         traceroute_hops.map(|v| v.to_string()).unwrap_or_default(),
+        traceroute_mode.unwrap_or(""),
+        csv_escape(&traceroute_hostnames),
+        csv_escape(&traceroute_as_path),
+        result.pmtu.as_ref().map(|p| p.pmtu.to_string()).unwrap_or_default(),
+        result.pmtu.as_ref().map(|p| p.mss.to_string()).unwrap_or_default(),
+        conn_stage(|s| s.dns_ms),
+        conn_stage(|s| s.tcp_ms),
+        conn_stage(|s| s.tls_ms),
+        conn_stage(|s| s.ttfb_ms),
+        conn_stage(|s| s.transfer_ms),
+        tcp_congestion.unwrap_or_default(),
+        tcp_srtt(tcp_download),
+        tcp_limit(tcp_download),
+        tcp_srtt(tcp_upload),
+        tcp_upload
+            .and_then(|t| t.retransmit_pct)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
+        tcp_limit(tcp_upload),
+        tcp_socket
+            .and_then(|t| t.rcvbuf)
+            .map(|v| v.to_string())
+            .unwrap_or_default(),
+        tcp_socket
+            .and_then(|t| t.sndbuf)
+            .map(|v| v.to_string())
+            .unwrap_or_default(),
+        result.http_protocol.as_deref().unwrap_or(""),
+        rpm(download_rpm.and_then(|r| r.rpm)),
+        rpm(download_rpm.and_then(|r| r.fresh_rpm)),
+        rpm(download_rpm.map(|r| r.in_flight_rpm)),
+        rpm(upload_rpm.and_then(|r| r.rpm)),
+        rpm(upload_rpm.and_then(|r| r.fresh_rpm)),
+        rpm(upload_rpm.map(|r| r.in_flight_rpm)),
+        bufferbloat.map(|b| b.grade.label()).unwrap_or(""),
+        bufferbloat
+            .and_then(|b| b.download_increase_ms)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
+        bufferbloat
+            .and_then(|b| b.upload_increase_ms)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
+        result
+            .fresh_connection_latency
+            .as_ref()
+            .and_then(|l| l.median_ms)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
+        result
+            .warm_connection_latency
+            .as_ref()
+            .and_then(|l| l.median_ms)
+            .map(|v| format!("{:.3}", v))
+            .unwrap_or_default(),
+        nat.map(|n| n.nat_type.as_str()).unwrap_or(""),
+        nat.and_then(|n| n.mapping).map(|b| b.label()).unwrap_or(""),
+        nat.and_then(|n| n.filtering).map(|b| b.label()).unwrap_or(""),
+        nat.map(|n| n.mapped_address.as_str()).unwrap_or(""),
     ));
     std::fs::write(path, out).context("write export csv")?;
     Ok(())
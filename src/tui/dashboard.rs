@@ -10,6 +10,7 @@ use ratatui::{
 
 use super::charts;
 use super::state::{push_wrapped_status_kv, UiState};
+use crate::model::{BufferbloatGrade, ConnectionStages, TcpLimit};
 
 /// Helper function to get the maximum y value from a series of points
 pub fn max_y(points: &[(f64, f64)]) -> f64 {
@@ -41,6 +42,57 @@ fn udp_split_bar(sent: u64, received: u64, width: usize) -> Line<'static> {
     ])
 }
 
+/// Waterfall bar of connection stages, each segment scaled to its share of the total
+fn connection_waterfall(stages: &ConnectionStages, width: usize) -> Vec<Line<'static>> {
+    const COLORS: [Color; 5] = [
+        Color::Magenta,
+        Color::Blue,
+        Color::Cyan,
+        Color::Yellow,
+        Color::Green,
+    ];
+    let total = stages.total_ms().max(f64::EPSILON);
+
+    let mut bar = vec![
+        Span::styled("Waterfall: ", Style::default().fg(Color::Gray)),
+        Span::raw("["),
+    ];
+    let mut legend = vec![Span::raw("  ")];
+    for ((label, ms), color) in stages.stages().into_iter().zip(COLORS) {
+        // Any non-zero stage gets at least one cell so it stays visible
+        let units = if ms > 0.0 {
+            ((width as f64 * ms / total).round() as usize).max(1)
+        } else {
+            0
+        };
+        bar.push(Span::styled("█".repeat(units), Style::default().fg(color)));
+        legend.push(Span::styled(
+            format!("{} {:.0} ", label, ms),
+            Style::default().fg(color),
+        ));
+    }
+    bar.push(Span::raw(format!("] {:.0}ms", stages.total_ms())));
+
+    vec![Line::from(bar), Line::from(legend)]
+}
+
+/// Text sparkline of the last `width` values, scaled from zero to their maximum.
+fn sparkline_text(values: &[f64], width: usize) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().cloned().fold(0.0_f64, f64::max);
+    values
+        .iter()
+        .map(|v| {
+            if max <= 0.0 {
+                return LEVELS[0];
+            }
+            let idx = ((v / max) * (LEVELS.len() - 1) as f64).round() as usize;
+            LEVELS[idx.min(LEVELS.len() - 1)]
+        })
+        .collect()
+}
+
 /// Get color for quality label based on loss severity
 fn quality_label_color(label: &str) -> Color {
     match label {
@@ -59,12 +111,19 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
         return draw_dashboard_compact(area, f, state);
     }
 
+    // The bidirectional row only takes space once that phase has started
+    let bidir_height = if state.bidir_phase_start.is_some() {
+        10
+    } else {
+        0
+    };
     let main = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
                 Constraint::Length(13), // Throughput charts row with metrics (side-by-side)
                 Constraint::Length(10), // Latency box plots with metrics below (idle + loaded DL + loaded UL)
+                Constraint::Length(bidir_height), // Bidirectional throughput + latency
                 Constraint::Length(3),  // Packet loss (UDP) row
                 Constraint::Min(0),     // Network Information + Keyboard Shortcuts (side-by-side)
                 Constraint::Length(5),  // Status row (full width at bottom)
@@ -306,6 +365,10 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
         f.render_widget(empty, lat_row[2]);
     }
 
+    if bidir_height > 0 {
+        draw_bidirectional_row(f, main[2], state);
+    }
+
     // Packet loss row (full width) with live progress during measurement
     let (udp_sent, udp_received, udp_total, udp_latest_rtt) = if state.udp_loss_total > 0 {
         (
@@ -343,8 +406,8 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
     let udp_block = Block::default()
         .borders(Borders::ALL)
         .title("Packet Loss (UDP/TURN)");
-    let udp_inner = udp_block.inner(main[2]);
-    f.render_widget(udp_block, main[2]);
+    let udp_inner = udp_block.inner(main[3]);
+    f.render_widget(udp_block, main[3]);
 
     if let Some(err) = state
         .last_result
@@ -501,7 +564,7 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
     let info_row = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
-        .split(main[3]);
+        .split(main[4]);
 
     // Network Information panel (left)
 
@@ -589,11 +652,337 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
         ]),
     ]);
 
+    // Upload cross-check: confirmed request bytes vs kernel-acknowledged bytes
+    if let Some(acc) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.upload.accounting.as_ref())
+    {
+        let mut spans = vec![
+            Span::styled("Upload check: ", Style::default().fg(Color::Gray)),
+            Span::raw(format!(
+                "sent {:.1}, confirmed {:.1}",
+                acc.sent_mbps(),
+                acc.confirmed_mbps()
+            )),
+        ];
+        if let Some(acked) = acc.kernel_acked_mbps() {
+            spans.push(Span::raw(format!(", acked {:.1}", acked)));
+        }
+        spans.push(Span::raw(" Mbps"));
+        if let Some(diff) = acc.difference_pct() {
+            let color = if diff.abs() > 10.0 {
+                Color::Yellow
+            } else {
+                Color::Gray
+            };
+            spans.push(Span::styled(
+                format!(" ({:+.1}%)", diff),
+                Style::default().fg(color),
+            ));
+        }
+        network_lines.push(Line::from(spans));
+    }
+
+    // Stream count and request size the throughput phases ended with
+    if let Some(r) = state
+        .last_result
+        .as_ref()
+        .filter(|r| r.download.streams > 0)
+    {
+        network_lines.push(Line::from(vec![
+            Span::styled("Streams: ", Style::default().fg(Color::Gray)),
+            Span::raw(format!(
+                "DL {} x {:.1}MB, UL {} x {:.1}MB",
+                r.download.streams,
+                r.download.bytes_per_req as f64 / 1_000_000.0,
+                r.upload.streams,
+                r.upload.bytes_per_req as f64 / 1_000_000.0
+            )),
+        ]));
+    }
+
+    // One connection vs the multi-stream result; a low share points at
+    // per-flow shaping or window limits rather than raw capacity
+    if let Some(r) = state.last_result.as_ref() {
+        let mut spans = Vec::new();
+        for (label, single, multi) in [
+            ("DL", &r.single_stream_download, &r.download),
+            ("UL", &r.single_stream_upload, &r.upload),
+        ] {
+            let Some(single) = single else { continue };
+            let pct = single.percent_of(multi).unwrap_or(0.0);
+            let color = if pct < 50.0 {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            if !spans.is_empty() {
+                spans.push(Span::raw(", "));
+            }
+            spans.push(Span::raw(format!("{} {:.1} Mbps ", label, single.mbps)));
+            spans.push(Span::styled(
+                format!("({:.0}%)", pct),
+                Style::default().fg(color),
+            ));
+        }
+        if !spans.is_empty() {
+            spans.insert(
+                0,
+                Span::styled("Single stream: ", Style::default().fg(Color::Gray)),
+            );
+            network_lines.push(Line::from(spans));
+        }
+    }
+
+    // What a new connection costs over a warm one
+    if let Some(r) = state.last_result.as_ref() {
+        let fresh = r
+            .fresh_connection_latency
+            .as_ref()
+            .and_then(|l| l.median_ms);
+        let warm = r.warm_connection_latency.as_ref().and_then(|l| l.median_ms);
+        if let (Some(fresh), Some(warm)) = (fresh, warm) {
+            network_lines.push(Line::from(vec![
+                Span::styled("Connection latency: ", Style::default().fg(Color::Gray)),
+                Span::raw(format!(
+                    "fresh {:.0}ms, warm {:.0}ms (setup +{:.0}ms)",
+                    fresh,
+                    warm,
+                    (fresh - warm).max(0.0)
+                )),
+            ]));
+        }
+    }
+
+    // Latency increase under load, graded against the configured bands
+    if let Some(bb) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.bufferbloat.as_ref())
+    {
+        let color = match bb.grade {
+            BufferbloatGrade::APlus | BufferbloatGrade::A => Color::Green,
+            BufferbloatGrade::B | BufferbloatGrade::C => Color::Yellow,
+            BufferbloatGrade::D | BufferbloatGrade::F => Color::Red,
+        };
+        let increase = |ms: Option<f64>| {
+            ms.map(|ms| format!("+{:.0}ms", ms))
+                .unwrap_or_else(|| "-".into())
+        };
+        network_lines.push(Line::from(vec![
+            Span::styled("Bufferbloat: ", Style::default().fg(Color::Gray)),
+            Span::styled(bb.grade.label(), Style::default().fg(color)),
+            Span::raw(format!(
+                " (DL {}, UL {})",
+                increase(bb.download_increase_ms),
+                increase(bb.upload_increase_ms)
+            )),
+        ]));
+    }
+
+    // Round-trips per minute while each direction is loaded
+    if let Some(resp) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.responsiveness.as_ref())
+    {
+        let mut spans = vec![Span::styled(
+            "Responsiveness: ",
+            Style::default().fg(Color::Gray),
+        )];
+        for (label, rpm) in [("DL", &resp.download), ("UL", &resp.upload)] {
+            let Some(rpm) = rpm else { continue };
+            let color = if rpm.best() < 300.0 {
+                Color::Red
+            } else if rpm.best() < 1000.0 {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            if spans.len() > 1 {
+                spans.push(Span::raw(", "));
+            }
+            spans.push(Span::raw(format!("{} ", label)));
+            spans.push(Span::styled(
+                format!("{:.0} RPM", rpm.best()),
+                Style::default().fg(color),
+            ));
+        }
+        network_lines.push(Line::from(spans));
+    }
+
+    // Side-by-side results when several --stun-server targets were probed
+    if let Some(r) = state
+        .last_result
+        .as_ref()
+        .filter(|r| r.stun_results.len() > 1)
+    {
+        for result in &r.stun_results {
+            let value = match result.summary {
+                Some(ref exp) => {
+                    let rtt = exp
+                        .latency
+                        .median_ms
+                        .map(|ms| format!(", rtt {:.0}ms", ms))
+                        .unwrap_or_default();
+                    Span::raw(format!("loss {:.1}%{}", exp.latency.loss * 100.0, rtt))
+                }
+                None => Span::styled("failed", Style::default().fg(Color::Red)),
+            };
+            network_lines.push(Line::from(vec![
+                Span::styled(
+                    format!("{}: ", result.target),
+                    Style::default().fg(Color::Gray),
+                ),
+                value,
+            ]));
+        }
+    }
+
+    // Same UDP probe, relayed through a TURN allocation
+    if let Some(relay) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.turn_relay.as_ref())
+    {
+        let loss_pct = relay.latency.loss * 100.0;
+        let rtt = relay
+            .latency
+            .median_ms
+            .map(|ms| format!(", rtt {:.0}ms", ms))
+            .unwrap_or_default();
+        network_lines.push(Line::from(vec![
+            Span::styled("TURN relay: ", Style::default().fg(Color::Gray)),
+            Span::raw(format!("loss {:.1}%{}", loss_pct, rtt)),
+        ]));
+    }
+
+    // UDP loss and jitter while the link was saturated (--udp-under-load)
+    if let Some(loaded) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.loaded_udp.as_ref())
+    {
+        let mut spans = vec![Span::styled(
+            "UDP under load: ",
+            Style::default().fg(Color::Gray),
+        )];
+        for (label, exp) in [("DL", &loaded.download), ("UL", &loaded.upload)] {
+            let Some(exp) = exp else { continue };
+            let loss_pct = exp.latency.loss * 100.0;
+            let color = if loss_pct == 0.0 {
+                Color::Green
+            } else if loss_pct < 2.5 {
+                Color::Yellow
+            } else {
+                Color::Red
+            };
+            if spans.len() > 1 {
+                spans.push(Span::raw(", "));
+            }
+            spans.push(Span::raw(format!("{} ", label)));
+            spans.push(Span::styled(
+                format!("loss {:.1}%", loss_pct),
+                Style::default().fg(color),
+            ));
+            if let Some(jitter) = exp.latency.jitter_ms {
+                spans.push(Span::raw(format!(" jitter {:.1}ms", jitter)));
+            }
+        }
+        network_lines.push(Line::from(spans));
+    }
+
+    // HTTP version the test requests actually used
+    if let Some(protocol) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.http_protocol.as_deref())
+    {
+        network_lines.push(Line::from(vec![
+            Span::styled("Protocol: ", Style::default().fg(Color::Gray)),
+            Span::raw(protocol.to_string()),
+        ]));
+    }
+
+    // Per-run socket options (congestion control, buffer sizes)
+    if let Some(socket) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.tcp_socket.as_ref())
+    {
+        network_lines.push(Line::from(vec![
+            Span::styled("TCP options: ", Style::default().fg(Color::Gray)),
+            Span::raw(socket.describe()),
+        ]));
+    }
+
+    // NAT type and mapping/filtering behavior from the STUN tests
+    if let Some(nat) = state.last_result.as_ref().and_then(|r| r.nat.as_ref()) {
+        network_lines.push(Line::from(vec![
+            Span::styled("NAT: ", Style::default().fg(Color::Gray)),
+            Span::raw(nat.describe()),
+        ]));
+    }
+
+    // Kernel TCP telemetry per phase: summary plus RTT and cwnd over time
+    if let Some(tcp) = state
+        .last_result
+        .as_ref()
+        .and_then(|r| r.tcp_telemetry.as_ref())
+    {
+        for (label, phase, color) in [
+            ("TCP DL: ", &tcp.download, Color::Green),
+            ("TCP UL: ", &tcp.upload, Color::Cyan),
+        ] {
+            let Some(phase) = phase else { continue };
+            let mut spans = vec![
+                Span::styled(label, Style::default().fg(Color::Gray)),
+                Span::raw(format!(
+                    "{}, {} conns, {} retrans",
+                    phase.congestion_control.as_deref().unwrap_or("-"),
+                    phase.connections,
+                    phase.retransmits
+                )),
+            ];
+            if let Some(limit) = phase.limit {
+                let limit_color = if limit == TcpLimit::Loss {
+                    Color::Red
+                } else {
+                    Color::Yellow
+                };
+                spans.push(Span::raw(", limit: "));
+                spans.push(Span::styled(
+                    limit.label(),
+                    Style::default().fg(limit_color),
+                ));
+            }
+            network_lines.push(Line::from(spans));
+
+            let srtt: Vec<f64> = phase.samples.iter().map(|s| s.srtt_ms).collect();
+            let cwnd: Vec<f64> = phase.samples.iter().map(|s| s.cwnd).collect();
+            network_lines.push(Line::from(vec![
+                Span::styled("  RTT ", Style::default().fg(Color::Gray)),
+                Span::styled(sparkline_text(&srtt, 15), Style::default().fg(color)),
+                Span::raw(format!(
+                    " {:.0}ms",
+                    phase.median_srtt_ms().unwrap_or(f64::NAN)
+                )),
+                Span::styled("  cwnd ", Style::default().fg(Color::Gray)),
+                Span::styled(sparkline_text(&cwnd, 15), Style::default().fg(color)),
+                Span::raw(format!(" {:.0}", phase.median_cwnd().unwrap_or(f64::NAN))),
+            ]));
+        }
+    }
+
     // Diagnostic results at the end, before the source link
     let has_diagnostics = state.dns_summary.is_some()
         || state.tls_summary.is_some()
+        || state.pmtu_summary.is_some()
+        || state.connection_timing.is_some()
         || state.ip_comparison.is_some()
-        || state.traceroute_summary.is_some();
+        || state.traceroute_summary.is_some()
+        || state.path_monitor.is_some();
 
     if has_diagnostics {
         network_lines.push(Line::from("")); // Separator
@@ -603,6 +992,61 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
                 Span::styled("DNS resolution: ", Style::default().fg(Color::Gray)),
                 Span::raw(format!("{:.2}ms", dns.resolution_time_ms)),
             ]));
+            // Per-resolver first/repeat timings; the slowest first lookup stands out
+            let slowest = dns
+                .per_server
+                .iter()
+                .filter_map(|s| s.worst_first_ms())
+                .fold(None, |acc: Option<f64>, v| {
+                    Some(acc.map_or(v, |a| a.max(v)))
+                });
+            let timing = |t: &crate::model::DnsRecordTiming| match t.first_ms {
+                Some(first) => format!(
+                    "{:.0}/{}",
+                    first,
+                    t.repeat_ms
+                        .map(|w| format!("{:.0}", w))
+                        .unwrap_or_else(|| "-".into())
+                ),
+                None if t.error.is_some() => "err".into(),
+                None => "-".into(),
+            };
+            for s in &dns.per_server {
+                let style = if dns.per_server.len() > 1 && s.worst_first_ms() == slowest {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                };
+                network_lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("  {}{}: ", s.server, if s.public { "*" } else { "" }),
+                        Style::default().fg(Color::Gray),
+                    ),
+                    Span::styled(
+                        format!("A {} AAAA {} ms", timing(&s.a), timing(&s.aaaa)),
+                        style,
+                    ),
+                ]));
+            }
+            for e in &dns.encrypted {
+                let value = match e.first_ms {
+                    Some(first) => Span::raw(format!(
+                        "{:.2}ms ({} reused)",
+                        first,
+                        e.reused_ms
+                            .map(|r| format!("{:.2}ms", r))
+                            .unwrap_or_else(|| "-".into())
+                    )),
+                    None => Span::styled("failed", Style::default().fg(Color::Red)),
+                };
+                network_lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("{}: ", e.protocol.label()),
+                        Style::default().fg(Color::Gray),
+                    ),
+                    value,
+                ]));
+            }
         }
 
         if let Some(ref tls) = state.tls_summary {
@@ -614,6 +1058,72 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
                     tls.protocol_version.as_deref().unwrap_or("-")
                 )),
             ]));
+            if tls.alpn.is_some() || tls.key_exchange_group.is_some() {
+                network_lines.push(Line::from(vec![
+                    Span::styled("TLS ALPN/group: ", Style::default().fg(Color::Gray)),
+                    Span::raw(format!(
+                        "{} / {}",
+                        tls.alpn.as_deref().unwrap_or("-"),
+                        tls.key_exchange_group.as_deref().unwrap_or("-")
+                    )),
+                ]));
+            }
+            if let Some(leaf) = tls.certificates.first() {
+                let issuer_style = if tls.custom_root {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default()
+                };
+                let mut spans = vec![
+                    Span::styled("TLS issuer: ", Style::default().fg(Color::Gray)),
+                    Span::styled(leaf.issuer.clone(), issuer_style),
+                ];
+                if tls.custom_root {
+                    spans.push(Span::styled(
+                        " (not publicly trusted)",
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                network_lines.push(Line::from(spans));
+            }
+            if let Some(resumed_ms) = tls.resumed_handshake_time_ms {
+                let mut text = format!(
+                    "{:.2}ms{}",
+                    resumed_ms,
+                    if tls.resumed { "" } else { " (not resumed)" }
+                );
+                if let (Some(zero_rtt_ms), Some(accepted)) =
+                    (tls.zero_rtt_first_byte_ms, tls.early_data_accepted)
+                {
+                    text.push_str(&format!(
+                        ", 0-RTT {:.0}ms{}",
+                        zero_rtt_ms,
+                        if accepted { "" } else { " (rejected)" }
+                    ));
+                }
+                network_lines.push(Line::from(vec![
+                    Span::styled("TLS resumption: ", Style::default().fg(Color::Gray)),
+                    Span::raw(text),
+                ]));
+            }
+        }
+
+        if let Some(ref pmtu) = state.pmtu_summary {
+            let mut spans = vec![
+                Span::styled("Path MTU: ", Style::default().fg(Color::Gray)),
+                Span::raw(format!("PMTU {}, MSS {}", pmtu.pmtu, pmtu.mss)),
+            ];
+            if pmtu.black_hole_suspected {
+                spans.push(Span::styled(
+                    " (black hole?)",
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            network_lines.push(Line::from(spans));
+        }
+
+        if let Some(ref ct) = state.connection_timing {
+            network_lines.extend(connection_waterfall(&ct.median, 30));
         }
 
         if let Some(ref cmp) = state.ip_comparison {
@@ -649,7 +1159,34 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
             let status = if tr.completed { "complete" } else { "partial" };
             network_lines.push(Line::from(vec![
                 Span::styled("Traceroute: ", Style::default().fg(Color::Gray)),
-                Span::raw(format!("{} hops ({})", tr.hops.len(), status)),
+                Span::raw(format!(
+                    "{} hops ({}, {})",
+                    tr.hops.len(),
+                    status,
+                    tr.mode.label()
+                )),
+            ]));
+        }
+
+        if let Some(ref pm) = state.path_monitor {
+            // Summarize the lossiest hop; the Path tab has the full table
+            let worst = pm.hops.iter().filter(|h| h.sent > 0).max_by(|a, b| {
+                a.loss
+                    .partial_cmp(&b.loss)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            let text = match worst {
+                Some(h) if h.loss > 0.0 => format!(
+                    "{} hops, max loss {:.1}% at hop {}",
+                    pm.hops.len(),
+                    h.loss * 100.0,
+                    h.hop_number
+                ),
+                _ => format!("{} hops, no loss", pm.hops.len()),
+            };
+            network_lines.push(Line::from(vec![
+                Span::styled("Path: ", Style::default().fg(Color::Gray)),
+                Span::raw(text),
             ]));
         }
     }
@@ -739,7 +1276,7 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
 
     // Custom comments (wrapping to fit status area)
     if let Some(comments) = state.comments.as_deref() {
-        push_wrapped_status_kv(&mut status_lines, "Comments", comments, main[4].width);
+        push_wrapped_status_kv(&mut status_lines, "Comments", comments, main[5].width);
     }
 
     // Info line - split into two lines if it contains a saved path, with wrapping
@@ -752,7 +1289,7 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
 
             // Wrap the path to fit within available width
             // Account for borders (2 chars on each side)
-            let status_area_width = main[4].width.saturating_sub(4);
+            let status_area_width = main[5].width.saturating_sub(4);
             let label_width = label_text.chars().count() as u16;
             let path_chars: Vec<char> = path_str.chars().collect();
             let mut remaining = path_chars.as_slice();
@@ -802,7 +1339,95 @@ pub fn draw_dashboard(area: Rect, f: &mut Frame, state: &UiState) {
 
     let status =
         Paragraph::new(status_lines).block(Block::default().borders(Borders::ALL).title("Status"));
-    f.render_widget(status, main[4]);
+    f.render_widget(status, main[5]);
+}
+
+/// Both directions on one chart, next to the latency seen under full-duplex load
+fn draw_bidirectional_row(f: &mut Frame, area: Rect, state: &UiState) {
+    let row = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(67), Constraint::Percentage(33)].as_ref())
+        .split(area);
+
+    let title = Line::from(vec![
+        Span::raw("Bidirectional (DL "),
+        Span::styled(
+            format!("{:.0}", state.bidir_dl_mbps),
+            Style::default().fg(Color::Green),
+        ),
+        Span::raw(" / UL "),
+        Span::styled(
+            format!("{:.0}", state.bidir_ul_mbps),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(" Mbps)"),
+    ]);
+    let dl_points = &state.bidir_dl_points;
+    let ul_points = &state.bidir_ul_points;
+    if !dl_points.is_empty() {
+        let x_min = dl_points.first().map(|(x, _)| *x).unwrap_or(0.0);
+        let x_max = dl_points.last().map(|(x, _)| *x).unwrap_or(0.0);
+        let y_max = max_y(dl_points).max(max_y(ul_points)).max(10.0);
+        let y_max = (y_max * 1.10).min(10_000.0);
+
+        let datasets = vec![
+            Dataset::default()
+                .graph_type(GraphType::Line)
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Green))
+                .data(dl_points),
+            Dataset::default()
+                .graph_type(GraphType::Line)
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Cyan))
+                .data(ul_points),
+        ];
+        charts::render_chart_with_metrics_inside(
+            f,
+            row[0],
+            datasets,
+            Axis::default().bounds([x_min, x_max.max(1.0)]),
+            Axis::default().title("Mbps").bounds([0.0, y_max]),
+            title,
+            None,
+            Color::Green,
+        );
+    } else {
+        let empty = Paragraph::new("Waiting for bidirectional phase...")
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(empty, row[0]);
+    }
+
+    if state.bidir_latency_samples.len() >= 2 {
+        let median = crate::metrics::compute_metrics(&state.bidir_latency_samples)
+            .map(|(_, med, _, _)| med)
+            .unwrap_or(f64::NAN);
+        let jitter = crate::metrics::compute_jitter(&state.bidir_latency_samples);
+        let title = Line::from(vec![
+            Span::raw("Latency Bidirectional ("),
+            Span::styled(
+                format!("{:.0}ms", median),
+                Style::default().fg(Color::Magenta),
+            ),
+            Span::raw(")"),
+        ]);
+        charts::render_box_plot_with_metrics_inside(
+            f,
+            row[1],
+            &state.bidir_latency_samples,
+            title,
+            Some(Color::Magenta),
+            jitter,
+            None,
+        );
+    } else {
+        let empty = Paragraph::new("Waiting for data...").block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Latency Bidirectional"),
+        );
+        f.render_widget(empty, row[1]);
+    }
 }
 
 pub fn draw_dashboard_compact(area: Rect, f: &mut Frame, state: &UiState) {
@@ -987,6 +1612,9 @@ pub fn draw_dashboard_compact(area: Rect, f: &mut Frame, state: &UiState) {
     if let Some(ref tls) = state.tls_summary {
         diag_parts.push(format!("TLS:{:.0}ms", tls.handshake_time_ms));
     }
+    if let Some(ref pmtu) = state.pmtu_summary {
+        diag_parts.push(format!("PMTU:{}", pmtu.pmtu));
+    }
     if let Some(ref tr) = state.traceroute_summary {
         diag_parts.push(format!("Hops:{}", tr.hops.len()));
     }
@@ -3,6 +3,7 @@ mod dashboard;
 mod export;
 mod help;
 mod history;
+mod path;
 mod state;
 
 pub use state::UiState;
@@ -34,6 +35,7 @@ use dashboard::draw_dashboard;
 use export::{copy_to_clipboard, enrich_result_with_network_info, export_result_csv, export_result_json, save_and_show_path};
 use help::draw_help;
 use history::{show_history, draw_history_detail};
+use path::draw_path;
 use state::update_available_networks;
 
 pub async fn run(args: Cli) -> Result<()> {
@@ -248,6 +250,12 @@ pub async fn run(args: Cli) -> Result<()> {
                                 state.ul_bytes_total = 0;
                                 state.dl_phase_start = None;
                                 state.ul_phase_start = None;
+                                state.bidir_phase_start = None;
+                                state.bidir_dl_mbps = 0.0;
+                                state.bidir_ul_mbps = 0.0;
+                                state.bidir_dl_points.clear();
+                                state.bidir_ul_points.clear();
+                                state.bidir_latency_samples.clear();
                                 state.idle_latency_samples.clear();
                                 state.loaded_dl_latency_samples.clear();
                                 state.loaded_ul_latency_samples.clear();
@@ -267,8 +275,11 @@ pub async fn run(args: Cli) -> Result<()> {
                                 // Clear diagnostic results
                                 state.dns_summary = None;
                                 state.tls_summary = None;
+                                state.pmtu_summary = None;
+                                state.connection_timing = None;
                                 state.ip_comparison = None;
                                 state.traceroute_summary = None;
+                                state.path_monitor = None;
                                 run_ctx = Some(start_run(&args).await?);
                             }
                         }
@@ -350,7 +361,7 @@ pub async fn run(args: Cli) -> Result<()> {
                         }
                         (KeyModifiers::SHIFT, KeyCode::BackTab) => {
                             // Shift+Tab cycles backwards
-                            let new_tab = if state.tab == 0 { 3 } else { state.tab - 1 };
+                            let new_tab = if state.tab == 0 { 4 } else { state.tab - 1 };
                             state.tab = new_tab;
                             if new_tab == 1 {
                                 state.history_selected = 0;
@@ -358,7 +369,7 @@ pub async fn run(args: Cli) -> Result<()> {
                             }
                         }
                         (_, KeyCode::Tab) => {
-                            let new_tab = (state.tab + 1) % 4;
+                            let new_tab = (state.tab + 1) % 5;
                             state.tab = new_tab;
                             // Reset history selection when switching to history tab
                             if new_tab == 1 {
@@ -367,7 +378,7 @@ pub async fn run(args: Cli) -> Result<()> {
                             }
                         }
                         (_, KeyCode::Char('?')) => {
-                            state.tab = 3; // help
+                            state.tab = 4; // help
                         }
                         // History navigation and deletion (only when on History tab)
                         (_, KeyCode::Up) | (_, KeyCode::Char('k')) => {
@@ -686,6 +697,12 @@ fn apply_event(state: &mut UiState, ev: TestEvent) {
                     state.loaded_ul_latency_sent = 0;
                     state.loaded_ul_latency_received = 0;
                 }
+                Phase::Bidirectional => {
+                    state.bidir_phase_start = Some(Instant::now());
+                    state.bidir_dl_points.clear();
+                    state.bidir_ul_points.clear();
+                    state.bidir_latency_samples.clear();
+                }
                 Phase::PacketLoss => {
                     state.udp_loss_sent = 0;
                     state.udp_loss_received = 0;
@@ -777,6 +794,16 @@ fn apply_event(state: &mut UiState, ev: TestEvent) {
                         }
                     }
                 }
+                (Phase::Bidirectional, Some(Phase::Bidirectional)) => {
+                    if let (true, Some(ms)) = (ok, rtt_ms) {
+                        state.bidir_latency_samples.push(ms);
+                        if state.bidir_latency_samples.len() > 10000 {
+                            state
+                                .bidir_latency_samples
+                                .drain(0..(state.bidir_latency_samples.len() - 10000));
+                        }
+                    }
+                }
                 _ => {}
             }
         }
@@ -813,6 +840,16 @@ fn apply_event(state: &mut UiState, ev: TestEvent) {
                 _ => {}
             }
         }
+        TestEvent::BidirectionalTick {
+            download_bps,
+            upload_bps,
+        } => {
+            let t = state.run_start.elapsed().as_secs_f64();
+            state.bidir_dl_mbps = (download_bps * 8.0) / 1_000_000.0;
+            state.bidir_ul_mbps = (upload_bps * 8.0) / 1_000_000.0;
+            UiState::push_point(&mut state.bidir_dl_points, t, state.bidir_dl_mbps.max(0.0));
+            UiState::push_point(&mut state.bidir_ul_points, t, state.bidir_ul_mbps.max(0.0));
+        }
         TestEvent::UdpLossProgress {
             sent,
             received,
@@ -851,6 +888,18 @@ fn apply_event(state: &mut UiState, ev: TestEvent) {
             );
             state.tls_summary = Some(summary);
         }
+        TestEvent::DiagnosticPmtu { summary } => {
+            state.info = format!("PMTU {}, MSS {}", summary.pmtu, summary.mss);
+            state.pmtu_summary = Some(summary);
+        }
+        TestEvent::DiagnosticConnectionTiming { summary } => {
+            state.info = format!(
+                "Connection: {:.0}ms median over {} samples",
+                summary.median.total_ms(),
+                summary.samples.len()
+            );
+            state.connection_timing = Some(summary);
+        }
         TestEvent::DiagnosticIpComparison { comparison } => {
             let v4_info = comparison
                 .ipv4_result
@@ -888,12 +937,16 @@ fn apply_event(state: &mut UiState, ev: TestEvent) {
         }
         TestEvent::TracerouteComplete { summary } => {
             state.info = format!(
-                "Traceroute: {} hops to {}",
+                "Traceroute ({}): {} hops to {}",
+                summary.mode.label(),
                 summary.hops.len(),
                 summary.destination
             );
             state.traceroute_summary = Some(summary);
         }
+        TestEvent::PathMonitorUpdate { summary } => {
+            state.path_monitor = Some(summary);
+        }
         TestEvent::ExternalIps { ipv4, ipv6 } => {
             state.external_ipv4 = ipv4;
             state.external_ipv6 = ipv6;
@@ -911,6 +964,7 @@ fn draw(area: Rect, f: &mut ratatui::Frame, state: &mut UiState) {
         Line::from("Dashboard"),
         Line::from("History"),
         Line::from("Charts"),
+        Line::from("Path"),
         Line::from("Help"),
     ])
     .select(state.tab)
@@ -939,6 +993,7 @@ fn draw(area: Rect, f: &mut ratatui::Frame, state: &mut UiState) {
             }
         }
         2 => draw_charts(chunks[1], f, state),
+        3 => draw_path(chunks[1], f, state),
         _ => draw_help(chunks[1], f),
     }
 }
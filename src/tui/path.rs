@@ -0,0 +1,148 @@
+use crate::model::{PathMonitorSummary, TracerouteSummary};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+use super::state::UiState;
+
+/// Path tab: traceroute hops and live per-hop statistics from the path monitor.
+pub fn draw_path(area: Rect, f: &mut Frame, state: &UiState) {
+    match (&state.traceroute_summary, &state.path_monitor) {
+        (None, None) => {
+            let p = Paragraph::new(vec![
+                Line::from("No path data."),
+                Line::from(""),
+                Line::from("Run with --traceroute to trace the path once, or --path-monitor to"),
+                Line::from("probe every hop for the whole test (needs CAP_NET_RAW or root)."),
+                Line::from("Add --asn-table to label hops with their AS."),
+            ])
+            .block(Block::default().borders(Borders::ALL).title("Path"));
+            f.render_widget(p, area);
+        }
+        (Some(tr), None) => draw_traceroute_table(area, f, tr),
+        (None, Some(pm)) => draw_monitor_table(area, f, pm),
+        (Some(tr), Some(pm)) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(area);
+            draw_traceroute_table(rows[0], f, tr);
+            draw_monitor_table(rows[1], f, pm);
+        }
+    }
+}
+
+fn header_style() -> Style {
+    Style::default()
+        .fg(Color::Gray)
+        .add_modifier(Modifier::BOLD)
+}
+
+fn draw_traceroute_table(area: Rect, f: &mut Frame, tr: &TracerouteSummary) {
+    let header = Row::new(vec!["Hop", "Address", "Host", "AS", "RTT (ms)"]).style(header_style());
+
+    let rows: Vec<Row> = tr
+        .hops
+        .iter()
+        .map(|hop| {
+            let rtts = if hop.rtt_ms.is_empty() {
+                "*".to_string()
+            } else {
+                hop.rtt_ms
+                    .iter()
+                    .map(|r| format!("{:.1}", r))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            let as_label = match (hop.asn, hop.as_org.as_deref()) {
+                (Some(asn), Some(org)) => format!("AS{} {}", asn, org),
+                (Some(asn), None) => format!("AS{}", asn),
+                _ => String::new(),
+            };
+            Row::new(vec![
+                Cell::from(hop.hop_number.to_string()),
+                Cell::from(hop.ip_address.clone().unwrap_or_else(|| "*".into())),
+                Cell::from(hop.hostname.clone().unwrap_or_default()),
+                Cell::from(as_label).style(Style::default().fg(Color::Cyan)),
+                Cell::from(rtts),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Length(16),
+        Constraint::Min(16),
+        Constraint::Min(12),
+        Constraint::Length(20),
+    ];
+
+    let status = if tr.completed { "complete" } else { "partial" };
+    let table = Table::new(rows, widths).header(header).block(
+        Block::default().borders(Borders::ALL).title(format!(
+            "Traceroute ({}) to {} - {}",
+            tr.mode.label(),
+            tr.destination,
+            status
+        )),
+    );
+    f.render_widget(table, area);
+}
+
+fn draw_monitor_table(area: Rect, f: &mut Frame, pm: &PathMonitorSummary) {
+    let ms = |v: Option<f64>| v.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "-".into());
+
+    let header = Row::new(vec![
+        "Hop", "Address", "Loss%", "Snt", "Last", "Best", "Avg", "Worst", "StDev",
+    ])
+    .style(header_style());
+
+    let rows: Vec<Row> = pm
+        .hops
+        .iter()
+        .map(|hop| {
+            let loss_pct = hop.loss * 100.0;
+            let loss_color = if hop.sent == 0 || loss_pct == 0.0 {
+                Color::Green
+            } else if loss_pct < 10.0 {
+                Color::Yellow
+            } else {
+                Color::Red
+            };
+            Row::new(vec![
+                Cell::from(hop.hop_number.to_string()),
+                Cell::from(hop.ip_address.clone().unwrap_or_else(|| "???".into())),
+                Cell::from(format!("{:.1}", loss_pct)).style(Style::default().fg(loss_color)),
+                Cell::from(hop.sent.to_string()),
+                Cell::from(ms(hop.last_ms)),
+                Cell::from(ms(hop.best_ms)),
+                Cell::from(ms(hop.avg_ms)),
+                Cell::from(ms(hop.worst_ms)),
+                Cell::from(ms(hop.stddev_ms)),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(4),
+        Constraint::Min(16),
+        Constraint::Length(6),
+        Constraint::Length(5),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Length(7),
+    ];
+
+    let table = Table::new(rows, widths).header(header).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Path to {} ({} rounds)", pm.destination, pm.rounds)),
+    );
+    f.render_widget(table, area);
+}
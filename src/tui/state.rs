@@ -1,4 +1,7 @@
-use crate::model::{DnsSummary, IpVersionComparison, Phase, RunResult, TlsSummary, TracerouteSummary};
+use crate::model::{
+    ConnectionTimingSummary, DnsSummary, IpVersionComparison, PathMonitorSummary, Phase,
+    PmtuSummary, RunResult, TlsSummary, TracerouteSummary,
+};
 use ratatui::{
     style::Color,
     style::Style,
@@ -36,6 +39,14 @@ pub struct UiState {
     pub dl_phase_start: Option<Instant>,
     pub ul_phase_start: Option<Instant>,
 
+    // Bidirectional phase (download and upload at once)
+    pub bidir_phase_start: Option<Instant>,
+    pub bidir_dl_mbps: f64,
+    pub bidir_ul_mbps: f64,
+    pub bidir_dl_points: Vec<(f64, f64)>,
+    pub bidir_ul_points: Vec<(f64, f64)>,
+    pub bidir_latency_samples: Vec<f64>,
+
     // Live latency samples for real-time stats
     pub idle_latency_samples: Vec<f64>,
     pub loaded_dl_latency_samples: Vec<f64>,
@@ -87,8 +98,11 @@ pub struct UiState {
     // Diagnostic results
     pub dns_summary: Option<DnsSummary>,
     pub tls_summary: Option<TlsSummary>,
+    pub pmtu_summary: Option<PmtuSummary>,
+    pub connection_timing: Option<ConnectionTimingSummary>,
     pub ip_comparison: Option<IpVersionComparison>,
     pub traceroute_summary: Option<TracerouteSummary>,
+    pub path_monitor: Option<PathMonitorSummary>,
     /// None = check not completed, Some(None) = on latest, Some(Some(v)) = update available
     pub update_status: Option<Option<String>>,
 }
@@ -120,6 +134,12 @@ impl Default for UiState {
             ul_bytes_total: 0,
             dl_phase_start: None,
             ul_phase_start: None,
+            bidir_phase_start: None,
+            bidir_dl_mbps: 0.0,
+            bidir_ul_mbps: 0.0,
+            bidir_dl_points: Vec::new(),
+            bidir_ul_points: Vec::new(),
+            bidir_latency_samples: Vec::new(),
             idle_latency_samples: Vec::new(),
             loaded_dl_latency_samples: Vec::new(),
             loaded_ul_latency_samples: Vec::new(),
@@ -165,8 +185,11 @@ impl Default for UiState {
             // Diagnostic results
             dns_summary: None,
             tls_summary: None,
+            pmtu_summary: None,
+            connection_timing: None,
             ip_comparison: None,
             traceroute_summary: None,
+            path_monitor: None,
             update_status: None,
         }
     }
@@ -298,6 +321,7 @@ impl UiState {
                 p75_ms: Some(p75),
                 max_ms,
                 jitter_ms,
+                trimmed_mean_ms: crate::metrics::trimmed_mean_95(samples),
             }
         } else {
             crate::model::LatencySummary {